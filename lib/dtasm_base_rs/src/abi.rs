@@ -0,0 +1,142 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Versions of the dtasm ABI. A module states the ABI version it was built for
+//! as `major.minor` string in the `dtasm.abi_version` custom section; modules
+//! without this section are treated as version 1.0. Minor versions only add to
+//! the ABI, so a runtime supports all modules of its major version up to its own
+//! minor version; modules may only rely on newer behaviour (`AbiFeature`) if
+//! they state a version supporting it.
+
+use std::fmt;
+
+use crate::errors::DtasmError;
+
+/// Name of the custom section holding the ABI version of a module
+pub const ABI_VERSION_SECTION: &str = "dtasm.abi_version";
+
+/// Contents of the `dtasm.abi_version` section for `AbiVersion::CURRENT`
+pub const ABI_VERSION_SECTION_CONTENTS: &str = "1.1";
+
+/// `ABI_VERSION_SECTION_CONTENTS` as byte array of length `N` (the length of
+/// the string), for embedding it in a custom section
+pub const fn abi_version_section_bytes<const N: usize>() -> [u8; N] {
+    let contents = ABI_VERSION_SECTION_CONTENTS.as_bytes();
+    let mut bytes = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        bytes[i] = contents[i];
+        i += 1;
+    }
+
+    bytes
+}
+
+/// Version of the dtasm ABI
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord)]
+pub struct AbiVersion {
+    pub major: u32,
+    pub minor: u32
+}
+
+/// Behaviour added to the ABI after version 1.0 that modules of older versions
+/// must not rely on
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AbiFeature {
+    /// Log messages are reported through the `dtasm.log` import
+    LogImport
+}
+
+impl AbiVersion {
+    /// Version of modules that do not state their ABI version
+    pub const LEGACY: AbiVersion = AbiVersion { major: 1, minor: 0 };
+
+    /// Version implemented by this crate
+    pub const CURRENT: AbiVersion = AbiVersion { major: 1, minor: 1 };
+
+    /// Parse a version string of the form `major.minor`
+    ///
+    /// * `version` - the version string, e.g. `1.1`
+    pub fn parse(version: &str) -> Result<AbiVersion, DtasmError> {
+        let invalid = || DtasmError::UnsupportedAbiVersion(version.to_string());
+
+        let (major, minor) = version.trim().split_once('.').ok_or_else(invalid)?;
+        Ok(AbiVersion {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?
+        })
+    }
+
+    /// Whether a runtime implementing this version can run modules built for
+    /// the `module` version
+    pub fn supports_module(&self, module: &AbiVersion) -> bool {
+        self.major == module.major && self.minor >= module.minor
+    }
+
+    /// Whether modules built for this version support the feature
+    pub fn supports(&self, feature: AbiFeature) -> bool {
+        *self >= feature.min_version()
+    }
+}
+
+impl Default for AbiVersion {
+    fn default() -> Self {
+        AbiVersion::LEGACY
+    }
+}
+
+impl fmt::Display for AbiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl AbiFeature {
+    /// First ABI version supporting the feature
+    pub fn min_version(&self) -> AbiVersion {
+        match self {
+            AbiFeature::LogImport => AbiVersion { major: 1, minor: 1 }
+        }
+    }
+}
+
+impl fmt::Display for AbiFeature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AbiFeature::LogImport => write!(f, "the `dtasm.log` import")
+        }
+    }
+}
+
+/// Check that a module of the given ABI version may use a feature
+///
+/// * `version` - ABI version stated by the module
+/// * `feature` - feature used by the module
+pub fn check_abi_feature(version: AbiVersion, feature: AbiFeature) -> Result<(), DtasmError> {
+    match version.supports(feature) {
+        true => Ok(()),
+        false => Err(DtasmError::UnsupportedAbiFeature(feature, feature.min_version(), version))
+    }
+}
+
+/// ABI version stated by the contents of a `dtasm.abi_version` section, or the
+/// legacy version if the module has no such section; fails if the runtime does
+/// not support the version
+///
+/// * `section` - contents of the custom section, if any
+pub fn check_abi_version(section: Option<&[u8]>) -> Result<AbiVersion, DtasmError> {
+    let version = match section {
+        Some(bytes) => {
+            let version = std::str::from_utf8(bytes)
+                .map_err(|_| DtasmError::UnsupportedAbiVersion(String::from_utf8_lossy(bytes).into_owned()))?;
+            AbiVersion::parse(version)?
+        },
+        None => AbiVersion::LEGACY
+    };
+
+    if !AbiVersion::CURRENT.supports_module(&version) {
+        return Err(DtasmError::UnsupportedAbiVersion(version.to_string()));
+    }
+
+    Ok(version)
+}
@@ -0,0 +1,185 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Checking of variable values against the `min` and `max` bounds declared in
+//! the model description. Bounds apply to the numeric variable types (and to
+//! each element of numeric arrays); values of other types are not checked.
+//! NaN values of bounded variables are always rejected.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::slice;
+
+use crate::errors::DtasmError;
+use crate::model_description as MD;
+use crate::types::{DtasmVarType, DtasmVarValues};
+
+/// Treatment of values outside the bounds of their variable
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum BoundsPolicy {
+    /// Refuse the values with `DtasmError::VariableOutOfBounds`
+    Reject,
+    /// Replace the values by the nearest bound
+    Clamp,
+    /// Accept the values, but report the violations
+    Warn
+}
+
+impl Default for BoundsPolicy {
+    fn default() -> Self {
+        BoundsPolicy::Reject
+    }
+}
+
+/// Value outside the bounds of its variable
+///
+/// * `id` - id of the variable
+/// * `value` - the offending value
+/// * `min` - lower bound of the variable, if any
+/// * `max` - upper bound of the variable, if any
+/// * `is_nan` - whether the value is NaN, which cannot be compared to the bounds
+#[derive(Debug,Clone,PartialEq)]
+pub struct BoundsViolation {
+    pub id: i32,
+    pub value: String,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    pub is_nan: bool
+}
+
+impl BoundsViolation {
+    pub fn to_error(&self) -> DtasmError {
+        DtasmError::VariableOutOfBounds(self.value.clone(), self.id)
+    }
+}
+
+impl fmt::Display for BoundsViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Value {} of variable id `{}` is outside the bounds [{}, {}]", self.value, self.id,
+            self.min.as_deref().unwrap_or("-inf"), self.max.as_deref().unwrap_or("inf"))
+    }
+}
+
+/// Find all values that are outside the bounds of their variable
+///
+/// * `values` - the values to check
+/// * `var_types` - variable types (including bounds) by id
+pub fn check_bounds(values: &DtasmVarValues, var_types: &HashMap<i32, DtasmVarType>) -> Vec<BoundsViolation> {
+    let mut violations = Vec::new();
+
+    check_values(values.real_values.iter().map(|(id, v)| (id, slice::from_ref(v))), var_types, MD::VariableType::DtasmReal, |b| b.real_val, &mut violations);
+    check_values(values.int_values.iter().map(|(id, v)| (id, slice::from_ref(v))), var_types, MD::VariableType::DtasmInt, |b| b.int_val, &mut violations);
+    check_values(values.int64_values.iter().map(|(id, v)| (id, slice::from_ref(v))), var_types, MD::VariableType::DtasmInt64, |b| b.int64_val, &mut violations);
+    check_values(values.float32_values.iter().map(|(id, v)| (id, slice::from_ref(v))), var_types, MD::VariableType::DtasmFloat32, |b| b.float32_val, &mut violations);
+
+    check_values(values.real_arrays.iter().map(|(id, v)| (id, v.as_slice())), var_types, MD::VariableType::DtasmReal, |b| b.real_val, &mut violations);
+    check_values(values.int_arrays.iter().map(|(id, v)| (id, v.as_slice())), var_types, MD::VariableType::DtasmInt, |b| b.int_val, &mut violations);
+    check_values(values.int64_arrays.iter().map(|(id, v)| (id, v.as_slice())), var_types, MD::VariableType::DtasmInt64, |b| b.int64_val, &mut violations);
+    check_values(values.float32_arrays.iter().map(|(id, v)| (id, v.as_slice())), var_types, MD::VariableType::DtasmFloat32, |b| b.float32_val, &mut violations);
+
+    violations
+}
+
+/// Replace all values outside the bounds of their variable by the nearest bound
+///
+/// * `values` - the values to clamp
+/// * `var_types` - variable types (including bounds) by id
+pub fn clamp_to_bounds(values: &mut DtasmVarValues, var_types: &HashMap<i32, DtasmVarType>) {
+    clamp_values(values.real_values.iter_mut().map(|(id, v)| (id, slice::from_mut(v))), var_types, MD::VariableType::DtasmReal, |b| b.real_val);
+    clamp_values(values.int_values.iter_mut().map(|(id, v)| (id, slice::from_mut(v))), var_types, MD::VariableType::DtasmInt, |b| b.int_val);
+    clamp_values(values.int64_values.iter_mut().map(|(id, v)| (id, slice::from_mut(v))), var_types, MD::VariableType::DtasmInt64, |b| b.int64_val);
+    clamp_values(values.float32_values.iter_mut().map(|(id, v)| (id, slice::from_mut(v))), var_types, MD::VariableType::DtasmFloat32, |b| b.float32_val);
+
+    clamp_values(values.real_arrays.iter_mut().map(|(id, v)| (id, v.as_mut_slice())), var_types, MD::VariableType::DtasmReal, |b| b.real_val);
+    clamp_values(values.int_arrays.iter_mut().map(|(id, v)| (id, v.as_mut_slice())), var_types, MD::VariableType::DtasmInt, |b| b.int_val);
+    clamp_values(values.int64_arrays.iter_mut().map(|(id, v)| (id, v.as_mut_slice())), var_types, MD::VariableType::DtasmInt64, |b| b.int64_val);
+    clamp_values(values.float32_arrays.iter_mut().map(|(id, v)| (id, v.as_mut_slice())), var_types, MD::VariableType::DtasmFloat32, |b| b.float32_val);
+}
+
+/// Apply a bounds policy to the given values
+///
+/// Returns the violations found (values have already been clamped with
+/// `BoundsPolicy::Clamp`) or, with `BoundsPolicy::Reject`, an error for the
+/// first violation. NaN values are rejected with every policy.
+///
+/// * `values` - the values to check
+/// * `var_types` - variable types (including bounds) by id
+/// * `policy` - treatment of values outside the bounds
+pub fn apply_bounds_policy(values: &mut DtasmVarValues, var_types: &HashMap<i32, DtasmVarType>,
+    policy: BoundsPolicy) -> Result<Vec<BoundsViolation>, DtasmError> {
+
+    let violations = check_bounds(values, var_types);
+
+    if let Some(violation) = violations.iter().find(|v| v.is_nan) {
+        return Err(violation.to_error());
+    }
+    if let (BoundsPolicy::Reject, Some(violation)) = (policy, violations.first()) {
+        return Err(violation.to_error());
+    }
+
+    if policy == BoundsPolicy::Clamp && !violations.is_empty() {
+        clamp_to_bounds(values, var_types);
+    }
+
+    Ok(violations)
+}
+
+/// Bounds of the variable, if it is of the given type
+fn bounds<T>(var_type: &DtasmVarType, value_type: MD::VariableType, field: fn(&MD::VariableValue) -> T) -> (Option<T>, Option<T>) {
+    if var_type.value_type != value_type {
+        return (None, None);
+    }
+
+    (var_type.min.as_ref().map(field), var_type.max.as_ref().map(field))
+}
+
+fn check_values<'a, T: 'a + PartialOrd + Copy + ToString>(values: impl Iterator<Item = (&'a i32, &'a [T])>,
+    var_types: &HashMap<i32, DtasmVarType>, value_type: MD::VariableType, field: fn(&MD::VariableValue) -> T,
+    violations: &mut Vec<BoundsViolation>) {
+
+    for (id, vals) in values {
+        let (min, max) = match var_types.get(id) {
+            Some(var_type) => bounds(var_type, value_type, field),
+            None => continue
+        };
+
+        if min.is_none() && max.is_none() {
+            continue;
+        }
+
+        for val in vals {
+            let below = min.map_or(false, |min| *val < min);
+            let above = max.map_or(false, |max| *val > max);
+            let is_nan = val.partial_cmp(val).is_none();
+
+            if below || above || is_nan {
+                violations.push(BoundsViolation {
+                    id: *id,
+                    value: val.to_string(),
+                    min: min.map(|v| v.to_string()),
+                    max: max.map(|v| v.to_string()),
+                    is_nan
+                });
+            }
+        }
+    }
+}
+
+fn clamp_values<'a, T: 'a + PartialOrd + Copy>(values: impl Iterator<Item = (&'a i32, &'a mut [T])>,
+    var_types: &HashMap<i32, DtasmVarType>, value_type: MD::VariableType, field: fn(&MD::VariableValue) -> T) {
+
+    for (id, vals) in values {
+        let (min, max) = match var_types.get(id) {
+            Some(var_type) => bounds(var_type, value_type, field),
+            None => continue
+        };
+
+        for val in vals.iter_mut() {
+            match (min, max) {
+                (Some(min), _) if *val < min => *val = min,
+                (_, Some(max)) if *val > max => *val = max,
+                _ => {}
+            }
+        }
+    }
+}
@@ -26,5 +26,45 @@ pub enum DtasmError {
     #[error("Invalid variable value `{0}` for variable id `{1}`")]
     InvalidVariableValue(String, i32), 
     #[error("Not implemented: `{0}`")]
-    NotImplementedError(String)
-}
\ No newline at end of file
+    NotImplementedError(String),
+    #[error("Invalid model description: `{0}`")]
+    InvalidModelDescription(String),
+    #[error("Value with `{0}` elements does not match dimensions of variable id `{1}`")]
+    VariableDimensionMismatch(usize, i32),
+    #[error("Value `{0}` is outside the bounds of variable id `{1}`")]
+    VariableOutOfBounds(String, i32),
+    #[error("Invalid or unknown unit: `{0}`")]
+    InvalidUnit(String),
+    #[error("Incompatible units `{0}` and `{1}`")]
+    IncompatibleUnits(String, String),
+    #[error("Unsupported dtasm ABI version `{0}` (supported are versions 1.0 to {})", crate::abi::AbiVersion::CURRENT)]
+    UnsupportedAbiVersion(String),
+    #[error("Module uses {0}, which requires dtasm ABI version {1} or later, but states version {2}")]
+    UnsupportedAbiFeature(crate::abi::AbiFeature, crate::abi::AbiVersion, crate::abi::AbiVersion)
+}
+
+impl DtasmError {
+    /// Numeric code of the error as transferred in the `error_code` field of
+    /// dtasm result messages (0 is reserved for "no error")
+    pub fn code(&self) -> i32 {
+        match self {
+            DtasmError::MissingDtasmExport(_) => 1,
+            DtasmError::InvalidCallingOrder => 2,
+            DtasmError::UnknownVariableId(_) => 3,
+            DtasmError::VariableTypeMismatch(_, _) => 4,
+            DtasmError::VariableCausalityMismatch(_, _) => 5,
+            DtasmError::VariableCausalityInvalidForSet(_, _) => 6,
+            DtasmError::VariableInvalidForSet(_) => 7,
+            DtasmError::DtasmInternalError(_) => 8,
+            DtasmError::InvalidVariableValue(_, _) => 9,
+            DtasmError::NotImplementedError(_) => 10,
+            DtasmError::InvalidModelDescription(_) => 11,
+            DtasmError::VariableDimensionMismatch(_, _) => 12,
+            DtasmError::VariableOutOfBounds(_, _) => 13,
+            DtasmError::InvalidUnit(_) => 14,
+            DtasmError::IncompatibleUnits(_, _) => 15,
+            DtasmError::UnsupportedAbiVersion(_) => 16,
+            DtasmError::UnsupportedAbiFeature(_, _, _) => 17
+        }
+    }
+}
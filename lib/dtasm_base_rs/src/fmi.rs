@@ -0,0 +1,505 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Conversion of FMI 2.0 and 3.0 model descriptions (`modelDescription.xml`)
+//! into dtasm model descriptions (enabled with the `fmi` feature), e.g. when
+//! porting the sources of an FMU to dtasm. Value references become variable
+//! ids; causality, variability, value types, units, start values (as
+//! defaults), enumerations, the default experiment and the co-simulation
+//! capabilities are carried over. Everything that cannot be expressed in dtasm
+//! is reported as warning.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::path::Path;
+
+use roxmltree::Node;
+
+use crate::errors::DtasmError;
+use crate::model_description as MD;
+use crate::validation::{Diagnostic, Severity};
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum FmiVersion {
+    Fmi2,
+    Fmi3
+}
+
+/// Capabilities of FMI co-simulation that dtasm modules do not have
+const UNSUPPORTED_CAPABILITIES: [&str; 10] = [
+    "needsExecutionTool", "canRunAsynchronuously", "canGetAndSetFMUstate", "canSerializeFMUstate",
+    "providesDirectionalDerivative", "providesDirectionalDerivatives", "providesAdjointDerivatives",
+    "providesIntermediateUpdate", "hasEventMode", "canReturnEarlyAfterIntermediateUpdate"
+];
+
+/// Variable attributes without counterpart in dtasm
+const UNSUPPORTED_ATTRIBUTES: [&str; 6] = [
+    "reinit", "previous", "clocks", "intermediateUpdate", "canHandleMultipleSetPerTimeInstant", "displayUnit"
+];
+
+/// Convert an FMI 2.0 or 3.0 model description; returns the dtasm model
+/// description together with warnings about everything that could not be
+/// converted
+///
+/// * `xml` - contents of the `modelDescription.xml`
+pub fn convert_fmi_model_description(xml: &str) -> Result<(MD::ModelDescription, Vec<Diagnostic>), DtasmError> {
+    let doc = roxmltree::Document::parse(xml).map_err(|e| DtasmError::InvalidModelDescription(e.to_string()))?;
+    let root = doc.root_element();
+    if !root.has_tag_name("fmiModelDescription") {
+        return Err(DtasmError::InvalidModelDescription("Root element is not `fmiModelDescription`".to_string()));
+    }
+
+    let fmi_version = root.attribute("fmiVersion").unwrap_or_default();
+    let version = match fmi_version.split('.').next() {
+        Some("2") => FmiVersion::Fmi2,
+        Some("3") => FmiVersion::Fmi3,
+        _ => return Err(DtasmError::InvalidModelDescription(format!("Unsupported FMI version `{}`", fmi_version)))
+    };
+
+    let mut diagnostics = Vec::new();
+
+    let model = convert_model_info(root, version, &mut diagnostics);
+    let experiment = child(root, "DefaultExperiment").map(|exp| convert_experiment(exp, &mut diagnostics));
+    let unit_definitions = child(root, "UnitDefinitions")
+        .map(|units| convert_unit_definitions(units, &mut diagnostics))
+        .unwrap_or_default();
+
+    let mut simple_types = HashMap::new();
+    let mut enumeration_types = Vec::new();
+    if let Some(types) = child(root, "TypeDefinitions") {
+        convert_type_definitions(types, version, &mut simple_types, &mut enumeration_types, &mut diagnostics);
+    }
+
+    let variables = match child(root, "ModelVariables") {
+        Some(vars) => convert_variables(vars, version, &simple_types, &mut diagnostics),
+        None => Vec::new()
+    };
+
+    Ok((MD::ModelDescription { model, variables, experiment, enumeration_types, unit_definitions }, diagnostics))
+}
+
+/// Read and convert an FMI 2.0 or 3.0 `modelDescription.xml` file, see
+/// `convert_fmi_model_description`
+pub fn read_fmi_model_description<P: AsRef<Path>>(path: P) -> Result<(MD::ModelDescription, Vec<Diagnostic>), DtasmError> {
+    let xml = std::fs::read_to_string(path)
+        .map_err(|e| DtasmError::InvalidModelDescription(e.to_string()))?;
+
+    convert_fmi_model_description(&xml)
+}
+
+fn warning(variable_id: Option<i32>, message: String) -> Diagnostic {
+    Diagnostic { severity: Severity::Warning, variable_id, message }
+}
+
+fn child<'a>(node: Node<'a, 'a>, name: &str) -> Option<Node<'a, 'a>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn elements<'a>(node: Node<'a, 'a>) -> impl Iterator<Item = Node<'a, 'a>> {
+    node.children().filter(|n| n.is_element())
+}
+
+fn is_true(value: Option<&str>) -> bool {
+    matches!(value, Some("true") | Some("1"))
+}
+
+fn convert_model_info(root: Node, version: FmiVersion, diagnostics: &mut Vec<Diagnostic>) -> MD::ModelInfo {
+    let co_simulation = child(root, "CoSimulation");
+    let flag = |name: &str| is_true(co_simulation.and_then(|cs| cs.attribute(name)));
+
+    match co_simulation {
+        Some(cs) => {
+            for capability in UNSUPPORTED_CAPABILITIES.iter().filter(|c| is_true(cs.attribute(**c))) {
+                diagnostics.push(warning(None, format!("Capability `{}` cannot be expressed in dtasm", capability)));
+            }
+            if cs.attribute("maxOutputDerivativeOrder").map_or(false, |order| order != "0") {
+                diagnostics.push(warning(None, "Output derivatives cannot be expressed in dtasm".to_string()));
+            }
+        },
+        None => diagnostics.push(warning(None,
+            "The FMU does not support co-simulation; its capabilities are not carried over".to_string()))
+    }
+    if child(root, "ModelExchange").is_some() || child(root, "ScheduledExecution").is_some() {
+        diagnostics.push(warning(None, "Only the co-simulation interface of the FMU is carried over".to_string()));
+    }
+
+    let id_attribute = match version {
+        FmiVersion::Fmi2 => "guid",
+        FmiVersion::Fmi3 => "instantiationToken"
+    };
+    let attr = |name: &str| root.attribute(name).unwrap_or_default().to_string();
+
+    MD::ModelInfo {
+        name: attr("modelName"),
+        id: attr(id_attribute),
+        description: attr("description"),
+        generation_tool: attr("generationTool"),
+        generation_date_time: attr("generationDateAndTime"),
+        name_delimiter: ".".to_string(),
+        capabilities: MD::Capabilities {
+            can_handle_variable_step_size: flag("canHandleVariableCommunicationStepSize"),
+            can_reset_step: false,
+            can_interpolate_inputs: flag("canInterpolateInputs")
+        }
+    }
+}
+
+fn convert_experiment(exp: Node, diagnostics: &mut Vec<Diagnostic>) -> MD::ExperimentInfo {
+    let attr = |name: &str| exp.attribute(name).and_then(|v| v.parse::<f64>().ok());
+
+    if let Some(tolerance) = attr("tolerance") {
+        diagnostics.push(warning(None,
+            format!("Default tolerance {} cannot be expressed in dtasm, it needs to be passed to init", tolerance)));
+    }
+
+    MD::ExperimentInfo {
+        time_step_min: 0.0,
+        time_step_max: 0.0,
+        time_step_default: attr("stepSize").unwrap_or(0.0),
+        start_time_default: attr("startTime").unwrap_or(0.0),
+        end_time_default: attr("stopTime").unwrap_or(0.0),
+        time_unit: "s".to_string()
+    }
+}
+
+fn convert_unit_definitions(units: Node, diagnostics: &mut Vec<Diagnostic>) -> Vec<MD::UnitDefinition> {
+    let mut unit_definitions = Vec::new();
+
+    for unit in elements(units).filter(|n| n.has_tag_name("Unit")) {
+        let name = unit.attribute("name").unwrap_or_default().to_string();
+        if child(unit, "DisplayUnit").is_some() {
+            diagnostics.push(warning(None, format!("Display units of unit `{}` cannot be expressed in dtasm", name)));
+        }
+
+        let base_unit = match child(unit, "BaseUnit") {
+            Some(base_unit) => base_unit,
+            None => {
+                diagnostics.push(warning(None, format!("Unit `{}` has no base unit and is not defined", name)));
+                continue;
+            }
+        };
+        let exponent = |symbol: &str| base_unit.attribute(symbol).and_then(|v| v.parse::<i32>().ok()).unwrap_or(0);
+        let value = |name: &str, default: f64| base_unit.attribute(name).and_then(|v| v.parse::<f64>().ok()).unwrap_or(default);
+
+        unit_definitions.push(MD::UnitDefinition {
+            name,
+            base_unit: MD::BaseUnit {
+                kg: exponent("kg"),
+                m: exponent("m"),
+                s: exponent("s"),
+                a: exponent("A"),
+                k: exponent("K"),
+                mol: exponent("mol"),
+                cd: exponent("cd"),
+                rad: exponent("rad"),
+                factor: value("factor", 1.0),
+                offset: value("offset", 0.0)
+            }
+        });
+    }
+
+    unit_definitions
+}
+
+/// Collect simple types (by name, with the element carrying their attributes)
+/// and convert enumeration types
+fn convert_type_definitions<'a>(types: Node<'a, 'a>, version: FmiVersion, simple_types: &mut HashMap<String, Node<'a, 'a>>,
+    enumeration_types: &mut Vec<MD::EnumerationType>, diagnostics: &mut Vec<Diagnostic>) {
+
+    for type_def in elements(types) {
+        let name = type_def.attribute("name").unwrap_or_default().to_string();
+        let type_node = match version {
+            FmiVersion::Fmi2 => match elements(type_def).next() {
+                Some(node) => node,
+                None => continue
+            },
+            FmiVersion::Fmi3 => type_def
+        };
+
+        if type_node.has_tag_name("Enumeration") || type_node.has_tag_name("EnumerationType") {
+            let mut items = Vec::new();
+            for item in elements(type_node).filter(|n| n.has_tag_name("Item")) {
+                let item_name = item.attribute("name").unwrap_or_default();
+                match item.attribute("value").and_then(|v| v.parse::<i32>().ok()) {
+                    Some(value) => items.push(MD::EnumerationItem {
+                        name: item_name.to_string(),
+                        value,
+                        description: item.attribute("description").unwrap_or_default().to_string()
+                    }),
+                    None => diagnostics.push(warning(None,
+                        format!("Value of item `{}` of enumeration `{}` does not fit into a 32 bit integer", item_name, name)))
+                }
+            }
+
+            enumeration_types.push(MD::EnumerationType {
+                name: name.clone(),
+                description: type_def.attribute("description").unwrap_or_default().to_string(),
+                items
+            });
+        }
+
+        simple_types.insert(name, type_node);
+    }
+}
+
+/// Type of a variable element, or `None` if it cannot be expressed in dtasm
+fn variable_type(tag: &str) -> Option<MD::VariableType> {
+    match tag {
+        "Real" | "Float64" => Some(MD::VariableType::DtasmReal),
+        "Float32" => Some(MD::VariableType::DtasmFloat32),
+        "Integer" | "Enumeration" | "Int8" | "UInt8" | "Int16" | "UInt16" | "Int32" => Some(MD::VariableType::DtasmInt),
+        "UInt32" | "Int64" | "UInt64" => Some(MD::VariableType::DtasmInt64),
+        "Boolean" => Some(MD::VariableType::DtasmBool),
+        "String" => Some(MD::VariableType::DtasmString),
+        "Binary" => Some(MD::VariableType::DtasmBinary),
+        _ => None
+    }
+}
+
+/// Element holding the type specific attributes of a variable (the variable
+/// itself for FMI 3.0)
+fn type_element<'a>(var: Node<'a, 'a>, version: FmiVersion) -> Option<Node<'a, 'a>> {
+    match version {
+        FmiVersion::Fmi2 => elements(var).find(|n| variable_type(n.tag_name().name()).is_some()),
+        FmiVersion::Fmi3 => Some(var)
+    }
+}
+
+fn convert_variables(vars: Node, version: FmiVersion, simple_types: &HashMap<String, Node>,
+    diagnostics: &mut Vec<Diagnostic>) -> Vec<MD::ModelVariable> {
+
+    let var_nodes: Vec<Node> = elements(vars).collect();
+    let value_reference = |var: &Node| var.attribute("valueReference").and_then(|vr| vr.parse::<i64>().ok());
+
+    // ids for value references that cannot be used as id
+    let mut next_id = var_nodes.iter()
+        .filter_map(value_reference)
+        .filter(|vr| *vr <= i32::MAX as i64)
+        .max()
+        .map_or(0, |vr| vr as i32 + 1);
+
+    let mut variables = Vec::new();
+    let mut used_ids = HashSet::new();
+    // FMI 2.0 aliases share value reference and base type
+    let mut fmi2_value_refs = HashSet::new();
+    // derivative references (index for FMI 2.0, value reference for FMI 3.0) to be resolved
+    let mut derivatives: Vec<(usize, i64)> = Vec::new();
+    let mut ids_by_index: Vec<Option<i32>> = Vec::new();
+    let mut ids_by_value_ref: HashMap<i64, i32> = HashMap::new();
+
+    for var in var_nodes {
+        ids_by_index.push(None);
+        let name = var.attribute("name").unwrap_or_default().to_string();
+
+        let type_node = match type_element(var, version) {
+            Some(node) => node,
+            None => {
+                diagnostics.push(warning(None, format!("Variable `{}` has no type and is left out", name)));
+                continue;
+            }
+        };
+        let tag = type_node.tag_name().name();
+        let value_type = match variable_type(tag) {
+            Some(value_type) => value_type,
+            None => {
+                diagnostics.push(warning(None, format!("Variable `{}` of type {} cannot be expressed in dtasm and is left out", name, tag)));
+                continue;
+            }
+        };
+
+        let causality = match var.attribute("causality").unwrap_or("local") {
+            "parameter" => MD::CausalityType::Parameter,
+            "input" => MD::CausalityType::Input,
+            "output" => MD::CausalityType::Output,
+            "local" => MD::CausalityType::Local,
+            "calculatedParameter" => {
+                diagnostics.push(warning(None, format!("Calculated parameter `{}` is converted to a local variable", name)));
+                MD::CausalityType::Local
+            },
+            "structuralParameter" => {
+                diagnostics.push(warning(None, format!("Structural parameter `{}` is converted to a parameter", name)));
+                MD::CausalityType::Parameter
+            },
+            causality => {
+                diagnostics.push(warning(None, format!("Variable `{}` of causality `{}` is left out", name, causality)));
+                continue;
+            }
+        };
+
+        let vr = value_reference(&var);
+        if version == FmiVersion::Fmi2 {
+            if let Some(vr) = vr {
+                if !fmi2_value_refs.insert((vr, tag.to_string())) {
+                    diagnostics.push(warning(None, format!("Alias variable `{}` cannot be expressed in dtasm and is left out", name)));
+                    continue;
+                }
+            }
+        }
+
+        let id = match vr {
+            Some(vr) if vr <= i32::MAX as i64 && !used_ids.contains(&(vr as i32)) => vr as i32,
+            _ => {
+                let id = next_id;
+                next_id += 1;
+                diagnostics.push(warning(Some(id), format!("Value reference of variable `{}` cannot be used as id, it gets id {}", name, id)));
+                id
+            }
+        };
+        used_ids.insert(id);
+        if let Some(vr) = vr {
+            ids_by_value_ref.entry(vr).or_insert(id);
+        }
+        *ids_by_index.last_mut().unwrap() = Some(id);
+
+        // attributes of the variable take precedence over those of its declared type
+        let declared_type = type_node.attribute("declaredType").unwrap_or_default();
+        let simple_type = simple_types.get(declared_type);
+        let attr = |attr_name: &str| type_node.attribute(attr_name).or_else(|| simple_type.and_then(|t| t.attribute(attr_name)));
+        let value = |attr_name: &str| attr(attr_name).and_then(|v| parse_value(value_type, first_value(v)));
+
+        let variability = match var.attribute("variability") {
+            Some("constant") => MD::VariabilityType::Constant,
+            Some("fixed") => MD::VariabilityType::Fixed,
+            Some("tunable") => MD::VariabilityType::Tunable,
+            Some("discrete") => MD::VariabilityType::Discrete,
+            Some("continuous") => MD::VariabilityType::Continuous,
+            _ if version == FmiVersion::Fmi3 && value_type != MD::VariableType::DtasmReal
+                && value_type != MD::VariableType::DtasmFloat32 => MD::VariabilityType::Discrete,
+            _ => MD::VariabilityType::Continuous
+        };
+
+        let dimensions = convert_dimensions(var, &name, id, diagnostics);
+        let default = convert_start(type_node, value_type, &name, id, !dimensions.is_empty(), diagnostics);
+
+        if tag == "UInt64" {
+            diagnostics.push(warning(Some(id), format!("Values of the UInt64 variable `{}` above the 64 bit integer range cannot be represented", name)));
+        }
+        for attribute in UNSUPPORTED_ATTRIBUTES.iter().filter(|a| var.attribute(**a).is_some() || type_node.attribute(**a).is_some()) {
+            diagnostics.push(warning(Some(id), format!("Attribute `{}` of variable `{}` cannot be expressed in dtasm", attribute, name)));
+        }
+        if child(var, "Alias").is_some() {
+            diagnostics.push(warning(Some(id), format!("Aliases of variable `{}` cannot be expressed in dtasm", name)));
+        }
+
+        if let Some(derivative) = attr("derivative").and_then(|d| d.parse::<i64>().ok()) {
+            derivatives.push((variables.len(), derivative));
+        }
+
+        let enumeration_type = match tag {
+            "Enumeration" => declared_type.to_string(),
+            _ => String::new()
+        };
+
+        variables.push(MD::ModelVariable {
+            id,
+            name,
+            value_type,
+            description: var.attribute("description").unwrap_or_default().to_string(),
+            unit: match value_type {
+                MD::VariableType::DtasmReal | MD::VariableType::DtasmFloat32 => attr("unit").unwrap_or_default().to_string(),
+                _ => String::new()
+            },
+            causality,
+            derivative_of_id: -1,
+            default,
+            dimensions,
+            enumeration_type,
+            min: value("min"),
+            max: value("max"),
+            nominal: value("nominal"),
+            variability
+        });
+    }
+
+    for (var_index, derivative) in derivatives {
+        let state_id = match version {
+            FmiVersion::Fmi2 => usize::try_from(derivative - 1).ok().and_then(|i| ids_by_index.get(i)).copied().flatten(),
+            FmiVersion::Fmi3 => ids_by_value_ref.get(&derivative).copied()
+        };
+
+        match state_id {
+            Some(id) => variables[var_index].derivative_of_id = id,
+            None => diagnostics.push(warning(Some(variables[var_index].id),
+                format!("Derivative reference {} of variable `{}` cannot be resolved", derivative, variables[var_index].name)))
+        }
+    }
+
+    variables
+}
+
+/// Fixed array dimensions of an FMI 3.0 variable; dimensions given by a
+/// structural parameter cannot be expressed and are taken as 1
+fn convert_dimensions(var: Node, name: &str, id: i32, diagnostics: &mut Vec<Diagnostic>) -> Vec<i32> {
+    elements(var)
+        .filter(|n| n.has_tag_name("Dimension"))
+        .map(|dim| match dim.attribute("start").and_then(|s| s.parse::<i32>().ok()) {
+            Some(size) => size,
+            None => {
+                diagnostics.push(warning(Some(id),
+                    format!("Variable size dimension of variable `{}` cannot be expressed in dtasm, it is taken as 1", name)));
+                1
+            }
+        })
+        .collect()
+}
+
+/// Start value as default; arrays can only have a single default for all elements
+fn convert_start(type_node: Node, value_type: MD::VariableType, name: &str, id: i32, is_array: bool,
+    diagnostics: &mut Vec<Diagnostic>) -> Option<MD::VariableValue> {
+
+    let starts: Vec<&str> = match (type_node.attribute("start"), value_type) {
+        (Some(start), MD::VariableType::DtasmString) => vec![start],
+        (Some(start), _) => start.split_whitespace().collect(),
+        (None, _) => elements(type_node)
+            .filter(|n| n.has_tag_name("Start"))
+            .filter_map(|n| n.attribute("value"))
+            .collect()
+    };
+
+    let start = starts.first()?;
+    if starts.iter().any(|s| s != start) {
+        let kind = if is_array { "array variable" } else { "variable" };
+        diagnostics.push(warning(Some(id),
+            format!("Start values of {} `{}` differ, only the first one is used as default", kind, name)));
+    }
+
+    let default = parse_value(value_type, start);
+    if default.is_none() {
+        diagnostics.push(warning(Some(id), format!("Start value `{}` of variable `{}` cannot be converted", start, name)));
+    }
+
+    default
+}
+
+fn first_value(value: &str) -> &str {
+    value.split_whitespace().next().unwrap_or(value)
+}
+
+fn parse_value(value_type: MD::VariableType, text: &str) -> Option<MD::VariableValue> {
+    let mut value = MD::VariableValue::default();
+    let text = text.trim();
+
+    match value_type {
+        MD::VariableType::DtasmReal => value.real_val = text.parse().ok()?,
+        MD::VariableType::DtasmFloat32 => value.float32_val = text.parse().ok()?,
+        MD::VariableType::DtasmInt => value.int_val = text.parse().ok()?,
+        MD::VariableType::DtasmInt64 => value.int64_val = text.parse().ok()?,
+        MD::VariableType::DtasmBool => value.bool_val = match text {
+            "true" | "1" => true,
+            "false" | "0" => false,
+            _ => return None
+        },
+        MD::VariableType::DtasmString => value.string_val = text.to_string(),
+        MD::VariableType::DtasmBinary => {
+            let hex = text.strip_prefix("0x").unwrap_or(text);
+            if hex.len() % 2 != 0 {
+                return None;
+            }
+            value.binary_val = (0..hex.len()).step_by(2)
+                .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+                .collect::<Option<Vec<u8>>>()?;
+        }
+    }
+
+    Some(value)
+}
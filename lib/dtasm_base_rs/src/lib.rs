@@ -1,7 +1,14 @@
 // Copyright 2021 Siemens AG
 // SPDX-License-Identifier: MIT
 
+pub mod abi;
+pub mod bounds;
 pub mod errors;
+#[cfg(feature = "fmi")]
+pub mod fmi;
 pub mod model_description;
 pub mod model_conversion;
-pub mod types;
\ No newline at end of file
+pub mod types;
+pub mod units;
+pub mod value_conversion;
+pub mod validation;
\ No newline at end of file
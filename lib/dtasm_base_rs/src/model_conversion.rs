@@ -3,6 +3,8 @@
 
 use std::collections::HashMap;
 
+use flatbuffers as FB;
+
 use dtasm_abi::dtasm_generated::dtasm_types as DTT;
 use dtasm_abi::dtasm_generated::dtasm_model_description as DTMD;
 use crate::model_description as MD;
@@ -12,7 +14,9 @@ pub fn convert_model_description(md: &DTMD::ModelDescription) -> MD::ModelDescri
     MD::ModelDescription {
         model: convert_model_info(&md.model()), 
         experiment: convert_experiment(md.experiment()), 
-        variables: convert_variables(&md)
+        variables: convert_variables(&md),
+        enumeration_types: convert_enumeration_types(&md),
+        unit_definitions: convert_unit_definitions(&md)
     }
 }
 
@@ -24,7 +28,7 @@ pub fn convert_model_info(mi: &DTMD::ModelInfo) -> MD::ModelInfo {
         generation_tool: String::from(mi.generation_tool().unwrap_or_default()),
         generation_date_time: String::from(mi.generation_datetime().unwrap_or_default()),
         name_delimiter: String::from(mi.name_delimiter().unwrap_or_default()),
-        capabilities: convert_capabilities(&mi.capabilities().unwrap())
+        capabilities: mi.capabilities().map(|cap| convert_capabilities(&cap)).unwrap_or_default()
     }
 }
 
@@ -32,7 +36,7 @@ pub fn convert_capabilities(cap: &DTMD::Capabilities) -> MD::Capabilities {
     MD::Capabilities {
         can_handle_variable_step_size: cap.can_handle_variable_step_size(),
         can_reset_step: cap.can_reset_step(),
-        can_interpolate_inputs: cap.can_handle_variable_step_size()
+        can_interpolate_inputs: cap.can_interpolate_inputs()
     }
 }
 
@@ -61,7 +65,13 @@ pub fn convert_variables(md: &DTMD::ModelDescription) -> Vec<MD::ModelVariable>
                 description: String::from(var.description().unwrap_or_default()),
                 unit: String::from(var.unit().unwrap_or_default()),
                 derivative_of_id: var.derivative_of_id(),
-                default: convert_variable_value(var.default())
+                default: convert_variable_value(var.default()),
+                dimensions: var.dimensions().map(|dims| dims.iter().collect()).unwrap_or_default(),
+                enumeration_type: String::from(var.enumeration_type().unwrap_or_default()),
+                min: convert_variable_value(var.min()),
+                max: convert_variable_value(var.max()),
+                nominal: convert_variable_value(var.nominal()),
+                variability: convert_variability(var.variability())
             }
         );
     }
@@ -69,6 +79,56 @@ pub fn convert_variables(md: &DTMD::ModelDescription) -> Vec<MD::ModelVariable>
     vars
 }
 
+pub fn convert_enumeration_types(md: &DTMD::ModelDescription) -> Vec<MD::EnumerationType> {
+    let mut enum_types: Vec<MD::EnumerationType> = Vec::new();
+    for enum_type in md.enumeration_types().iter().flat_map(|types| types.iter()) {
+        enum_types.push(
+            MD::EnumerationType {
+                name: String::from(enum_type.name()),
+                description: String::from(enum_type.description().unwrap_or_default()),
+                items: enum_type.items().iter().flat_map(|items| items.iter())
+                    .map(|item| MD::EnumerationItem {
+                        name: String::from(item.name()),
+                        value: item.value(),
+                        description: String::from(item.description().unwrap_or_default())
+                    })
+                    .collect()
+            }
+        );
+    }
+
+    enum_types
+}
+
+pub fn convert_unit_definitions(md: &DTMD::ModelDescription) -> Vec<MD::UnitDefinition> {
+    let mut unit_defs: Vec<MD::UnitDefinition> = Vec::new();
+    for unit_def in md.unit_definitions().iter().flat_map(|defs| defs.iter()) {
+        unit_defs.push(
+            MD::UnitDefinition {
+                name: String::from(unit_def.name()),
+                base_unit: unit_def.base_unit().map(|bu| convert_base_unit(&bu)).unwrap_or_default()
+            }
+        );
+    }
+
+    unit_defs
+}
+
+pub fn convert_base_unit(bu: &DTMD::BaseUnit) -> MD::BaseUnit {
+    MD::BaseUnit {
+        kg: bu.kg(),
+        m: bu.m(),
+        s: bu.s(),
+        a: bu.a(),
+        k: bu.k(),
+        mol: bu.mol(),
+        cd: bu.cd(),
+        rad: bu.rad(),
+        factor: bu.factor(),
+        offset: bu.offset()
+    }
+}
+
 pub fn convert_causality(caus: DTMD::CausalityType) -> MD::CausalityType {
     match caus {
         DTMD::CausalityType::input => MD::CausalityType::Input,
@@ -79,12 +139,26 @@ pub fn convert_causality(caus: DTMD::CausalityType) -> MD::CausalityType {
     }
 }
 
+pub fn convert_variability(var: DTMD::VariabilityType) -> MD::VariabilityType {
+    match var {
+        DTMD::VariabilityType::constant => MD::VariabilityType::Constant,
+        DTMD::VariabilityType::fixed => MD::VariabilityType::Fixed,
+        DTMD::VariabilityType::tunable => MD::VariabilityType::Tunable,
+        DTMD::VariabilityType::discrete => MD::VariabilityType::Discrete,
+        DTMD::VariabilityType::continuous => MD::VariabilityType::Continuous,
+        _ => MD::VariabilityType::Continuous,
+    }
+}
+
 pub fn convert_value_type(val_type: DTT::VariableType) -> MD::VariableType {
     match val_type {
         DTT::VariableType::DtasmReal => MD::VariableType::DtasmReal,
         DTT::VariableType::DtasmInt => MD::VariableType::DtasmInt,
         DTT::VariableType::DtasmBool => MD::VariableType::DtasmBool, 
         DTT::VariableType::DtasmString => MD::VariableType::DtasmString,
+        DTT::VariableType::DtasmBinary => MD::VariableType::DtasmBinary,
+        DTT::VariableType::DtasmInt64 => MD::VariableType::DtasmInt64,
+        DTT::VariableType::DtasmFloat32 => MD::VariableType::DtasmFloat32,
         _ => MD::VariableType::DtasmString,
     }
 }
@@ -94,7 +168,10 @@ pub fn convert_variable_value(value: Option<DTT::VariableValue>) -> Option<MD::V
         real_val: value?.real_val(), 
         int_val: value?.int_val(),
         bool_val: value?.bool_val(), 
-        string_val: String::from(value?.string_val().unwrap_or_default())
+        string_val: String::from(value?.string_val().unwrap_or_default()),
+        binary_val: value?.binary_val().map(|v| v.to_vec()).unwrap_or_default(),
+        int64_val: value?.int64_val(),
+        float32_val: value?.float32_val()
     })
 }
 
@@ -108,7 +185,12 @@ pub fn collect_var_types(md: &MD::ModelDescription) -> HashMap<i32, DtasmVarType
                 name: model_var.name.clone(), 
                 causality: model_var.causality.clone(),
                 value_type: model_var.value_type.clone(),
-                default: model_var.default.clone()
+                default: model_var.default.clone(),
+                dimensions: model_var.dimensions.clone(),
+                enumeration: md.enumeration_type(model_var).cloned(),
+                min: model_var.min.clone(),
+                max: model_var.max.clone(),
+                variability: model_var.variability
             });
     }
 
@@ -124,7 +206,238 @@ pub fn add_var_types(md: &MD::ModelDescription, var_types: &mut HashMap<i32, Dta
                 name: model_var.name.clone(), 
                 causality: model_var.causality.clone(),
                 value_type: model_var.value_type.clone(),
-                default: model_var.default.clone()
+                default: model_var.default.clone(),
+                dimensions: model_var.dimensions.clone(),
+                enumeration: md.enumeration_type(model_var).cloned(),
+                min: model_var.min.clone(),
+                max: model_var.max.clone(),
+                variability: model_var.variability
             });
     }
-}
\ No newline at end of file
+}
+
+/// Serialize a model description into a model description FlatBuffer (including
+/// the `DTAS` file identifier), as returned by the `getModelDescription` export
+pub fn model_description_to_bytes(md: &MD::ModelDescription) -> Vec<u8> {
+    let mut builder = FB::FlatBufferBuilder::new();
+
+    let model = create_model_info(&mut builder, &md.model);
+    let variables = create_variables(&mut builder, &md.variables);
+    let experiment = md.experiment.as_ref().map(|exp| create_experiment(&mut builder, exp));
+    let enumeration_types = match md.enumeration_types.is_empty() {
+        true => None,
+        false => Some(create_enumeration_types(&mut builder, &md.enumeration_types))
+    };
+    let unit_definitions = match md.unit_definitions.is_empty() {
+        true => None,
+        false => Some(create_unit_definitions(&mut builder, &md.unit_definitions))
+    };
+
+    let md_fb = DTMD::ModelDescription::create(&mut builder, &DTMD::ModelDescriptionArgs{
+        model: Some(model),
+        variables: Some(variables),
+        experiment,
+        enumeration_types,
+        unit_definitions
+    });
+    DTMD::finish_model_description_buffer(&mut builder, md_fb);
+
+    builder.finished_data().to_vec()
+}
+
+pub fn create_model_info<'a>(builder: &mut FB::FlatBufferBuilder<'a>, mi: &MD::ModelInfo) -> FB::WIPOffset<DTMD::ModelInfo<'a>> {
+    let name = builder.create_string(&mi.name);
+    let id = builder.create_string(&mi.id);
+    let description = builder.create_string(&mi.description);
+    let generation_tool = builder.create_string(&mi.generation_tool);
+    let generation_datetime = builder.create_string(&mi.generation_date_time);
+    let name_delimiter = builder.create_string(&mi.name_delimiter);
+    let capabilities = create_capabilities(builder, &mi.capabilities);
+
+    DTMD::ModelInfo::create(builder, &DTMD::ModelInfoArgs{
+        name: Some(name),
+        id: Some(id),
+        description: Some(description),
+        generation_tool: Some(generation_tool),
+        generation_datetime: Some(generation_datetime),
+        name_delimiter: Some(name_delimiter),
+        capabilities: Some(capabilities)
+    })
+}
+
+pub fn create_capabilities<'a>(builder: &mut FB::FlatBufferBuilder<'a>, cap: &MD::Capabilities) -> FB::WIPOffset<DTMD::Capabilities<'a>> {
+    DTMD::Capabilities::create(builder, &DTMD::CapabilitiesArgs{
+        can_handle_variable_step_size: cap.can_handle_variable_step_size,
+        can_reset_step: cap.can_reset_step,
+        can_interpolate_inputs: cap.can_interpolate_inputs
+    })
+}
+
+pub fn create_experiment<'a>(builder: &mut FB::FlatBufferBuilder<'a>, exp: &MD::ExperimentInfo) -> FB::WIPOffset<DTMD::ExperimentInfo<'a>> {
+    let time_unit = builder.create_string(&exp.time_unit);
+
+    DTMD::ExperimentInfo::create(builder, &DTMD::ExperimentInfoArgs{
+        timestep_min: exp.time_step_min,
+        timestep_max: exp.time_step_max,
+        timestep_default: exp.time_step_default,
+        starttime_default: exp.start_time_default,
+        endtime_default: exp.end_time_default,
+        time_unit: Some(time_unit)
+    })
+}
+
+/// Create the variables vector; variables are sorted by id since `id` is the
+/// key of the ModelVariable table
+pub fn create_variables<'a>(builder: &mut FB::FlatBufferBuilder<'a>, vars: &Vec<MD::ModelVariable>)
+    -> FB::WIPOffset<FB::Vector<'a, FB::ForwardsUOffset<DTMD::ModelVariable<'a>>>> {
+
+    let mut sorted_vars: Vec<&MD::ModelVariable> = vars.iter().collect();
+    sorted_vars.sort_by(|a,b| a.id.cmp(&b.id));
+
+    let mut var_offs: Vec<FB::WIPOffset<DTMD::ModelVariable>> = Vec::new();
+    for var in sorted_vars {
+        let name = builder.create_string(&var.name);
+        let description = builder.create_string(&var.description);
+        let unit = builder.create_string(&var.unit);
+        let default = var.default.as_ref().map(|value| create_variable_value(builder, value));
+        let min = var.min.as_ref().map(|value| create_variable_value(builder, value));
+        let max = var.max.as_ref().map(|value| create_variable_value(builder, value));
+        let nominal = var.nominal.as_ref().map(|value| create_variable_value(builder, value));
+        let dimensions = match var.is_array() {
+            true => Some(builder.create_vector(&var.dimensions)),
+            false => None
+        };
+        let enumeration_type = match var.enumeration_type.is_empty() {
+            true => None,
+            false => Some(builder.create_string(&var.enumeration_type))
+        };
+
+        var_offs.push(DTMD::ModelVariable::create(builder, &DTMD::ModelVariableArgs{
+            id: var.id,
+            name: Some(name),
+            value_type: create_value_type(var.value_type),
+            description: Some(description),
+            unit: Some(unit),
+            causality: create_causality(var.causality),
+            derivative_of_id: var.derivative_of_id,
+            default,
+            dimensions,
+            enumeration_type,
+            min,
+            max,
+            nominal,
+            variability: create_variability(var.variability)
+        }));
+    }
+
+    builder.create_vector(&var_offs)
+}
+
+pub fn create_enumeration_types<'a>(builder: &mut FB::FlatBufferBuilder<'a>, enum_types: &Vec<MD::EnumerationType>)
+    -> FB::WIPOffset<FB::Vector<'a, FB::ForwardsUOffset<DTMD::EnumerationType<'a>>>> {
+
+    let mut type_offs: Vec<FB::WIPOffset<DTMD::EnumerationType>> = Vec::new();
+    for enum_type in enum_types {
+        let mut item_offs: Vec<FB::WIPOffset<DTMD::EnumerationItem>> = Vec::new();
+        for item in &enum_type.items {
+            let name = builder.create_string(&item.name);
+            let description = builder.create_string(&item.description);
+
+            item_offs.push(DTMD::EnumerationItem::create(builder, &DTMD::EnumerationItemArgs{
+                name: Some(name),
+                value: item.value,
+                description: Some(description)
+            }));
+        }
+
+        let name = builder.create_string(&enum_type.name);
+        let description = builder.create_string(&enum_type.description);
+        let items = builder.create_vector(&item_offs);
+
+        type_offs.push(DTMD::EnumerationType::create(builder, &DTMD::EnumerationTypeArgs{
+            name: Some(name),
+            description: Some(description),
+            items: Some(items)
+        }));
+    }
+
+    builder.create_vector(&type_offs)
+}
+
+pub fn create_unit_definitions<'a>(builder: &mut FB::FlatBufferBuilder<'a>, unit_defs: &Vec<MD::UnitDefinition>)
+    -> FB::WIPOffset<FB::Vector<'a, FB::ForwardsUOffset<DTMD::UnitDefinition<'a>>>> {
+
+    let mut def_offs: Vec<FB::WIPOffset<DTMD::UnitDefinition>> = Vec::new();
+    for unit_def in unit_defs {
+        let name = builder.create_string(&unit_def.name);
+        let bu = &unit_def.base_unit;
+        let base_unit = DTMD::BaseUnit::create(builder, &DTMD::BaseUnitArgs{
+            kg: bu.kg,
+            m: bu.m,
+            s: bu.s,
+            a: bu.a,
+            k: bu.k,
+            mol: bu.mol,
+            cd: bu.cd,
+            rad: bu.rad,
+            factor: bu.factor,
+            offset: bu.offset
+        });
+
+        def_offs.push(DTMD::UnitDefinition::create(builder, &DTMD::UnitDefinitionArgs{
+            name: Some(name),
+            base_unit: Some(base_unit)
+        }));
+    }
+
+    builder.create_vector(&def_offs)
+}
+
+pub fn create_causality(caus: MD::CausalityType) -> DTMD::CausalityType {
+    match caus {
+        MD::CausalityType::Input => DTMD::CausalityType::input,
+        MD::CausalityType::Local => DTMD::CausalityType::local,
+        MD::CausalityType::Parameter => DTMD::CausalityType::parameter,
+        MD::CausalityType::Output => DTMD::CausalityType::output,
+    }
+}
+
+pub fn create_variability(var: MD::VariabilityType) -> DTMD::VariabilityType {
+    match var {
+        MD::VariabilityType::Constant => DTMD::VariabilityType::constant,
+        MD::VariabilityType::Fixed => DTMD::VariabilityType::fixed,
+        MD::VariabilityType::Tunable => DTMD::VariabilityType::tunable,
+        MD::VariabilityType::Discrete => DTMD::VariabilityType::discrete,
+        MD::VariabilityType::Continuous => DTMD::VariabilityType::continuous,
+    }
+}
+
+pub fn create_value_type(val_type: MD::VariableType) -> DTT::VariableType {
+    match val_type {
+        MD::VariableType::DtasmReal => DTT::VariableType::DtasmReal,
+        MD::VariableType::DtasmInt => DTT::VariableType::DtasmInt,
+        MD::VariableType::DtasmBool => DTT::VariableType::DtasmBool,
+        MD::VariableType::DtasmString => DTT::VariableType::DtasmString,
+        MD::VariableType::DtasmBinary => DTT::VariableType::DtasmBinary,
+        MD::VariableType::DtasmInt64 => DTT::VariableType::DtasmInt64,
+        MD::VariableType::DtasmFloat32 => DTT::VariableType::DtasmFloat32,
+    }
+}
+
+pub fn create_variable_value<'a>(builder: &mut FB::FlatBufferBuilder<'a>, value: &MD::VariableValue) -> FB::WIPOffset<DTT::VariableValue<'a>> {
+    let string_val = builder.create_string(&value.string_val);
+    let binary_val = match value.binary_val.is_empty() {
+        true => None,
+        false => Some(builder.create_vector(&value.binary_val))
+    };
+
+    DTT::VariableValue::create(builder, &DTT::VariableValueArgs{
+        real_val: value.real_val,
+        int_val: value.int_val,
+        bool_val: value.bool_val,
+        string_val: Some(string_val),
+        binary_val,
+        int64_val: value.int64_val,
+        float32_val: value.float32_val
+    })
+}
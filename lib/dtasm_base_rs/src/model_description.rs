@@ -1,70 +1,167 @@
 // Copyright 2021 Siemens AG
 // SPDX-License-Identifier: MIT
 
-#[derive(Debug,Clone)]
+//! Model description of a dtasm module. The types can be read from and written
+//! to the JSON representation of the model description FlatBuffer as used by
+//! flatc, e.g. `modelDescription.json` files of module sources.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::DtasmError;
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct ModelDescription {
     pub model: ModelInfo, 
     pub variables: Vec<ModelVariable>,
-    pub experiment: Option<ExperimentInfo>
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<ExperimentInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enumeration_types: Vec<EnumerationType>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unit_definitions: Vec<UnitDefinition>
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct ModelInfo {
     pub name: String, 
+    #[serde(default)]
     pub id: String,
+    #[serde(default)]
     pub description: String, 
+    #[serde(default)]
     pub generation_tool: String,
+    #[serde(default, rename = "generation_datetime")]
     pub generation_date_time: String, 
+    #[serde(default)]
     pub name_delimiter: String, 
+    #[serde(default)]
     pub capabilities: Capabilities
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,Default,PartialEq,Serialize,Deserialize)]
+#[serde(default)]
 pub struct Capabilities {
     pub can_handle_variable_step_size: bool,
     pub can_reset_step: bool,
     pub can_interpolate_inputs: bool
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,Default,PartialEq,Serialize,Deserialize)]
+#[serde(default)]
 pub struct ExperimentInfo {
+    #[serde(rename = "timestep_min")]
     pub time_step_min: f64,
+    #[serde(rename = "timestep_max")]
     pub time_step_max: f64, 
+    #[serde(rename = "timestep_default")]
     pub time_step_default: f64, 
+    #[serde(rename = "starttime_default")]
     pub start_time_default: f64,
+    #[serde(rename = "endtime_default")]
     pub end_time_default: f64, 
     pub time_unit: String
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 pub struct ModelVariable {
     pub id: i32, 
     pub name: String, 
+    #[serde(default)]
     pub value_type: VariableType, 
+    #[serde(default)]
     pub description: String, 
+    #[serde(default)]
     pub unit: String, 
+    #[serde(default)]
     pub causality: CausalityType, 
+    #[serde(default = "no_derivative")]
     pub derivative_of_id: i32, 
-    pub default: Option<VariableValue>
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<VariableValue>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dimensions: Vec<i32>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub enumeration_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<VariableValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<VariableValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nominal: Option<VariableValue>,
+    #[serde(default)]
+    pub variability: VariabilityType
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct EnumerationType {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub items: Vec<EnumerationItem>
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct EnumerationItem {
+    pub name: String,
+    #[serde(default)]
+    pub value: i32,
+    #[serde(default)]
+    pub description: String
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct UnitDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub base_unit: BaseUnit
+}
+
+/// Exponents of the SI base units, with `factor` and `offset` converting values
+/// to SI: `si_value = factor * value + offset`
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(default)]
+pub struct BaseUnit {
+    pub kg: i32,
+    pub m: i32,
+    pub s: i32,
+    pub a: i32,
+    pub k: i32,
+    pub mol: i32,
+    pub cd: i32,
+    pub rad: i32,
+    pub factor: f64,
+    pub offset: f64
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,Default,PartialEq,Serialize,Deserialize)]
+#[serde(default)]
 pub struct VariableValue {
     pub real_val: f64,
     pub int_val: i32,
     pub bool_val: bool,
-    pub string_val: String
+    pub string_val: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub binary_val: Vec<u8>,
+    pub int64_val: i64,
+    pub float32_val: f32
 }
 
-#[derive(Debug,Clone,Eq,PartialEq,Copy)]
+#[derive(Debug,Clone,Eq,PartialEq,Copy,Serialize,Deserialize)]
 pub enum VariableType {
     DtasmReal, 
     DtasmInt, 
     DtasmBool,
     DtasmString, 
+    DtasmBinary,
+    DtasmInt64,
+    DtasmFloat32
 }
 
-#[derive(Debug,Clone,Eq,PartialEq,Copy)]
+#[derive(Debug,Clone,Eq,PartialEq,Copy,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CausalityType {
     Local,
     Parameter,
@@ -72,3 +169,127 @@ pub enum CausalityType {
     Output
 }
 
+/// How the value of a variable may change; parameters are `Fixed` (settable
+/// at initialization only) unless declared `Tunable`, in which case they can
+/// also be set between steps
+#[derive(Debug,Clone,Eq,PartialEq,Copy,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariabilityType {
+    Constant,
+    Fixed,
+    Tunable,
+    Discrete,
+    Continuous
+}
+
+// Defaults as specified in the FlatBuffers schema
+
+impl Default for VariableType {
+    fn default() -> Self {
+        VariableType::DtasmReal
+    }
+}
+
+impl Default for CausalityType {
+    fn default() -> Self {
+        CausalityType::Local
+    }
+}
+
+impl Default for VariabilityType {
+    fn default() -> Self {
+        VariabilityType::Continuous
+    }
+}
+
+impl Default for BaseUnit {
+    fn default() -> Self {
+        BaseUnit { kg: 0, m: 0, s: 0, a: 0, k: 0, mol: 0, cd: 0, rad: 0, factor: 1.0, offset: 0.0 }
+    }
+}
+
+fn no_derivative() -> i32 {
+    -1
+}
+
+impl ModelVariable {
+    /// Whether the variable is array-valued
+    pub fn is_array(&self) -> bool {
+        !self.dimensions.is_empty()
+    }
+
+    /// Number of elements of an array variable (1 for scalar variables)
+    pub fn array_len(&self) -> usize {
+        array_len(&self.dimensions)
+    }
+
+    /// Whether values can be set between steps, i.e. the variable is an input
+    /// or a tunable parameter
+    pub fn is_settable_between_steps(&self) -> bool {
+        is_settable_between_steps(self.causality, self.variability)
+    }
+}
+
+impl EnumerationType {
+    /// Value of the item with the given name
+    pub fn item_value(&self, name: &str) -> Option<i32> {
+        self.items.iter().find(|item| item.name == name).map(|item| item.value)
+    }
+
+    /// Name of the item with the given value
+    pub fn item_name(&self, value: i32) -> Option<&str> {
+        self.items.iter().find(|item| item.value == value).map(|item| item.name.as_str())
+    }
+}
+
+/// Whether variables of the given causality and variability can be set between
+/// steps (inputs and tunable parameters)
+pub fn is_settable_between_steps(causality: CausalityType, variability: VariabilityType) -> bool {
+    match causality {
+        CausalityType::Input => true,
+        CausalityType::Parameter => variability == VariabilityType::Tunable,
+        _ => false
+    }
+}
+
+/// Number of elements of an array with the given dimensions (1 for scalars)
+pub fn array_len(dimensions: &[i32]) -> usize {
+    dimensions.iter().map(|d| (*d).max(0) as usize).product()
+}
+
+impl ModelDescription {
+    /// Parse a model description from its JSON representation
+    pub fn from_json(json: &str) -> Result<ModelDescription, DtasmError> {
+        serde_json::from_str(json)
+            .map_err(|e| DtasmError::InvalidModelDescription(e.to_string()))
+    }
+
+    /// Serialize the model description to (pretty-printed) JSON
+    pub fn to_json(&self) -> Result<String, DtasmError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| DtasmError::InvalidModelDescription(e.to_string()))
+    }
+
+    /// Read a model description from a JSON file
+    pub fn read_json<P: AsRef<Path>>(path: P) -> Result<ModelDescription, DtasmError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| DtasmError::InvalidModelDescription(e.to_string()))?;
+
+        ModelDescription::from_json(&json)
+    }
+
+    /// Write the model description to a JSON file
+    pub fn write_json<P: AsRef<Path>>(&self, path: P) -> Result<(), DtasmError> {
+        std::fs::write(path, self.to_json()?)
+            .map_err(|e| DtasmError::InvalidModelDescription(e.to_string()))
+    }
+
+    /// Enumeration type referenced by the given variable, if any
+    pub fn enumeration_type(&self, variable: &ModelVariable) -> Option<&EnumerationType> {
+        if variable.enumeration_type.is_empty() {
+            return None;
+        }
+
+        self.enumeration_types.iter().find(|t| t.name == variable.enumeration_type)
+    }
+}
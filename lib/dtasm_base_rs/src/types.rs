@@ -10,15 +10,39 @@ pub struct DtasmVarType {
     pub name: String, 
     pub value_type: MD::VariableType,
     pub causality: MD::CausalityType,
-    pub default: Option<MD::VariableValue>
+    pub default: Option<MD::VariableValue>,
+    pub dimensions: Vec<i32>,
+    pub enumeration: Option<MD::EnumerationType>,
+    pub min: Option<MD::VariableValue>,
+    pub max: Option<MD::VariableValue>,
+    pub variability: MD::VariabilityType
 }
 
+impl DtasmVarType {
+    /// Whether values can be set between steps (inputs and tunable parameters)
+    pub fn is_settable_between_steps(&self) -> bool {
+        MD::is_settable_between_steps(self.causality, self.variability)
+    }
+}
+
+/// Values of variables by id. Scalar variables go into `*_values`, array
+/// variables into `*_arrays` with their elements flattened in row-major order.
+/// Binary variables are opaque byte payloads and always scalar.
 #[derive(Debug,Clone)]
 pub struct DtasmVarValues {
     pub real_values: HashMap<i32, f64>,
     pub int_values: HashMap<i32, i32>,
     pub bool_values: HashMap<i32, bool>,
     pub string_values: HashMap<i32, String>,
+    pub real_arrays: HashMap<i32, Vec<f64>>,
+    pub int_arrays: HashMap<i32, Vec<i32>>,
+    pub bool_arrays: HashMap<i32, Vec<bool>>,
+    pub string_arrays: HashMap<i32, Vec<String>>,
+    pub binary_values: HashMap<i32, Vec<u8>>,
+    pub int64_values: HashMap<i32, i64>,
+    pub float32_values: HashMap<i32, f32>,
+    pub int64_arrays: HashMap<i32, Vec<i64>>,
+    pub float32_arrays: HashMap<i32, Vec<f32>>,
 }
 
 impl DtasmVarValues{
@@ -27,9 +51,64 @@ impl DtasmVarValues{
             real_values: HashMap::new(),
             int_values: HashMap::new(),
             bool_values: HashMap::new(),
-            string_values: HashMap::new()
+            string_values: HashMap::new(),
+            real_arrays: HashMap::new(),
+            int_arrays: HashMap::new(),
+            bool_arrays: HashMap::new(),
+            string_arrays: HashMap::new(),
+            binary_values: HashMap::new(),
+            int64_values: HashMap::new(),
+            float32_values: HashMap::new(),
+            int64_arrays: HashMap::new(),
+            float32_arrays: HashMap::new()
         }
     }
+
+    /// Insert a value for a variable from the given default value; array 
+    /// variables receive the default value for each of their elements
+    pub fn insert_default(&mut self, variable: &MD::ModelVariable, default: &MD::VariableValue) {
+        let id = variable.id;
+        let n = variable.array_len();
+
+        match (variable.value_type, variable.is_array()) {
+            (MD::VariableType::DtasmReal, false) => { self.real_values.insert(id, default.real_val); },
+            (MD::VariableType::DtasmInt, false) => { self.int_values.insert(id, default.int_val); },
+            (MD::VariableType::DtasmBool, false) => { self.bool_values.insert(id, default.bool_val); },
+            (MD::VariableType::DtasmString, false) => { self.string_values.insert(id, default.string_val.clone()); },
+            (MD::VariableType::DtasmReal, true) => { self.real_arrays.insert(id, vec![default.real_val; n]); },
+            (MD::VariableType::DtasmInt, true) => { self.int_arrays.insert(id, vec![default.int_val; n]); },
+            (MD::VariableType::DtasmBool, true) => { self.bool_arrays.insert(id, vec![default.bool_val; n]); },
+            (MD::VariableType::DtasmString, true) => { self.string_arrays.insert(id, vec![default.string_val.clone(); n]); },
+            (MD::VariableType::DtasmBinary, _) => { self.binary_values.insert(id, default.binary_val.clone()); },
+            (MD::VariableType::DtasmInt64, false) => { self.int64_values.insert(id, default.int64_val); },
+            (MD::VariableType::DtasmFloat32, false) => { self.float32_values.insert(id, default.float32_val); },
+            (MD::VariableType::DtasmInt64, true) => { self.int64_arrays.insert(id, vec![default.int64_val; n]); },
+            (MD::VariableType::DtasmFloat32, true) => { self.float32_arrays.insert(id, vec![default.float32_val; n]); },
+        };
+    }
+
+    /// Ids of all variables that have a value, sorted
+    pub fn ids(&self) -> Vec<i32> {
+        let mut ids: Vec<i32> = self.real_values.keys()
+            .chain(self.int_values.keys())
+            .chain(self.bool_values.keys())
+            .chain(self.string_values.keys())
+            .chain(self.real_arrays.keys())
+            .chain(self.int_arrays.keys())
+            .chain(self.bool_arrays.keys())
+            .chain(self.string_arrays.keys())
+            .chain(self.binary_values.keys())
+            .chain(self.int64_values.keys())
+            .chain(self.float32_values.keys())
+            .chain(self.int64_arrays.keys())
+            .chain(self.float32_arrays.keys())
+            .copied()
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        ids
+    }
 }
 
 #[derive(Debug,Clone)]
@@ -81,20 +160,49 @@ impl Into<DTT::Status> for Status {
     }
 }
 
+/// Response from a call that only reports a status (`init`, `setValues`, 
+/// `resetStep`).
+///
+/// * `status` - Status reported by the module.
+/// * `message` - Diagnostic message of the module, if any.
+/// * `error_code` - Code of the `DtasmError` that caused an error status, if any.
+#[derive(Debug,Clone)]
+pub struct StatusResponse {
+    pub status: Status, 
+    pub message: Option<String>,
+    pub error_code: Option<i32>
+}
+
 /// Response from a call to retrieve values of variables. 
 ///
 /// * `status` - Status after the last time step computation.
 /// * `current_time` - Current internal time of the instance.
 /// * `values` - Current values of the requested variables.
+/// * `message` - Diagnostic message of the module, if any.
+/// * `error_code` - Code of the `DtasmError` that caused an error status, if any.
 #[derive(Debug,Clone)]
 pub struct GetValuesResponse {
     pub status: Status, 
     pub current_time: f64,
-    pub values: DtasmVarValues
+    pub values: DtasmVarValues,
+    pub message: Option<String>,
+    pub error_code: Option<i32>
 }
 
+/// Response from a call to advance the simulation by one step.
+///
+/// * `status` - Status reported by the module.
+/// * `updated_time` - Time reached by the step.
+/// * `message` - Diagnostic message of the module, if any.
+/// * `error_code` - Code of the `DtasmError` that caused an error status, if any.
+/// * `event_occurred` - Whether the step ended early (at `updated_time`) because of an event.
+/// * `next_time_event` - Time of the next time event known to the module, if any.
 #[derive(Debug,Clone)]
 pub struct DoStepResponse {
     pub status: Status, 
-    pub updated_time: f64
+    pub updated_time: f64,
+    pub message: Option<String>,
+    pub error_code: Option<i32>,
+    pub event_occurred: bool,
+    pub next_time_event: Option<f64>
 }
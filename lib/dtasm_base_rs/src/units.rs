@@ -0,0 +1,270 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Physical units of variables. A unit is given by the exponents of the SI base
+//! units together with a factor and an offset converting values to SI, i.e.
+//! `si_value = factor * value + offset`. Unit strings are resolved by a
+//! `UnitRegistry`, which knows a set of common units and the unit definitions of
+//! a model description, and which also accepts compound expressions like
+//! `kg.m/s2`, `km/h` or `rad/s^2`.
+
+use std::collections::HashMap;
+
+use crate::errors::DtasmError;
+use crate::model_description as MD;
+
+/// Symbols of the SI base units in the order of `Unit::exponents` (including
+/// rad to distinguish angles from dimensionless quantities)
+pub const BASE_UNITS: [&str; 8] = ["kg", "m", "s", "A", "K", "mol", "cd", "rad"];
+
+/// SI prefixes, `da` first so it is not mistaken for `d`
+const PREFIXES: [(&str, f64); 21] = [
+    ("da", 1e1), ("Y", 1e24), ("Z", 1e21), ("E", 1e18), ("P", 1e15), ("T", 1e12),
+    ("G", 1e9), ("M", 1e6), ("k", 1e3), ("h", 1e2), ("d", 1e-1), ("c", 1e-2),
+    ("m", 1e-3), ("u", 1e-6), ("\u{b5}", 1e-6), ("\u{3bc}", 1e-6), ("n", 1e-9),
+    ("p", 1e-12), ("f", 1e-15), ("a", 1e-18), ("y", 1e-24)
+];
+
+/// Physical unit
+///
+/// * `exponents` - exponents of the SI base units, see `BASE_UNITS`
+/// * `factor` - factor converting values to SI
+/// * `offset` - offset converting values to SI (e.g. 273.15 for degC)
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Unit {
+    pub exponents: [i32; 8],
+    pub factor: f64,
+    pub offset: f64
+}
+
+/// Affine conversion `converted = factor * value + offset` between two
+/// compatible units
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct UnitConversion {
+    pub factor: f64,
+    pub offset: f64
+}
+
+impl Unit {
+    /// Dimensionless unit
+    pub fn one() -> Unit {
+        Unit { exponents: [0; 8], factor: 1.0, offset: 0.0 }
+    }
+
+    /// Whether values of both units describe the same physical quantity
+    pub fn is_compatible(&self, other: &Unit) -> bool {
+        self.exponents == other.exponents
+    }
+
+    /// Conversion of values in this unit into values in the `target` unit, if
+    /// the units are compatible
+    pub fn conversion_to(&self, target: &Unit) -> Option<UnitConversion> {
+        if !self.is_compatible(target) {
+            return None;
+        }
+
+        Some(UnitConversion {
+            factor: self.factor / target.factor,
+            offset: (self.offset - target.offset) / target.factor
+        })
+    }
+
+    fn si(factor: f64, exponents: [i32; 8]) -> Unit {
+        Unit { exponents, factor, offset: 0.0 }
+    }
+
+    fn scaled(&self, factor: f64) -> Unit {
+        Unit { exponents: self.exponents, factor: self.factor * factor, offset: self.offset * factor }
+    }
+
+    /// Power of the unit; offsets only apply to a unit on its own and are dropped
+    fn powi(&self, exponent: i32) -> Unit {
+        let mut exponents = self.exponents;
+        exponents.iter_mut().for_each(|e| *e *= exponent);
+
+        Unit { exponents, factor: self.factor.powi(exponent), offset: 0.0 }
+    }
+
+    fn mul(&self, other: &Unit) -> Unit {
+        let mut exponents = self.exponents;
+        exponents.iter_mut().zip(other.exponents.iter()).for_each(|(e, o)| *e += o);
+
+        Unit { exponents, factor: self.factor * other.factor, offset: 0.0 }
+    }
+}
+
+impl From<&MD::BaseUnit> for Unit {
+    fn from(bu: &MD::BaseUnit) -> Self {
+        Unit {
+            exponents: [bu.kg, bu.m, bu.s, bu.a, bu.k, bu.mol, bu.cd, bu.rad],
+            factor: bu.factor,
+            offset: bu.offset
+        }
+    }
+}
+
+impl UnitConversion {
+    pub fn identity() -> UnitConversion {
+        UnitConversion { factor: 1.0, offset: 0.0 }
+    }
+
+    /// Whether the conversion leaves values unchanged (up to rounding)
+    pub fn is_identity(&self) -> bool {
+        (self.factor - 1.0).abs() <= 4.0 * f64::EPSILON && self.offset.abs() <= 4.0 * f64::EPSILON
+    }
+
+    pub fn apply(&self, value: f64) -> f64 {
+        self.factor * value + self.offset
+    }
+}
+
+/// Units by name, used to resolve unit strings of variables
+#[derive(Debug,Clone)]
+pub struct UnitRegistry {
+    units: HashMap<String, Unit>
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        UnitRegistry::new()
+    }
+}
+
+impl UnitRegistry {
+    /// Registry of common units: the SI base units and SI derived units as well
+    /// as some others like `min`, `h`, `bar`, `degC` or `rpm`
+    pub fn new() -> UnitRegistry {
+        let pi = std::f64::consts::PI;
+        let common = [
+            ("1", Unit::one()),
+            ("kg", Unit::si(1.0, [1, 0, 0, 0, 0, 0, 0, 0])),
+            ("g", Unit::si(1e-3, [1, 0, 0, 0, 0, 0, 0, 0])),
+            ("m", Unit::si(1.0, [0, 1, 0, 0, 0, 0, 0, 0])),
+            ("s", Unit::si(1.0, [0, 0, 1, 0, 0, 0, 0, 0])),
+            ("A", Unit::si(1.0, [0, 0, 0, 1, 0, 0, 0, 0])),
+            ("K", Unit::si(1.0, [0, 0, 0, 0, 1, 0, 0, 0])),
+            ("mol", Unit::si(1.0, [0, 0, 0, 0, 0, 1, 0, 0])),
+            ("cd", Unit::si(1.0, [0, 0, 0, 0, 0, 0, 1, 0])),
+            ("rad", Unit::si(1.0, [0, 0, 0, 0, 0, 0, 0, 1])),
+            ("sr", Unit::si(1.0, [0, 0, 0, 0, 0, 0, 0, 2])),
+            ("Hz", Unit::si(1.0, [0, 0, -1, 0, 0, 0, 0, 0])),
+            ("N", Unit::si(1.0, [1, 1, -2, 0, 0, 0, 0, 0])),
+            ("Pa", Unit::si(1.0, [1, -1, -2, 0, 0, 0, 0, 0])),
+            ("J", Unit::si(1.0, [1, 2, -2, 0, 0, 0, 0, 0])),
+            ("W", Unit::si(1.0, [1, 2, -3, 0, 0, 0, 0, 0])),
+            ("C", Unit::si(1.0, [0, 0, 1, 1, 0, 0, 0, 0])),
+            ("V", Unit::si(1.0, [1, 2, -3, -1, 0, 0, 0, 0])),
+            ("F", Unit::si(1.0, [-1, -2, 4, 2, 0, 0, 0, 0])),
+            ("Ohm", Unit::si(1.0, [1, 2, -3, -2, 0, 0, 0, 0])),
+            ("S", Unit::si(1.0, [-1, -2, 3, 2, 0, 0, 0, 0])),
+            ("Wb", Unit::si(1.0, [1, 2, -2, -1, 0, 0, 0, 0])),
+            ("T", Unit::si(1.0, [1, 0, -2, -1, 0, 0, 0, 0])),
+            ("H", Unit::si(1.0, [1, 2, -2, -2, 0, 0, 0, 0])),
+            ("min", Unit::si(60.0, [0, 0, 1, 0, 0, 0, 0, 0])),
+            ("h", Unit::si(3600.0, [0, 0, 1, 0, 0, 0, 0, 0])),
+            ("d", Unit::si(86400.0, [0, 0, 1, 0, 0, 0, 0, 0])),
+            ("l", Unit::si(1e-3, [0, 3, 0, 0, 0, 0, 0, 0])),
+            ("L", Unit::si(1e-3, [0, 3, 0, 0, 0, 0, 0, 0])),
+            ("bar", Unit::si(1e5, [1, -1, -2, 0, 0, 0, 0, 0])),
+            ("Wh", Unit::si(3600.0, [1, 2, -2, 0, 0, 0, 0, 0])),
+            ("deg", Unit::si(pi / 180.0, [0, 0, 0, 0, 0, 0, 0, 1])),
+            ("rpm", Unit::si(2.0 * pi / 60.0, [0, 0, -1, 0, 0, 0, 0, 1])),
+            ("%", Unit::si(1e-2, [0; 8])),
+            ("degC", Unit { exponents: [0, 0, 0, 0, 1, 0, 0, 0], factor: 1.0, offset: 273.15 }),
+            ("degF", Unit { exponents: [0, 0, 0, 0, 1, 0, 0, 0], factor: 5.0 / 9.0, offset: 273.15 - 32.0 * 5.0 / 9.0 })
+        ];
+
+        UnitRegistry {
+            units: common.iter().map(|(name, unit)| (name.to_string(), *unit)).collect()
+        }
+    }
+
+    /// Registry of common units and the unit definitions of the model
+    /// description (which take precedence)
+    pub fn with_model_description(md: &MD::ModelDescription) -> UnitRegistry {
+        let mut registry = UnitRegistry::new();
+        for unit_def in &md.unit_definitions {
+            registry.insert(&unit_def.name, Unit::from(&unit_def.base_unit));
+        }
+
+        registry
+    }
+
+    /// Add or replace a unit
+    pub fn insert(&mut self, name: &str, unit: Unit) {
+        self.units.insert(name.to_string(), unit);
+    }
+
+    /// Resolve a unit string: either the name of a known unit or an expression
+    /// of (SI-prefixed) known units, combined by `.` or `*` and `/`, each with an
+    /// optional integer exponent (`m2`, `s-1` or `s^2`). Offsets (as of `degC`)
+    /// only apply to known units on their own.
+    ///
+    /// * `unit` - the unit string, e.g. `kg.m/s2`
+    pub fn resolve(&self, unit: &str) -> Result<Unit, DtasmError> {
+        let unit = unit.trim();
+        let invalid = || DtasmError::InvalidUnit(unit.to_string());
+
+        if let Some(known) = self.units.get(unit) {
+            return Ok(*known);
+        }
+
+        let mut terms: Vec<(Unit, i32)> = Vec::new();
+        let mut divide = false;
+        let mut term_start = 0;
+        for (pos, c) in unit.char_indices().chain(std::iter::once((unit.len(), '.'))) {
+            if c != '.' && c != '*' && c != '/' {
+                continue;
+            }
+
+            let term = &unit[term_start..pos];
+            let (term_unit, exponent) = match term.parse::<u32>() {
+                Ok(number) => (Unit::one().scaled(number as f64), 1),
+                Err(_) => {
+                    let (symbol, exponent) = split_exponent(term).ok_or_else(invalid)?;
+                    (self.resolve_symbol(symbol).ok_or_else(invalid)?, exponent)
+                }
+            };
+            terms.push((term_unit, if divide { -exponent } else { exponent }));
+
+            divide = c == '/';
+            term_start = pos + c.len_utf8();
+        }
+
+        Ok(terms.iter().fold(Unit::one(), |result, (term_unit, exponent)| result.mul(&term_unit.powi(*exponent))))
+    }
+
+    /// Known unit, optionally preceded by an SI prefix
+    fn resolve_symbol(&self, symbol: &str) -> Option<Unit> {
+        if let Some(known) = self.units.get(symbol) {
+            return Some(*known);
+        }
+
+        PREFIXES.iter()
+            .filter_map(|(prefix, factor)| Some((self.units.get(symbol.strip_prefix(prefix)?)?, factor)))
+            .find(|(known, _)| known.offset == 0.0)
+            .map(|(known, factor)| known.scaled(*factor))
+    }
+}
+
+/// Split a term like `m2`, `s-1` or `s^2` into symbol and exponent
+fn split_exponent(term: &str) -> Option<(&str, i32)> {
+    let digits_start = term.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (mut symbol, digits) = term.split_at(digits_start);
+
+    let mut exponent = 1;
+    if !digits.is_empty() {
+        exponent = digits.parse().ok()?;
+        if let Some(rest) = symbol.strip_suffix('-') {
+            symbol = rest;
+            exponent = -exponent;
+        }
+        symbol = symbol.strip_suffix('^').unwrap_or(symbol);
+    }
+
+    if symbol.is_empty() {
+        return None;
+    }
+
+    Some((symbol, exponent))
+}
@@ -0,0 +1,359 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Semantic validation of model descriptions beyond what the FlatBuffers schema
+//! can express, e.g. uniqueness of variable ids and names or consistency of
+//! default values and experiment settings.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::model_description as MD;
+use crate::units::UnitRegistry;
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum Severity {
+    Warning,
+    Error
+}
+
+/// Finding of the model description validation
+///
+/// * `severity` - whether the model description is invalid or merely questionable
+/// * `variable_id` - id of the affected variable, if any
+/// * `message` - description of the finding
+#[derive(Debug,Clone,PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub variable_id: Option<i32>,
+    pub message: String
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error"
+        };
+
+        match self.variable_id {
+            Some(id) => write!(f, "{} (variable id {}): {}", severity, id, self.message),
+            None => write!(f, "{}: {}", severity, self.message)
+        }
+    }
+}
+
+fn error(variable_id: Option<i32>, message: String) -> Diagnostic {
+    Diagnostic { severity: Severity::Error, variable_id, message }
+}
+
+fn warning(variable_id: Option<i32>, message: String) -> Diagnostic {
+    Diagnostic { severity: Severity::Warning, variable_id, message }
+}
+
+/// Check a model description for semantic consistency
+///
+/// * `md` - the model description to check
+pub fn validate(md: &MD::ModelDescription) -> Vec<Diagnostic> {
+    validate_with(md, false)
+}
+
+/// Like `validate`, but report findings that make a module hard to use 
+/// correctly, e.g. inputs without default value, as errors
+///
+/// * `md` - the model description to check
+pub fn validate_strict(md: &MD::ModelDescription) -> Vec<Diagnostic> {
+    validate_with(md, true)
+}
+
+fn validate_with(md: &MD::ModelDescription, strict: bool) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    check_ids(md, &mut diagnostics);
+    check_names(md, &mut diagnostics);
+    check_dimensions(md, &mut diagnostics);
+    check_derivatives(md, &mut diagnostics);
+    check_defaults(md, strict, &mut diagnostics);
+    check_bounds(md, &mut diagnostics);
+    check_enumerations(md, &mut diagnostics);
+    check_units(md, &mut diagnostics);
+    check_variability(md, &mut diagnostics);
+    if let Some(exp) = &md.experiment {
+        check_experiment(exp, &mut diagnostics);
+    }
+
+    diagnostics
+}
+
+/// Whether any of the diagnostics is an error
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+fn check_ids(md: &MD::ModelDescription, diagnostics: &mut Vec<Diagnostic>) {
+    let mut ids = HashSet::new();
+
+    for var in &md.variables {
+        if !ids.insert(var.id) {
+            diagnostics.push(error(Some(var.id), format!("Variable id is not unique (variable {})", var.name)));
+        }
+    }
+}
+
+fn check_names(md: &MD::ModelDescription, diagnostics: &mut Vec<Diagnostic>) {
+    let delimiter = &md.model.name_delimiter;
+    let mut names = HashSet::new();
+
+    for var in &md.variables {
+        if var.name.is_empty() {
+            diagnostics.push(error(Some(var.id), "Variable name is empty".to_string()));
+            continue;
+        }
+
+        if !names.insert(var.name.as_str()) {
+            diagnostics.push(error(Some(var.id), format!("Variable name `{}` is not unique", var.name)));
+        }
+
+        if var.name.trim() != var.name {
+            diagnostics.push(warning(Some(var.id),
+                format!("Variable name `{}` has leading or trailing whitespace", var.name)));
+        }
+
+        if !delimiter.is_empty() && var.name.split(delimiter.as_str()).any(|part| part.is_empty()) {
+            diagnostics.push(error(Some(var.id),
+                format!("Variable name `{}` contains an empty part with respect to name delimiter `{}`", var.name, delimiter)));
+        }
+    }
+}
+
+fn check_dimensions(md: &MD::ModelDescription, diagnostics: &mut Vec<Diagnostic>) {
+    for var in md.variables.iter().filter(|v| v.dimensions.iter().any(|d| *d <= 0)) {
+        diagnostics.push(error(Some(var.id),
+            format!("Dimensions {:?} of variable {} must be positive", var.dimensions, var.name)));
+    }
+
+    for var in md.variables.iter().filter(|v| v.value_type == MD::VariableType::DtasmBinary && v.is_array()) {
+        diagnostics.push(error(Some(var.id),
+            format!("Binary variable {} must not have dimensions", var.name)));
+    }
+}
+
+fn check_derivatives(md: &MD::ModelDescription, diagnostics: &mut Vec<Diagnostic>) {
+    let vars: HashMap<i32, &MD::ModelVariable> = md.variables.iter()
+        .map(|v| (v.id, v))
+        .collect();
+
+    for var in md.variables.iter().filter(|v| v.derivative_of_id != -1) {
+        if var.value_type != MD::VariableType::DtasmReal {
+            diagnostics.push(error(Some(var.id),
+                format!("Derivative variable {} is of type {:?} instead of DtasmReal", var.name, var.value_type)));
+        }
+
+        match vars.get(&var.derivative_of_id) {
+            None => diagnostics.push(error(Some(var.id),
+                format!("derivative_of_id `{}` of variable {} does not refer to an existing variable", var.derivative_of_id, var.name))),
+            Some(state) if state.value_type != MD::VariableType::DtasmReal => diagnostics.push(error(Some(var.id),
+                format!("derivative_of_id `{}` of variable {} refers to a variable of type {:?}", var.derivative_of_id, var.name, state.value_type))),
+            Some(state) if state.dimensions != var.dimensions => diagnostics.push(error(Some(var.id),
+                format!("Dimensions {:?} of derivative variable {} differ from dimensions {:?} of variable id `{}`",
+                    var.dimensions, var.name, state.dimensions, var.derivative_of_id))),
+            Some(_) => {}
+        }
+    }
+}
+
+fn check_defaults(md: &MD::ModelDescription, strict: bool, diagnostics: &mut Vec<Diagnostic>) {
+    for var in &md.variables {
+        let default = match &var.default {
+            Some(default) => default,
+            None => {
+                if var.causality == MD::CausalityType::Input {
+                    let message = format!("Input variable {} has no default value", var.name);
+                    diagnostics.push(if strict { error(Some(var.id), message) } else { warning(Some(var.id), message) });
+                }
+                continue;
+            }
+        };
+
+        let set_fields = wrong_type_fields(default, var.value_type);
+        if !set_fields.is_empty() {
+            diagnostics.push(error(Some(var.id),
+                format!("Default value of variable {} of type {:?} sets {}", var.name, var.value_type, set_fields.join(", "))));
+        }
+    }
+}
+
+fn check_bounds(md: &MD::ModelDescription, diagnostics: &mut Vec<Diagnostic>) {
+    for var in md.variables.iter().filter(|v| v.min.is_some() || v.max.is_some() || v.nominal.is_some()) {
+        let numeric = matches!(var.value_type, MD::VariableType::DtasmReal | MD::VariableType::DtasmFloat32 
+            | MD::VariableType::DtasmInt | MD::VariableType::DtasmInt64);
+        if !numeric {
+            diagnostics.push(error(Some(var.id),
+                format!("Bounds or nominal value given for variable {} of non-numeric type {:?}", var.name, var.value_type)));
+            continue;
+        }
+
+        for (attr, value) in [("Min", &var.min), ("Max", &var.max), ("Nominal", &var.nominal)] {
+            let set_fields = value.as_ref().map(|v| wrong_type_fields(v, var.value_type)).unwrap_or_default();
+            if !set_fields.is_empty() {
+                diagnostics.push(error(Some(var.id),
+                    format!("{} value of variable {} of type {:?} sets {}", attr, var.name, var.value_type, set_fields.join(", "))));
+            }
+        }
+
+        if let (Some(min), Some(max)) = (&var.min, &var.max) {
+            if compare(min, max, var.value_type) == Some(Ordering::Greater) {
+                diagnostics.push(error(Some(var.id), format!("Min value of variable {} exceeds its max value", var.name)));
+            }
+        }
+
+        if let Some(default) = &var.default {
+            let below = var.min.as_ref().map_or(false, |min| compare(default, min, var.value_type) == Some(Ordering::Less));
+            let above = var.max.as_ref().map_or(false, |max| compare(default, max, var.value_type) == Some(Ordering::Greater));
+            if below || above {
+                diagnostics.push(error(Some(var.id), format!("Default value of variable {} is outside its bounds", var.name)));
+            }
+        }
+    }
+}
+
+fn check_enumerations(md: &MD::ModelDescription, diagnostics: &mut Vec<Diagnostic>) {
+    let mut type_names = HashSet::new();
+
+    for enum_type in &md.enumeration_types {
+        if !type_names.insert(enum_type.name.as_str()) {
+            diagnostics.push(error(None, format!("Enumeration type name `{}` is not unique", enum_type.name)));
+        }
+
+        if enum_type.items.is_empty() {
+            diagnostics.push(error(None, format!("Enumeration type `{}` declares no items", enum_type.name)));
+        }
+
+        let mut item_names = HashSet::new();
+        let mut item_values = HashSet::new();
+        for item in &enum_type.items {
+            if !item_names.insert(item.name.as_str()) {
+                diagnostics.push(error(None,
+                    format!("Item name `{}` of enumeration type `{}` is not unique", item.name, enum_type.name)));
+            }
+            if !item_values.insert(item.value) {
+                diagnostics.push(error(None,
+                    format!("Item value {} of enumeration type `{}` is not unique", item.value, enum_type.name)));
+            }
+        }
+    }
+
+    for var in md.variables.iter().filter(|v| !v.enumeration_type.is_empty()) {
+        if var.value_type != MD::VariableType::DtasmInt {
+            diagnostics.push(error(Some(var.id),
+                format!("Enumeration variable {} is of type {:?} instead of DtasmInt", var.name, var.value_type)));
+        }
+
+        match md.enumeration_type(var) {
+            None => diagnostics.push(error(Some(var.id),
+                format!("Enumeration type `{}` of variable {} is not declared", var.enumeration_type, var.name))),
+            Some(enum_type) => {
+                if let Some(default) = &var.default {
+                    if enum_type.item_name(default.int_val).is_none() {
+                        diagnostics.push(error(Some(var.id),
+                            format!("Default value {} of variable {} is not an item of enumeration type `{}`",
+                                default.int_val, var.name, enum_type.name)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn check_units(md: &MD::ModelDescription, diagnostics: &mut Vec<Diagnostic>) {
+    let mut unit_names = HashSet::new();
+
+    for unit_def in &md.unit_definitions {
+        if !unit_names.insert(unit_def.name.as_str()) {
+            diagnostics.push(error(None, format!("Unit definition name `{}` is not unique", unit_def.name)));
+        }
+
+        if unit_def.base_unit.factor == 0.0 {
+            diagnostics.push(error(None, format!("Factor of unit definition `{}` must not be zero", unit_def.name)));
+        }
+    }
+
+    let registry = UnitRegistry::with_model_description(md);
+    for var in md.variables.iter().filter(|v| !v.unit.is_empty()) {
+        if registry.resolve(&var.unit).is_err() {
+            diagnostics.push(warning(Some(var.id),
+                format!("Unit `{}` of variable {} is neither defined nor a known unit", var.unit, var.name)));
+        }
+    }
+}
+
+fn check_variability(md: &MD::ModelDescription, diagnostics: &mut Vec<Diagnostic>) {
+    for var in &md.variables {
+        match (var.variability, var.causality) {
+            (MD::VariabilityType::Tunable, MD::CausalityType::Parameter) => {},
+            (MD::VariabilityType::Tunable, causality) => diagnostics.push(error(Some(var.id),
+                format!("Variable {} with causality {:?} cannot be tunable (parameters only)", var.name, causality))),
+            (MD::VariabilityType::Constant, MD::CausalityType::Input) | (MD::VariabilityType::Constant, MD::CausalityType::Parameter) =>
+                diagnostics.push(error(Some(var.id),
+                    format!("Variable {} with causality {:?} cannot be constant", var.name, var.causality))),
+            (MD::VariabilityType::Constant, _) if var.default.is_none() => diagnostics.push(error(Some(var.id),
+                format!("Constant variable {} has no default value", var.name))),
+            _ => {}
+        }
+    }
+}
+
+fn check_experiment(exp: &MD::ExperimentInfo, diagnostics: &mut Vec<Diagnostic>) {
+    // zero means that the respective value is not specified
+    if exp.time_step_min < 0.0 || exp.time_step_max < 0.0 || exp.time_step_default < 0.0 {
+        diagnostics.push(error(None, "Experiment time steps must not be negative".to_string()));
+    }
+
+    if exp.time_step_min > 0.0 && exp.time_step_max > 0.0 && exp.time_step_min > exp.time_step_max {
+        diagnostics.push(error(None,
+            format!("Experiment timestep_min {} exceeds timestep_max {}", exp.time_step_min, exp.time_step_max)));
+    }
+
+    if exp.time_step_default > 0.0 {
+        if exp.time_step_min > 0.0 && exp.time_step_default < exp.time_step_min {
+            diagnostics.push(error(None,
+                format!("Experiment timestep_default {} is below timestep_min {}", exp.time_step_default, exp.time_step_min)));
+        }
+        if exp.time_step_max > 0.0 && exp.time_step_default > exp.time_step_max {
+            diagnostics.push(error(None,
+                format!("Experiment timestep_default {} exceeds timestep_max {}", exp.time_step_default, exp.time_step_max)));
+        }
+    }
+
+    if exp.end_time_default != 0.0 && exp.start_time_default > exp.end_time_default {
+        diagnostics.push(error(None,
+            format!("Experiment starttime_default {} is after endtime_default {}", exp.start_time_default, exp.end_time_default)));
+    }
+}
+
+/// Fields of a value table that are set although they do not belong to the
+/// given variable type; unset fields read as zero
+fn wrong_type_fields(value: &MD::VariableValue, value_type: MD::VariableType) -> Vec<&'static str> {
+    let mut set_fields = Vec::new();
+    if value.real_val != 0.0 && value_type != MD::VariableType::DtasmReal { set_fields.push("real_val"); }
+    if value.int_val != 0 && value_type != MD::VariableType::DtasmInt { set_fields.push("int_val"); }
+    if value.bool_val && value_type != MD::VariableType::DtasmBool { set_fields.push("bool_val"); }
+    if !value.string_val.is_empty() && value_type != MD::VariableType::DtasmString { set_fields.push("string_val"); }
+    if !value.binary_val.is_empty() && value_type != MD::VariableType::DtasmBinary { set_fields.push("binary_val"); }
+    if value.int64_val != 0 && value_type != MD::VariableType::DtasmInt64 { set_fields.push("int64_val"); }
+    if value.float32_val != 0.0 && value_type != MD::VariableType::DtasmFloat32 { set_fields.push("float32_val"); }
+
+    set_fields
+}
+
+/// Compare two values of a numeric variable type
+fn compare(a: &MD::VariableValue, b: &MD::VariableValue, value_type: MD::VariableType) -> Option<Ordering> {
+    match value_type {
+        MD::VariableType::DtasmReal => a.real_val.partial_cmp(&b.real_val),
+        MD::VariableType::DtasmFloat32 => a.float32_val.partial_cmp(&b.float32_val),
+        MD::VariableType::DtasmInt => Some(a.int_val.cmp(&b.int_val)),
+        MD::VariableType::DtasmInt64 => Some(a.int64_val.cmp(&b.int64_val)),
+        _ => None
+    }
+}
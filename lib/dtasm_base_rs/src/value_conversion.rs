@@ -0,0 +1,216 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Conversion of variable values between `DtasmVarValues` and the `VarValues`
+//! FlatBuffer table exchanged in `init`, `setValues` and `getValues`.
+
+use std::convert::identity;
+
+use flatbuffers as FB;
+
+use dtasm_abi::dtasm_generated::dtasm_types as DTT;
+use crate::errors::DtasmError;
+use crate::types::DtasmVarValues;
+
+/// Read all scalar, array and binary values from a `VarValues` table
+pub fn convert_var_values(values: &DTT::VarValues) -> Result<DtasmVarValues, DtasmError> {
+    let mut var_vals = DtasmVarValues::new();
+
+    for real_val in values.real_vals().iter().flat_map(identity) {
+        var_vals.real_values.insert(real_val.id(), real_val.val());
+    }
+    for int_val in values.int_vals().iter().flat_map(identity) {
+        var_vals.int_values.insert(int_val.id(), int_val.val());
+    }
+    for bool_val in values.bool_vals().iter().flat_map(identity) {
+        var_vals.bool_values.insert(bool_val.id(), bool_val.val());
+    }
+    for str_val in values.string_vals().iter().flat_map(identity) {
+        let id = str_val.id();
+        let val = str_val.val().ok_or(DtasmError::InvalidVariableValue("None".to_string(), id))?;
+        var_vals.string_values.insert(id, val.to_string());
+    }
+    for int64_val in values.int64_vals().iter().flat_map(identity) {
+        var_vals.int64_values.insert(int64_val.id(), int64_val.val());
+    }
+    for float32_val in values.float32_vals().iter().flat_map(identity) {
+        var_vals.float32_values.insert(float32_val.id(), float32_val.val());
+    }
+
+    for real_arr in values.real_array_vals().iter().flat_map(identity) {
+        let val = real_arr.val().map(|v| v.iter().collect()).unwrap_or_default();
+        var_vals.real_arrays.insert(real_arr.id(), val);
+    }
+    for int_arr in values.int_array_vals().iter().flat_map(identity) {
+        let val = int_arr.val().map(|v| v.iter().collect()).unwrap_or_default();
+        var_vals.int_arrays.insert(int_arr.id(), val);
+    }
+    for bool_arr in values.bool_array_vals().iter().flat_map(identity) {
+        let val = bool_arr.val().map(|v| v.iter().collect()).unwrap_or_default();
+        var_vals.bool_arrays.insert(bool_arr.id(), val);
+    }
+    for str_arr in values.string_array_vals().iter().flat_map(identity) {
+        let val = str_arr.val().map(|v| v.iter().map(|s| s.to_string()).collect()).unwrap_or_default();
+        var_vals.string_arrays.insert(str_arr.id(), val);
+    }
+    for int64_arr in values.int64_array_vals().iter().flat_map(identity) {
+        let val = int64_arr.val().map(|v| v.iter().collect()).unwrap_or_default();
+        var_vals.int64_arrays.insert(int64_arr.id(), val);
+    }
+    for float32_arr in values.float32_array_vals().iter().flat_map(identity) {
+        let val = float32_arr.val().map(|v| v.iter().collect()).unwrap_or_default();
+        var_vals.float32_arrays.insert(float32_arr.id(), val);
+    }
+
+    for bin_val in values.binary_vals().iter().flat_map(identity) {
+        let val = bin_val.val().map(|v| v.to_vec()).unwrap_or_default();
+        var_vals.binary_values.insert(bin_val.id(), val);
+    }
+
+    Ok(var_vals)
+}
+
+/// Build a `VarValues` table from the given values
+pub fn create_var_values<'a>(builder: &mut FB::FlatBufferBuilder<'a>, values: &DtasmVarValues) -> FB::WIPOffset<DTT::VarValues<'a>> {
+    let mut real_offs: Vec<FB::WIPOffset<DTT::RealVal>> = Vec::new();
+    for (id, val) in &values.real_values {
+        real_offs.push(DTT::RealVal::create(builder, &DTT::RealValArgs{
+            id: *id,
+            val: *val
+        }));
+    }
+    let real_vals = builder.create_vector(&real_offs);
+
+    let mut int_offs: Vec<FB::WIPOffset<DTT::IntVal>> = Vec::new();
+    for (id, val) in &values.int_values {
+        int_offs.push(DTT::IntVal::create(builder, &DTT::IntValArgs{
+            id: *id,
+            val: *val
+        }));
+    }
+    let int_vals = builder.create_vector(&int_offs);
+
+    let mut bool_offs: Vec<FB::WIPOffset<DTT::BoolVal>> = Vec::new();
+    for (id, val) in &values.bool_values {
+        bool_offs.push(DTT::BoolVal::create(builder, &DTT::BoolValArgs{
+            id: *id,
+            val: *val
+        }));
+    }
+    let bool_vals = builder.create_vector(&bool_offs);
+
+    let mut string_offs: Vec<FB::WIPOffset<DTT::StringVal>> = Vec::new();
+    for (id, val) in &values.string_values {
+        let val_str = builder.create_string(val);
+        string_offs.push(DTT::StringVal::create(builder, &DTT::StringValArgs{
+            id: *id,
+            val: Some(val_str)
+        }));
+    }
+    let string_vals = builder.create_vector(&string_offs);
+
+    let mut int64_offs: Vec<FB::WIPOffset<DTT::Int64Val>> = Vec::new();
+    for (id, val) in &values.int64_values {
+        int64_offs.push(DTT::Int64Val::create(builder, &DTT::Int64ValArgs{
+            id: *id,
+            val: *val
+        }));
+    }
+    let int64_vals = builder.create_vector(&int64_offs);
+
+    let mut float32_offs: Vec<FB::WIPOffset<DTT::Float32Val>> = Vec::new();
+    for (id, val) in &values.float32_values {
+        float32_offs.push(DTT::Float32Val::create(builder, &DTT::Float32ValArgs{
+            id: *id,
+            val: *val
+        }));
+    }
+    let float32_vals = builder.create_vector(&float32_offs);
+
+    let mut real_arr_offs: Vec<FB::WIPOffset<DTT::RealArrayVal>> = Vec::new();
+    for (id, val) in &values.real_arrays {
+        let val_vec = builder.create_vector(val);
+        real_arr_offs.push(DTT::RealArrayVal::create(builder, &DTT::RealArrayValArgs{
+            id: *id,
+            val: Some(val_vec)
+        }));
+    }
+    let real_array_vals = builder.create_vector(&real_arr_offs);
+
+    let mut int_arr_offs: Vec<FB::WIPOffset<DTT::IntArrayVal>> = Vec::new();
+    for (id, val) in &values.int_arrays {
+        let val_vec = builder.create_vector(val);
+        int_arr_offs.push(DTT::IntArrayVal::create(builder, &DTT::IntArrayValArgs{
+            id: *id,
+            val: Some(val_vec)
+        }));
+    }
+    let int_array_vals = builder.create_vector(&int_arr_offs);
+
+    let mut bool_arr_offs: Vec<FB::WIPOffset<DTT::BoolArrayVal>> = Vec::new();
+    for (id, val) in &values.bool_arrays {
+        let val_vec = builder.create_vector(val);
+        bool_arr_offs.push(DTT::BoolArrayVal::create(builder, &DTT::BoolArrayValArgs{
+            id: *id,
+            val: Some(val_vec)
+        }));
+    }
+    let bool_array_vals = builder.create_vector(&bool_arr_offs);
+
+    let mut string_arr_offs: Vec<FB::WIPOffset<DTT::StringArrayVal>> = Vec::new();
+    for (id, val) in &values.string_arrays {
+        let str_offs: Vec<FB::WIPOffset<&str>> = val.iter().map(|s| builder.create_string(s)).collect();
+        let val_vec = builder.create_vector(&str_offs);
+        string_arr_offs.push(DTT::StringArrayVal::create(builder, &DTT::StringArrayValArgs{
+            id: *id,
+            val: Some(val_vec)
+        }));
+    }
+    let string_array_vals = builder.create_vector(&string_arr_offs);
+
+    let mut int64_arr_offs: Vec<FB::WIPOffset<DTT::Int64ArrayVal>> = Vec::new();
+    for (id, val) in &values.int64_arrays {
+        let val_vec = builder.create_vector(val);
+        int64_arr_offs.push(DTT::Int64ArrayVal::create(builder, &DTT::Int64ArrayValArgs{
+            id: *id,
+            val: Some(val_vec)
+        }));
+    }
+    let int64_array_vals = builder.create_vector(&int64_arr_offs);
+
+    let mut float32_arr_offs: Vec<FB::WIPOffset<DTT::Float32ArrayVal>> = Vec::new();
+    for (id, val) in &values.float32_arrays {
+        let val_vec = builder.create_vector(val);
+        float32_arr_offs.push(DTT::Float32ArrayVal::create(builder, &DTT::Float32ArrayValArgs{
+            id: *id,
+            val: Some(val_vec)
+        }));
+    }
+    let float32_array_vals = builder.create_vector(&float32_arr_offs);
+
+    let mut binary_offs: Vec<FB::WIPOffset<DTT::BinaryVal>> = Vec::new();
+    for (id, val) in &values.binary_values {
+        let val_vec = builder.create_vector(val);
+        binary_offs.push(DTT::BinaryVal::create(builder, &DTT::BinaryValArgs{
+            id: *id,
+            val: Some(val_vec)
+        }));
+    }
+    let binary_vals = builder.create_vector(&binary_offs);
+
+    DTT::VarValues::create(builder, &DTT::VarValuesArgs{
+        real_vals: Some(real_vals),
+        int_vals: Some(int_vals),
+        bool_vals: Some(bool_vals),
+        string_vals: Some(string_vals),
+        real_array_vals: Some(real_array_vals),
+        int_array_vals: Some(int_array_vals),
+        bool_array_vals: Some(bool_array_vals),
+        string_array_vals: Some(string_array_vals),
+        binary_vals: Some(binary_vals),
+        int64_vals: Some(int64_vals),
+        float32_vals: Some(float32_vals),
+        int64_array_vals: Some(int64_array_vals),
+        float32_array_vals: Some(float32_array_vals)
+    })
+}
@@ -0,0 +1,647 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! `#[derive(DtasmModel)]` for simulation modules written with `dtasm_rs`.
+//!
+//! The model description is assembled from the struct definition and encoded
+//! as FlatBuffer at compile time. Model info and experiment settings are given
+//! in a `#[dtasm(...)]` attribute on the struct (keys as in
+//! `modelDescription.json`), variables in `#[dtasm(...)]` attributes on the
+//! fields; fields without attribute are internal state of the model.
+//!
+//! ```ignore
+//! #[derive(Default, DtasmModel)]
+//! #[dtasm(name = "Add", timestep_default = 0.01, endtime_default = 10.0, time_unit = "s")]
+//! pub struct Add {
+//!     #[dtasm(id = 1, causality = "input", default = 1.5)]
+//!     real_in1: f64,
+//!     #[dtasm(id = 2, causality = "input", unit = "m", description = "Second summand")]
+//!     real_in2: f64,
+//!     #[dtasm(id = 3, causality = "output")]
+//!     real_out: f64,
+//! }
+//! ```
+//!
+//! Supported field types are `f64`, `f32`, `i32`, `i64`, `bool`, `String` and
+//! `Vec<u8>` (binary variables, with byte string literals as default). Field
+//! attributes are `id` (required), `causality` (`local`, `parameter`, `input`
+//! or `output`), `variability` (`constant`, `fixed`, `tunable`, `discrete` or
+//! `continuous`), `name` (defaults to the field name), `unit`, `description`,
+//! `default`, `min`, `max`, `nominal` and `derivative_of` (id of the variable
+//! this is the derivative of).
+//!
+//! Array variables are fields of type `Vec<T>` (with `T` one of the scalar
+//! types except `Vec<u8>`) with a `dimensions = [2, 3]` attribute; their
+//! elements are stored in row-major order, `default`, `min` and `max` apply to
+//! each element. `set_defaults` sizes array fields to their dimensions.
+//!
+//! Enumeration variables are `i32` fields with `enumeration = "Mode"`; the
+//! items of the enumeration type are declared on one of its variables with
+//! `items = [Off = 0, On = 1]`.
+//!
+//! ```ignore
+//! #[dtasm(id = 4, causality = "input", enumeration = "Mode", items = [Off = 0, On = 1])]
+//! mode: i32,
+//! #[dtasm(id = 5, causality = "output", dimensions = [3], default = 0.0)]
+//! position: Vec<f64>,
+//! ```
+//!
+//! The model description is also embedded as `dtasm.model_description` custom
+//! section, so crates using the derive must not invoke
+//! `dtasm_model_description_section!` as well.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, GenericArgument, Ident, Lit, PathArguments, Token, Type, UnOp};
+
+use dtasm_base::model_conversion::model_description_to_bytes;
+use dtasm_base::model_description as MD;
+use dtasm_base::validation::{validate, Severity};
+
+#[proc_macro_derive(DtasmModel, attributes(dtasm))]
+pub fn derive_dtasm_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into()
+    }
+}
+
+/// `key` or `key = value` inside of a `#[dtasm(...)]` attribute
+struct DtasmArg {
+    key: Ident,
+    value: Option<Expr>
+}
+
+impl Parse for DtasmArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        let value = match input.peek(Token![=]) {
+            true => {
+                input.parse::<Token![=]>()?;
+                Some(input.parse()?)
+            },
+            false => None
+        };
+
+        Ok(DtasmArg { key, value })
+    }
+}
+
+impl DtasmArg {
+    fn value(&self) -> syn::Result<&Expr> {
+        self.value.as_ref()
+            .ok_or_else(|| syn::Error::new(self.key.span(), format!("`{}` requires a value", self.key)))
+    }
+
+    fn string(&self) -> syn::Result<String> {
+        match self.value()? {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::Str(s) => Ok(s.value()),
+                lit => Err(syn::Error::new(lit.span(), format!("`{}` expects a string literal", self.key)))
+            },
+            expr => Err(syn::Error::new(expr.span(), format!("`{}` expects a string literal", self.key)))
+        }
+    }
+
+    fn bytes(&self) -> syn::Result<Vec<u8>> {
+        match self.value()? {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::ByteStr(s) => Ok(s.value()),
+                lit => Err(syn::Error::new(lit.span(), format!("`{}` expects a byte string literal", self.key)))
+            },
+            expr => Err(syn::Error::new(expr.span(), format!("`{}` expects a byte string literal", self.key)))
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&self) -> syn::Result<T> {
+        self.number_of(self.value()?)
+    }
+
+    /// Numeric literals of an array literal, e.g. `[2, 3]`
+    fn numbers<T: std::str::FromStr>(&self) -> syn::Result<Vec<T>> {
+        match self.value()? {
+            Expr::Array(array) => array.elems.iter().map(|elem| self.number_of(elem)).collect(),
+            expr => Err(syn::Error::new(expr.span(), format!("`{}` expects an array of numeric literals", self.key)))
+        }
+    }
+
+    /// Enumeration items given as `[Name = value, ...]`
+    fn items(&self) -> syn::Result<Vec<MD::EnumerationItem>> {
+        let err = |span: proc_macro2::Span| syn::Error::new(span, 
+            format!("`{}` expects an array of items like `[Off = 0, On = 1]`", self.key));
+
+        match self.value()? {
+            Expr::Array(array) => array.elems.iter()
+                .map(|elem| match elem {
+                    Expr::Assign(assign) => match &*assign.left {
+                        Expr::Path(path) if path.path.get_ident().is_some() => Ok(MD::EnumerationItem {
+                            name: path.path.get_ident().unwrap().to_string(),
+                            value: self.number_of(&assign.right)?,
+                            description: String::new()
+                        }),
+                        left => Err(err(left.span()))
+                    },
+                    elem => Err(err(elem.span()))
+                })
+                .collect(),
+            expr => Err(err(expr.span()))
+        }
+    }
+
+    fn number_of<T: std::str::FromStr>(&self, value: &Expr) -> syn::Result<T> {
+        let err = || syn::Error::new(value.span(), format!("`{}` expects a numeric literal of type {}",
+            self.key, std::any::type_name::<T>()));
+
+        let (negative, lit) = match value {
+            Expr::Lit(expr) => (false, &expr.lit),
+            Expr::Unary(expr) => match (&expr.op, &*expr.expr) {
+                (UnOp::Neg(_), Expr::Lit(inner)) => (true, &inner.lit),
+                _ => return Err(err())
+            },
+            _ => return Err(err())
+        };
+        let digits = match lit {
+            Lit::Int(i) => i.base10_digits().to_string(),
+            Lit::Float(f) => f.base10_digits().to_string(),
+            _ => return Err(err())
+        };
+
+        let digits = match negative {
+            true => format!("-{}", digits),
+            false => digits
+        };
+        digits.parse().map_err(|_| err())
+    }
+
+    fn boolean(&self) -> syn::Result<bool> {
+        match &self.value {
+            // a flag without value is set
+            None => Ok(true),
+            Some(Expr::Lit(expr)) => match &expr.lit {
+                Lit::Bool(b) => Ok(b.value),
+                lit => Err(syn::Error::new(lit.span(), format!("`{}` expects a boolean literal", self.key)))
+            },
+            Some(expr) => Err(syn::Error::new(expr.span(), format!("`{}` expects a boolean literal", self.key)))
+        }
+    }
+}
+
+fn dtasm_args(attrs: &[Attribute]) -> syn::Result<Option<Vec<DtasmArg>>> {
+    let mut args: Option<Vec<DtasmArg>> = None;
+
+    for attr in attrs.iter().filter(|a| a.path.is_ident("dtasm")) {
+        let parsed = attr.parse_args_with(Punctuated::<DtasmArg, Token![,]>::parse_terminated)?;
+        args.get_or_insert_with(Vec::new).extend(parsed);
+    }
+
+    Ok(args)
+}
+
+fn unknown_key(arg: &DtasmArg) -> syn::Error {
+    syn::Error::new(arg.key.span(), format!("Unknown dtasm attribute `{}`", arg.key))
+}
+
+fn model_info(input: &DeriveInput, args: &[DtasmArg]) -> syn::Result<(MD::ModelInfo, Option<MD::ExperimentInfo>)> {
+    let mut info = MD::ModelInfo {
+        name: input.ident.to_string(),
+        id: String::new(),
+        description: String::new(),
+        generation_tool: "dtasm_derive".to_string(),
+        generation_date_time: String::new(),
+        name_delimiter: ".".to_string(),
+        capabilities: MD::Capabilities::default()
+    };
+    let mut experiment: Option<MD::ExperimentInfo> = None;
+
+    for arg in args {
+        match arg.key.to_string().as_str() {
+            "name" => info.name = arg.string()?,
+            "id" => info.id = arg.string()?,
+            "description" => info.description = arg.string()?,
+            "generation_tool" => info.generation_tool = arg.string()?,
+            "generation_datetime" => info.generation_date_time = arg.string()?,
+            "name_delimiter" => info.name_delimiter = arg.string()?,
+            "can_handle_variable_step_size" => info.capabilities.can_handle_variable_step_size = arg.boolean()?,
+            "can_reset_step" => info.capabilities.can_reset_step = arg.boolean()?,
+            "can_interpolate_inputs" => info.capabilities.can_interpolate_inputs = arg.boolean()?,
+            "timestep_min" => experiment.get_or_insert_with(Default::default).time_step_min = arg.number()?,
+            "timestep_max" => experiment.get_or_insert_with(Default::default).time_step_max = arg.number()?,
+            "timestep_default" => experiment.get_or_insert_with(Default::default).time_step_default = arg.number()?,
+            "starttime_default" => experiment.get_or_insert_with(Default::default).start_time_default = arg.number()?,
+            "endtime_default" => experiment.get_or_insert_with(Default::default).end_time_default = arg.number()?,
+            "time_unit" => experiment.get_or_insert_with(Default::default).time_unit = arg.string()?,
+            _ => return Err(unknown_key(arg))
+        }
+    }
+
+    Ok((info, experiment))
+}
+
+fn scalar_type(ty: &Type) -> Option<MD::VariableType> {
+    let ident = match ty {
+        Type::Path(tp) => tp.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None
+    };
+
+    match ident.as_deref() {
+        Some("f64") => Some(MD::VariableType::DtasmReal),
+        Some("i32") => Some(MD::VariableType::DtasmInt),
+        Some("i64") => Some(MD::VariableType::DtasmInt64),
+        Some("f32") => Some(MD::VariableType::DtasmFloat32),
+        Some("bool") => Some(MD::VariableType::DtasmBool),
+        Some("String") => Some(MD::VariableType::DtasmString),
+        _ => None
+    }
+}
+
+/// Variable type of a field and whether it is a `Vec` of elements of this type
+fn value_type(ty: &Type) -> syn::Result<(MD::VariableType, bool)> {
+    let err = || syn::Error::new(ty.span(), 
+        "dtasm variables must be of type f64, f32, i32, i64, bool, String, Vec<u8> or a Vec of these types");
+
+    if let Some(value_type) = scalar_type(ty) {
+        return Ok((value_type, false));
+    }
+
+    let segment = match ty {
+        Type::Path(tp) => tp.path.segments.last().ok_or_else(err)?,
+        _ => return Err(err())
+    };
+    let elem = match &segment.arguments {
+        PathArguments::AngleBracketed(args) if segment.ident == "Vec" && args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(elem) => elem,
+            _ => return Err(err())
+        },
+        _ => return Err(err())
+    };
+
+    match elem {
+        Type::Path(tp) if tp.path.is_ident("u8") => Ok((MD::VariableType::DtasmBinary, false)),
+        _ => scalar_type(elem).map(|value_type| (value_type, true)).ok_or_else(err)
+    }
+}
+
+/// Variable of a field and the enumeration type declared with `items`, if any
+fn model_variable(field_ident: &Ident, ty: &Type, args: &[DtasmArg]) 
+    -> syn::Result<(MD::ModelVariable, Option<MD::EnumerationType>)> {
+    let (value_type, is_vec) = value_type(ty)?;
+    let mut items: Option<(&DtasmArg, Vec<MD::EnumerationItem>)> = None;
+    let mut id: Option<i32> = None;
+    let mut var = MD::ModelVariable {
+        id: 0,
+        name: field_ident.to_string(),
+        value_type,
+        description: String::new(),
+        unit: String::new(),
+        causality: MD::CausalityType::Local,
+        derivative_of_id: -1,
+        default: None,
+        dimensions: Vec::new(),
+        enumeration_type: String::new(),
+        min: None,
+        max: None,
+        nominal: None,
+        variability: MD::VariabilityType::default()
+    };
+
+    for arg in args {
+        match arg.key.to_string().as_str() {
+            "id" => id = Some(arg.number()?),
+            "name" => var.name = arg.string()?,
+            "description" => var.description = arg.string()?,
+            "unit" => var.unit = arg.string()?,
+            "derivative_of" => var.derivative_of_id = arg.number()?,
+            "causality" => {
+                var.causality = match arg.string()?.as_str() {
+                    "local" => MD::CausalityType::Local,
+                    "parameter" => MD::CausalityType::Parameter,
+                    "input" => MD::CausalityType::Input,
+                    "output" => MD::CausalityType::Output,
+                    _ => return Err(syn::Error::new(arg.value()?.span(),
+                        "causality must be one of \"local\", \"parameter\", \"input\", \"output\""))
+                };
+            },
+            "variability" => {
+                var.variability = match arg.string()?.as_str() {
+                    "constant" => MD::VariabilityType::Constant,
+                    "fixed" => MD::VariabilityType::Fixed,
+                    "tunable" => MD::VariabilityType::Tunable,
+                    "discrete" => MD::VariabilityType::Discrete,
+                    "continuous" => MD::VariabilityType::Continuous,
+                    _ => return Err(syn::Error::new(arg.value()?.span(),
+                        "variability must be one of \"constant\", \"fixed\", \"tunable\", \"discrete\", \"continuous\""))
+                };
+            },
+            "default" => var.default = Some(variable_value(arg, value_type)?),
+            "min" => var.min = Some(variable_value(arg, value_type)?),
+            "max" => var.max = Some(variable_value(arg, value_type)?),
+            "nominal" => var.nominal = Some(variable_value(arg, value_type)?),
+            "dimensions" => var.dimensions = arg.numbers()?,
+            "enumeration" => var.enumeration_type = arg.string()?,
+            "items" => items = Some((arg, arg.items()?)),
+            _ => return Err(unknown_key(arg))
+        }
+    }
+
+    var.id = id.ok_or_else(|| syn::Error::new(field_ident.span(), "dtasm variables require an `id`"))?;
+
+    if is_vec && var.dimensions.is_empty() {
+        return Err(syn::Error::new(ty.span(), "dtasm variables of type Vec require `dimensions`"));
+    }
+    if !is_vec && !var.dimensions.is_empty() {
+        return Err(syn::Error::new(ty.span(), "dtasm variables with `dimensions` must be of type Vec"));
+    }
+
+    let enum_type = match items {
+        Some((arg, _)) if var.enumeration_type.is_empty() => 
+            return Err(syn::Error::new(arg.key.span(), "`items` requires `enumeration`")),
+        Some((_, items)) => Some(MD::EnumerationType {
+            name: var.enumeration_type.clone(),
+            description: String::new(),
+            items
+        }),
+        None => None
+    };
+
+    Ok((var, enum_type))
+}
+
+/// Value of the given variable type from the literal of an attribute argument
+fn variable_value(arg: &DtasmArg, value_type: MD::VariableType) -> syn::Result<MD::VariableValue> {
+    let mut value = MD::VariableValue::default();
+    match value_type {
+        MD::VariableType::DtasmReal => value.real_val = arg.number()?,
+        MD::VariableType::DtasmInt => value.int_val = arg.number()?,
+        MD::VariableType::DtasmBool => value.bool_val = arg.boolean()?,
+        MD::VariableType::DtasmString => value.string_val = arg.string()?,
+        MD::VariableType::DtasmBinary => value.binary_val = arg.bytes()?,
+        MD::VariableType::DtasmInt64 => value.int64_val = arg.number()?,
+        MD::VariableType::DtasmFloat32 => value.float32_val = arg.number()?
+    };
+
+    Ok(value)
+}
+
+fn type_tokens(value_type: MD::VariableType) -> TokenStream2 {
+    match value_type {
+        MD::VariableType::DtasmReal => quote!(::dtasm_rs::model_description::VariableType::DtasmReal),
+        MD::VariableType::DtasmInt => quote!(::dtasm_rs::model_description::VariableType::DtasmInt),
+        MD::VariableType::DtasmBool => quote!(::dtasm_rs::model_description::VariableType::DtasmBool),
+        MD::VariableType::DtasmString => quote!(::dtasm_rs::model_description::VariableType::DtasmString),
+        MD::VariableType::DtasmBinary => quote!(::dtasm_rs::model_description::VariableType::DtasmBinary),
+        MD::VariableType::DtasmInt64 => quote!(::dtasm_rs::model_description::VariableType::DtasmInt64),
+        MD::VariableType::DtasmFloat32 => quote!(::dtasm_rs::model_description::VariableType::DtasmFloat32)
+    }
+}
+
+/// Field of `DtasmVarValues` holding scalar or array values of the given type
+/// (binary variables are always scalar)
+fn value_map(value_type: MD::VariableType, array: bool) -> Ident {
+    let name = match (value_type, array) {
+        (MD::VariableType::DtasmReal, false) => "real_values",
+        (MD::VariableType::DtasmInt, false) => "int_values",
+        (MD::VariableType::DtasmBool, false) => "bool_values",
+        (MD::VariableType::DtasmString, false) => "string_values",
+        (MD::VariableType::DtasmBinary, _) => "binary_values",
+        (MD::VariableType::DtasmInt64, false) => "int64_values",
+        (MD::VariableType::DtasmFloat32, false) => "float32_values",
+        (MD::VariableType::DtasmReal, true) => "real_arrays",
+        (MD::VariableType::DtasmInt, true) => "int_arrays",
+        (MD::VariableType::DtasmBool, true) => "bool_arrays",
+        (MD::VariableType::DtasmString, true) => "string_arrays",
+        (MD::VariableType::DtasmInt64, true) => "int64_arrays",
+        (MD::VariableType::DtasmFloat32, true) => "float32_arrays"
+    };
+
+    Ident::new(name, proc_macro2::Span::call_site())
+}
+
+/// Match statement assigning the scalar or array values of one type to their 
+/// fields; array values must match the dimensions of their variable
+fn set_values_tokens(value_type: MD::VariableType, array: bool, vars: &[(Ident, MD::ModelVariable)]) -> TokenStream2 {
+    let map = value_map(value_type, array);
+
+    let arms = vars.iter().map(|(field, var)| {
+        let id = var.id;
+        if var.value_type == value_type && var.is_array() != array {
+            match array {
+                true => quote!(#id => { return Err(::dtasm_rs::errors::DtasmError::VariableDimensionMismatch(val.len(), *id)); }),
+                false => quote!(#id => { return Err(::dtasm_rs::errors::DtasmError::VariableDimensionMismatch(1, *id)); })
+            }
+        }
+        else if var.value_type == value_type && array {
+            let len = var.array_len();
+            quote!(#id => {
+                if val.len() != #len {
+                    return Err(::dtasm_rs::errors::DtasmError::VariableDimensionMismatch(val.len(), *id));
+                }
+                self.#field = val.clone();
+            })
+        }
+        else if var.value_type == value_type {
+            match value_type {
+                MD::VariableType::DtasmString | MD::VariableType::DtasmBinary => quote!(#id => { self.#field = val.clone(); }),
+                _ => quote!(#id => { self.#field = *val; })
+            }
+        }
+        else {
+            let var_type = type_tokens(var.value_type);
+            quote!(#id => { return Err(::dtasm_rs::errors::DtasmError::VariableTypeMismatch(#var_type, *id)); })
+        }
+    });
+
+    quote! {
+        for (id, val) in &values.#map {
+            match *id {
+                #(#arms)*
+                _ => { return Err(::dtasm_rs::errors::DtasmError::UnknownVariableId(*id)); }
+            }
+        }
+    }
+}
+
+/// Assignment of an array field with its default for each element (or the 
+/// default of the element type)
+fn array_default_tokens(field: &Ident, var: &MD::ModelVariable) -> TokenStream2 {
+    let len = var.array_len();
+    let value = match &var.default {
+        None => quote!(::std::default::Default::default()),
+        Some(default) => match var.value_type {
+            MD::VariableType::DtasmReal => { let v = default.real_val; quote!(#v) },
+            MD::VariableType::DtasmInt => { let v = default.int_val; quote!(#v) },
+            MD::VariableType::DtasmBool => { let v = default.bool_val; quote!(#v) },
+            MD::VariableType::DtasmString => { let v = &default.string_val; quote!(::std::string::String::from(#v)) },
+            MD::VariableType::DtasmInt64 => { let v = default.int64_val; quote!(#v) },
+            MD::VariableType::DtasmFloat32 => { let v = default.float32_val; quote!(#v) },
+            // binary variables are never arrays
+            MD::VariableType::DtasmBinary => quote!(::std::default::Default::default())
+        }
+    };
+
+    quote!(self.#field = ::std::vec![#value; #len];)
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new(input.span(), "DtasmModel requires a struct with named fields"))
+        },
+        _ => return Err(syn::Error::new(input.span(), "DtasmModel can only be derived for structs"))
+    };
+
+    let model_args = dtasm_args(&input.attrs)?.unwrap_or_default();
+    let (model, experiment) = model_info(input, &model_args)?;
+
+    let mut vars: Vec<(Ident, MD::ModelVariable)> = Vec::new();
+    let mut enumeration_types: Vec<MD::EnumerationType> = Vec::new();
+    for field in fields {
+        let ident = field.ident.clone().expect("named field");
+        if let Some(args) = dtasm_args(&field.attrs)? {
+            let (var, enum_type) = model_variable(&ident, &field.ty, &args)?;
+
+            if let Some(enum_type) = enum_type {
+                match enumeration_types.iter().find(|e| e.name == enum_type.name) {
+                    Some(declared) if declared.items != enum_type.items => return Err(syn::Error::new(ident.span(),
+                        format!("Items of enumeration type `{}` differ from its earlier declaration", enum_type.name))),
+                    Some(_) => {},
+                    None => enumeration_types.push(enum_type)
+                }
+            }
+            vars.push((ident, var));
+        }
+    }
+
+    for (ident, var) in &vars {
+        if !var.enumeration_type.is_empty() && !enumeration_types.iter().any(|e| e.name == var.enumeration_type) {
+            return Err(syn::Error::new(ident.span(), format!(
+                "Items of enumeration type `{}` must be declared with `items` on one of its variables", var.enumeration_type)));
+        }
+    }
+
+    let md = MD::ModelDescription {
+        model,
+        variables: vars.iter().map(|(_, var)| var.clone()).collect(),
+        experiment,
+        enumeration_types,
+        unit_definitions: Vec::new()
+    };
+
+    let errors: Vec<String> = validate(&md).iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| d.to_string())
+        .collect();
+    if !errors.is_empty() {
+        return Err(syn::Error::new(input.ident.span(), format!("Invalid model description: {}", errors.join("; "))));
+    }
+
+    let md_bytes = model_description_to_bytes(&md);
+    let md_len = md_bytes.len();
+
+    let defaults = vars.iter().filter_map(|(field, var)| {
+        if var.is_array() {
+            return Some(array_default_tokens(field, var));
+        }
+
+        let default = var.default.as_ref()?;
+        Some(match var.value_type {
+            MD::VariableType::DtasmReal => { let v = default.real_val; quote!(self.#field = #v;) },
+            MD::VariableType::DtasmInt => { let v = default.int_val; quote!(self.#field = #v;) },
+            MD::VariableType::DtasmBool => { let v = default.bool_val; quote!(self.#field = #v;) },
+            MD::VariableType::DtasmString => {
+                let v = &default.string_val;
+                quote!(self.#field = ::std::string::String::from(#v);)
+            },
+            MD::VariableType::DtasmBinary => {
+                let v = &default.binary_val;
+                quote!(self.#field = ::std::vec![#(#v),*];)
+            },
+            MD::VariableType::DtasmInt64 => { let v = default.int64_val; quote!(self.#field = #v;) },
+            MD::VariableType::DtasmFloat32 => { let v = default.float32_val; quote!(self.#field = #v;) }
+        })
+    });
+
+    let get_arms = vars.iter().map(|(field, var)| {
+        let id = var.id;
+        let map = value_map(var.value_type, var.is_array());
+        match var.value_type {
+            _ if var.is_array() => quote!(#id => { values.#map.insert(#id, self.#field.clone()); }),
+            MD::VariableType::DtasmString | MD::VariableType::DtasmBinary => quote!(#id => { values.#map.insert(#id, self.#field.clone()); }),
+            _ => quote!(#id => { values.#map.insert(#id, self.#field); })
+        }
+    });
+
+    let set_real = set_values_tokens(MD::VariableType::DtasmReal, false, &vars);
+    let set_int = set_values_tokens(MD::VariableType::DtasmInt, false, &vars);
+    let set_bool = set_values_tokens(MD::VariableType::DtasmBool, false, &vars);
+    let set_string = set_values_tokens(MD::VariableType::DtasmString, false, &vars);
+    let set_binary = set_values_tokens(MD::VariableType::DtasmBinary, false, &vars);
+    let set_int64 = set_values_tokens(MD::VariableType::DtasmInt64, false, &vars);
+    let set_float32 = set_values_tokens(MD::VariableType::DtasmFloat32, false, &vars);
+    let set_real_arrays = set_values_tokens(MD::VariableType::DtasmReal, true, &vars);
+    let set_int_arrays = set_values_tokens(MD::VariableType::DtasmInt, true, &vars);
+    let set_bool_arrays = set_values_tokens(MD::VariableType::DtasmBool, true, &vars);
+    let set_string_arrays = set_values_tokens(MD::VariableType::DtasmString, true, &vars);
+    let set_int64_arrays = set_values_tokens(MD::VariableType::DtasmInt64, true, &vars);
+    let set_float32_arrays = set_values_tokens(MD::VariableType::DtasmFloat32, true, &vars);
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::dtasm_rs::model::DtasmModel for #name #ty_generics #where_clause {
+            const MODEL_DESCRIPTION: &'static [u8] = &[#(#md_bytes),*];
+
+            fn set_defaults(&mut self) {
+                #(#defaults)*
+            }
+
+            fn get_values(&self, var_ids: &[i32], values: &mut ::dtasm_rs::types::DtasmVarValues)
+                -> ::std::result::Result<(), ::dtasm_rs::errors::DtasmError> {
+                for id in var_ids {
+                    match *id {
+                        #(#get_arms)*
+                        _ => { return Err(::dtasm_rs::errors::DtasmError::UnknownVariableId(*id)); }
+                    }
+                }
+
+                Ok(())
+            }
+
+            #[allow(unused_variables)]
+            fn set_values(&mut self, values: &::dtasm_rs::types::DtasmVarValues)
+                -> ::std::result::Result<(), ::dtasm_rs::errors::DtasmError> {
+                #set_real
+                #set_int
+                #set_bool
+                #set_string
+                #set_binary
+                #set_int64
+                #set_float32
+                #set_real_arrays
+                #set_int_arrays
+                #set_bool_arrays
+                #set_string_arrays
+                #set_int64_arrays
+                #set_float32_arrays
+
+                Ok(())
+            }
+        }
+
+        const _: () = {
+            #[cfg(target_arch = "wasm32")]
+            #[used]
+            #[link_section = "dtasm.model_description"]
+            static MODEL_DESCRIPTION_SECTION: [u8; #md_len] = [#(#md_bytes),*];
+        };
+    })
+}
@@ -5,7 +5,7 @@ use dtasm_base::types::{DtasmVarValues,LogLevel,Status,GetValuesResponse,DoStepR
 use dtasm_base::model_description::ModelDescription;
 use dtasm_base::errors::DtasmError;
 
-pub trait DtasmIf: Sync {
+pub trait DtasmIf {
     fn get_model_description(&mut self) -> Option<&'static [u8]>;
 
     fn initialize(&mut self, md: &ModelDescription, initial_vals: &DtasmVarValues, tmin: f64, tmax: Option<f64>, 
@@ -15,6 +15,24 @@ pub trait DtasmIf: Sync {
 
     fn set_values(&mut self, input_vals: &DtasmVarValues) -> Result<Status, DtasmError>;
 
+    /// Called after `set_values` if values of tunable parameters were set
+    /// between steps, e.g. to recompute quantities derived from them
+    ///
+    /// * `param_ids` - Ids of the tunable parameters that were set
+    fn parameters_changed(&mut self, _param_ids: &[i32]) -> Result<Status, DtasmError> {
+        Ok(Status::OK)
+    }
+
     fn do_step(&mut self, current_time: f64, timestep: f64) -> Result<DoStepResponse, DtasmError>;
+
+    /// Reset the module to the state at `reset_time` at the beginning of the
+    /// last step. Only needs to be implemented by modules declaring the
+    /// `can_reset_step` capability.
+    ///
+    /// * `current_time` - Time reached by the last step
+    /// * `reset_time` - Time at the beginning of the last step
+    fn reset_step(&mut self, _current_time: f64, _reset_time: f64) -> Result<Status, DtasmError> {
+        Err(DtasmError::NotImplementedError("reset_step".to_string()))
+    }
 }
 
@@ -2,7 +2,13 @@
 // SPDX-License-Identifier: MIT
 
 pub mod interface;
-pub use dtasm_base::{types,model_description,errors};
+pub mod model;
+mod macros;
+#[cfg(feature = "log")]
+mod logging;
+pub use dtasm_base::{types,model_description,errors,bounds,abi};
+pub use dtasm_derive::DtasmModel;
+pub use model::same_time;
 
 use dtasm_abi::dtasm_generated::dtasm_api as DTAPI;
 use dtasm_abi::dtasm_generated::dtasm_types as DTT;
@@ -14,18 +20,84 @@ use once_cell::sync::Lazy;
 
 use std::slice;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard};
 
 use dtasm_base::model_conversion::{convert_model_description,collect_var_types};
-use dtasm_base::types::{DtasmVarType,DtasmVarValues};
+use dtasm_base::value_conversion::{convert_var_values,create_var_values};
+use dtasm_base::types::{DtasmVarType,DtasmVarValues,DoStepResponse,GetValuesResponse,Status};
 
 extern "Rust" {
-    static mut SIM_MODULE: Lazy<Box<dyn interface::DtasmIf + Sync + Send>>;
+    // defined in the module crate by the `dtasm_module!` macro; the symbol name 
+    // tells module authors missing the macro what to do
+    #[link_name = "__DTASM_CREATE_MODULE__invoke_dtasm_module_macro_exactly_once"]
+    static __DTASM_CREATE_MODULE: fn() -> Box<dyn interface::DtasmIf + Send>;
 }
 
+static SIM_MODULE: Lazy<Mutex<Box<dyn interface::DtasmIf + Send>>> =
+    Lazy::new(|| Mutex::new((unsafe { __DTASM_CREATE_MODULE })()));
+
 static VARTYPES: Lazy<Mutex<HashMap<i32, DtasmVarType>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 static MDBYTES: Lazy<Mutex<&[u8]>> = Lazy::new(|| Mutex::new(&[]));
 static FBBUILDER: Lazy<Mutex<FB::FlatBufferBuilder>> = Lazy::new(|| Mutex::new(FB::FlatBufferBuilder::with_capacity(4096)));
+// simulation time last reported to the runtime, repeated in failed getValues responses
+static CURRENT_TIME: Lazy<Mutex<f64>> = Lazy::new(|| Mutex::new(0.0));
+
+/// Check values received in `initialize` or `set_values` against the `min`/`max`
+/// bounds declared in the model description of this module and apply the given
+/// policy (see `bounds::apply_bounds_policy`)
+///
+/// * `values` - the received values; clamped in place with `BoundsPolicy::Clamp`
+/// * `policy` - treatment of values outside the bounds
+pub fn apply_bounds_policy(values: &mut DtasmVarValues, policy: bounds::BoundsPolicy) 
+    -> Result<Vec<bounds::BoundsViolation>, errors::DtasmError> {
+    let var_types = VARTYPES.lock().unwrap();
+    bounds::apply_bounds_policy(values, &var_types, policy)
+}
+
+/// Decode a request received from the runtime
+///
+/// * `bytes` - the request FlatBuffer
+/// * `export` - name of the called export, for the error message
+fn decode_request<'a, T>(bytes: &'a [u8], export: &str) -> Result<T::Inner, errors::DtasmError>
+    where T: 'a + FB::Follow<'a> + FB::Verifiable {
+    FB::root::<T>(bytes)
+        .map_err(|e| errors::DtasmError::DtasmInternalError(format!("Invalid {} request: {}", export, e)))
+}
+
+/// Lock a global of the module; a lock poisoned by an earlier panic is reported 
+/// as error instead of panicking again
+fn lock<T>(mutex: &'static Mutex<T>) -> Result<MutexGuard<'static, T>, errors::DtasmError> {
+    mutex.lock()
+        .map_err(|_| errors::DtasmError::DtasmInternalError("Module state is poisoned by an earlier panic".to_string()))
+}
+
+/// Model description of the module as FlatBuffer; read from the module and 
+/// checked on first use, which also collects the variable types
+fn model_description_bytes() -> Result<&'static [u8], errors::DtasmError> {
+    let mut md_bytes = lock(&MDBYTES)?;
+
+    if md_bytes.is_empty() {
+        let bytes = lock(&SIM_MODULE)?.get_model_description()
+            .ok_or_else(|| errors::DtasmError::InvalidModelDescription("Module did not provide a model description".to_string()))?;
+        let md_dtasm = DTMD::root_as_model_description(bytes)
+            .map_err(|e| errors::DtasmError::InvalidModelDescription(e.to_string()))?;
+
+        *lock(&VARTYPES)? = collect_var_types(&convert_model_description(&md_dtasm));
+        *md_bytes = bytes;
+    }
+
+    Ok(*md_bytes)
+}
+
+/// Ids of the tunable parameters among the given values
+fn tunable_parameter_ids(values: &DtasmVarValues) -> Vec<i32> {
+    let var_types = VARTYPES.lock().unwrap();
+
+    values.ids().into_iter()
+        .filter(|id| var_types.get(id).map_or(false, |var_type|
+            var_type.causality == model_description::CausalityType::Parameter && var_type.is_settable_between_steps()))
+        .collect()
+}
 
 #[no_mangle]
 extern "C" fn alloc(size: size_t) -> *mut c_void {
@@ -39,27 +111,28 @@ extern "C" fn dealloc(p: *mut c_void) {
 
 #[no_mangle]
 extern "C" fn getModelDescription(out_p: *mut u8, max_len: u32) -> u32 {
-    assert!(!out_p.is_null());
-    let bytes = unsafe { slice::from_raw_parts_mut(out_p, max_len as usize) };
-
-    let mut md_bytes = MDBYTES.lock().unwrap();
-
-    if md_bytes.len() == 0 {
-        *md_bytes = unsafe { SIM_MODULE.get_model_description().unwrap() };
-
-        let md_dtasm = DTMD::root_as_model_description(*md_bytes).unwrap();
-        let md = convert_model_description(&md_dtasm);
+    // the export has no status; on error, an empty model description is returned 
+    // and the error is reported by the following `init` call
+    let md_bytes = match model_description_bytes() {
+        Ok(md_bytes) => md_bytes,
+        Err(_err) => {
+            #[cfg(feature = "log")]
+            logging::error(&_err.to_string());
+            return 0;
+        }
+    };
 
-        let mut var_types = VARTYPES.lock().unwrap();
-        *var_types = collect_var_types(&md);
+    if out_p.is_null() {
+        return md_bytes.len() as u32;
     }
+    let bytes = unsafe { slice::from_raw_parts_mut(out_p, max_len as usize) };
 
     if md_bytes.len() > max_len as usize {
         return md_bytes.len() as u32;
     }
     else
     {
-        bytes[..md_bytes.len()].copy_from_slice(*md_bytes);
+        bytes[..md_bytes.len()].copy_from_slice(md_bytes);
     }
 
     return md_bytes.len() as u32;
@@ -69,40 +142,21 @@ extern "C" fn getModelDescription(out_p: *mut u8, max_len: u32) -> u32 {
 extern "C" fn init(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_len: u32) -> u32
 {
     let in_bytes = unsafe { slice::from_raw_parts(in_p, in_len as usize) };
-    let init_req = FB::root::<DTAPI::InitReq>(in_bytes).unwrap();
 
-    let mut init_vals_sim = DtasmVarValues::new();
+    let init_res = decode_request::<DTAPI::InitReq>(in_bytes, "init").and_then(|init_req| {
+        let init_vals_sim = init_req.init_values()
+            .map(|vals| convert_var_values(&vals))
+            .unwrap_or_else(|| Ok(DtasmVarValues::new()))?;
 
-    let init_vals = init_req.init_values().unwrap();
-    init_vals.bool_vals().map(|bools| for boolean in bools.iter() {
-        let id = boolean.id();
-        let val = boolean.val();
-        init_vals_sim.bool_values.insert(id, val);
-    });
-    init_vals.real_vals().map(|reals| for real in reals.iter() {
-        let id = real.id();
-        let val = real.val();
-        init_vals_sim.real_values.insert(id, val);
-    });
-    init_vals.int_vals().map(|integers| for integer in integers.iter() {
-        let id = integer.id();
-        let val = integer.val();
-        init_vals_sim.int_values.insert(id, val);
-    });
-    init_vals.string_vals().map(|strings| for string in strings.iter() {
-        let id = string.id();
-        let val = string.val().unwrap().to_string();
-        init_vals_sim.string_values.insert(id, val);
-    });
+        let md_dtasm = DTMD::root_as_model_description(model_description_bytes()?)
+            .map_err(|e| errors::DtasmError::InvalidModelDescription(e.to_string()))?;
+        let md = convert_model_description(&md_dtasm);
+
+        #[cfg(feature = "log")]
+        logging::init(init_req.loglevel_limit().into());
 
-    let md_bytes = MDBYTES.lock().unwrap();
-    let md_dtasm = DTMD::root_as_model_description(*md_bytes).unwrap();
-    let md = convert_model_description(&md_dtasm);
-    
-    let init_res =
-    unsafe { 
-         SIM_MODULE.initialize(&md,
-             &init_vals_sim, 
+        let status = lock(&SIM_MODULE)?.initialize(&md,
+            &init_vals_sim, 
             init_req.starttime(), 
             match init_req.endtime_set() {
                 true => Some(init_req.endtime()),
@@ -114,17 +168,23 @@ extern "C" fn init(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_len: u3
             },
             init_req.loglevel_limit().into(), 
             init_req.check_consistency()
-        )
-    };
+        )?;
+
+        *lock(&CURRENT_TIME)? = init_req.starttime();
+        Ok(status)
+    });
 
     let ret_val: u32;
     let mut fb_builder = FBBUILDER.lock().unwrap();
     {
+        let (status, message, error_code) = match init_res {
+            Err(err) => (DTT::Status::Error, Some(fb_builder.create_string(&err.to_string())), err.code()),
+            Ok(status) => (status.into(), None, 0)
+        };
         let status_res = DTAPI::StatusRes::create(&mut fb_builder, &DTAPI::StatusResArgs{
-            status: match init_res {
-                Err(_err) => DTT::Status::Error, 
-                Ok(status) => status.into()
-            }
+            status,
+            message,
+            error_code
         });
 
         fb_builder.finish(status_res, None);
@@ -147,79 +207,48 @@ extern "C" fn init(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_len: u3
 extern "C" fn getValues(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_len: u32) -> u32
 {    
     let in_bytes = unsafe { slice::from_raw_parts(in_p, in_len as usize) };
-    let getvalues_req = FB::root::<DTAPI::GetValuesReq>(in_bytes).unwrap();
 
-    let get_ids = getvalues_req.ids().expect("Get values request did not contain any variables.");
-    let mut get_var_ids: Vec<i32> = vec!();
+    let get_values_res = decode_request::<DTAPI::GetValuesReq>(in_bytes, "getValues").and_then(|getvalues_req| {
+        let get_ids = getvalues_req.ids()
+            .ok_or_else(|| errors::DtasmError::DtasmInternalError("getValues request did not contain any variables".to_string()))?;
+        let mut get_var_ids: Vec<i32> = vec!();
 
-    for i in 0..get_ids.len() {
-        get_var_ids.push(get_ids.get(i));
-    }
+        for i in 0..get_ids.len() {
+            get_var_ids.push(get_ids.get(i));
+        }
 
-    let get_values_res =
-        unsafe { 
-            SIM_MODULE.get_values(&get_var_ids).expect("Error on calling get_values: ")
-        };
+        SIM_MODULE.lock().unwrap().get_values(&get_var_ids)
+    });
+
+    // on error, report an empty set of values along with the error message and
+    // the time last reported by the module
+    let get_values_res = match get_values_res {
+        Ok(res) => {
+            *CURRENT_TIME.lock().unwrap() = res.current_time;
+            res
+        },
+        Err(err) => GetValuesResponse {
+            status: Status::Error,
+            current_time: *CURRENT_TIME.lock().unwrap(),
+            values: DtasmVarValues::new(),
+            message: Some(err.to_string()),
+            error_code: Some(err.code())
+        }
+    };
 
     let ret_val: u32;
     let mut fb_builder = FBBUILDER.lock().unwrap();
     {
-        let mut real_offs: Vec<flatbuffers::WIPOffset<DTT::RealVal>> = Vec::new();
-        let real_vals = get_values_res.values.real_values;
-        for (key, value) in real_vals {
-            let real_val = DTT::RealVal::create(&mut fb_builder, &DTT::RealValArgs{
-                id: key,
-                val: value
-            });
-            real_offs.push(real_val);
-        }
-        let real_vals_fb = fb_builder.create_vector(&real_offs);
-
-        let mut int_offs: Vec<flatbuffers::WIPOffset<DTT::IntVal>> = Vec::new();
-        let int_vals = get_values_res.values.int_values;
-        for (key, value) in int_vals {
-            let int_val = DTT::IntVal::create(&mut fb_builder, &DTT::IntValArgs{
-                id: key,
-                val: value
-            });
-            int_offs.push(int_val);
-        }
-        let int_vals_fb = fb_builder.create_vector(&int_offs);
-
-        let mut bool_offs: Vec<flatbuffers::WIPOffset<DTT::BoolVal>> = Vec::new();
-        let bool_vals = get_values_res.values.bool_values;
-        for (key, value) in bool_vals {
-            let bool_val = DTT::BoolVal::create(&mut fb_builder, &DTT::BoolValArgs{
-                id: key,
-                val: value
-            });
-            bool_offs.push(bool_val);
-        }
-        let bool_vals_fb = fb_builder.create_vector(&bool_offs);
-
-        let mut string_offs: Vec<flatbuffers::WIPOffset<DTT::StringVal>> = Vec::new();
-        let str_vals = get_values_res.values.string_values;
-        for (id, val) in str_vals {
-            let val_str = fb_builder.create_string(&val);
-            let str_val = DTT::StringVal::create(&mut fb_builder, &DTT::StringValArgs{
-                id: id,
-                val: Some(val_str)
-            });
-            string_offs.push(str_val);
-        }
-        let str_vals_fb = fb_builder.create_vector(&string_offs);
+        let var_vals = create_var_values(&mut fb_builder, &get_values_res.values);
 
-        let scalar_vals = DTT::VarValues::create(&mut fb_builder, &DTT::VarValuesArgs{
-            real_vals: Some(real_vals_fb), 
-            int_vals: Some(int_vals_fb),
-            bool_vals: Some(bool_vals_fb),
-            string_vals: Some(str_vals_fb)
-        });
+        let message = get_values_res.message.as_ref().map(|msg| fb_builder.create_string(msg));
 
         let get_values_res_fb = DTAPI::GetValuesRes::create(&mut fb_builder, &DTAPI::GetValuesResArgs{
             current_time: get_values_res.current_time,
-            values: Some(scalar_vals), 
-            status: get_values_res.status.into()
+            values: Some(var_vals), 
+            status: get_values_res.status.into(),
+            message,
+            error_code: get_values_res.error_code.unwrap_or(0)
         });
 
         fb_builder.finish(get_values_res_fb, None);
@@ -242,45 +271,34 @@ extern "C" fn getValues(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_le
 extern "C" fn setValues(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_len: u32) -> u32
 {
     let in_bytes = unsafe { slice::from_raw_parts(in_p, in_len as usize) };
-    let set_req = FB::root::<DTAPI::SetValuesReq>(in_bytes).unwrap();
 
-    let mut set_vals_sim = DtasmVarValues::new();
+    let set_vals_res = decode_request::<DTAPI::SetValuesReq>(in_bytes, "setValues").and_then(|set_req| {
+        let set_vals_sim = set_req.values()
+            .map(|vals| convert_var_values(&vals))
+            .unwrap_or_else(|| Ok(DtasmVarValues::new()))?;
 
-    let set_vals = set_req.values().unwrap();
-    set_vals.bool_vals().map(|bools| for boolean in bools.iter() {
-        let id = boolean.id();
-        let val = boolean.val();
-        set_vals_sim.bool_values.insert(id, val);
-    });
-    set_vals.real_vals().map(|reals| for real in reals.iter() {
-        let id = real.id();
-        let val = real.val();
-        set_vals_sim.real_values.insert(id, val);
-    });
-    set_vals.int_vals().map(|integers| for integer in integers.iter() {
-        let id = integer.id();
-        let val = integer.val();
-        set_vals_sim.int_values.insert(id, val);
-    });
-    set_vals.string_vals().map(|strings| for string in strings.iter() {
-        let id = string.id();
-        let val = string.val().unwrap().to_string();
-        set_vals_sim.string_values.insert(id, val);
-    });
+        let mut sim_module = SIM_MODULE.lock().unwrap();
+        let status = sim_module.set_values(&set_vals_sim)?;
 
-    let set_vals_res =
-    unsafe { 
-         SIM_MODULE.set_values(&set_vals_sim)
-    };
+        // notify the module about tunable parameters set between steps
+        let param_ids = tunable_parameter_ids(&set_vals_sim);
+        match param_ids.is_empty() {
+            true => Ok(status),
+            false => sim_module.parameters_changed(&param_ids)
+        }
+    });
 
     let ret_val: u32;
     let mut fb_builder = FBBUILDER.lock().unwrap();
     {
+        let (status, message, error_code) = match set_vals_res {
+            Err(err) => (DTT::Status::Error, Some(fb_builder.create_string(&err.to_string())), err.code()),
+            Ok(status) => (status.into(), None, 0)
+        };
         let status_res = DTAPI::StatusRes::create(&mut fb_builder, &DTAPI::StatusResArgs{
-            status: match set_vals_res {
-                Err(_err) => DTT::Status::Error, 
-                Ok(status) => status.into()
-            }
+            status,
+            message,
+            error_code
         });
 
         fb_builder.finish(status_res, None);
@@ -303,22 +321,40 @@ extern "C" fn setValues(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_le
 extern "C" fn doStep(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_len: u32) -> u32
 {
     let in_bytes = unsafe { slice::from_raw_parts(in_p, in_len as usize) };
-    let dostep_req = FB::root::<DTAPI::DoStepReq>(in_bytes).unwrap();
-
-    let current_time = dostep_req.current_time();
-    let step = dostep_req.timestep();
 
-    let do_step_res =
-    unsafe { 
-        SIM_MODULE.do_step(current_time, step).expect("Error on calling do_step: ")
+    let do_step_res = decode_request::<DTAPI::DoStepReq>(in_bytes, "doStep").and_then(|dostep_req|
+        SIM_MODULE.lock().unwrap().do_step(dostep_req.current_time(), dostep_req.timestep()));
+
+    // on error, the time is not advanced
+    let mut current_time = CURRENT_TIME.lock().unwrap();
+    let do_step_res = match do_step_res {
+        Ok(res) => {
+            *current_time = res.updated_time;
+            res
+        },
+        Err(err) => DoStepResponse {
+            status: Status::Error,
+            updated_time: *current_time,
+            message: Some(err.to_string()),
+            error_code: Some(err.code()),
+            event_occurred: false,
+            next_time_event: None
+        }
     };
 
     let ret_val: u32;
     let mut fb_builder = FBBUILDER.lock().unwrap();
     {
+        let message = do_step_res.message.as_ref().map(|msg| fb_builder.create_string(msg));
+
         let do_step_res_fb = DTAPI::DoStepRes::create(&mut fb_builder, &DTAPI::DoStepResArgs{
             status: do_step_res.status.into(),
-            updated_time: do_step_res.updated_time
+            updated_time: do_step_res.updated_time,
+            message,
+            error_code: do_step_res.error_code.unwrap_or(0),
+            event_occurred: do_step_res.event_occurred,
+            next_time_event: do_step_res.next_time_event.unwrap_or(0.0),
+            next_time_event_set: do_step_res.next_time_event.is_some()
         });
 
         fb_builder.finish(do_step_res_fb, None);
@@ -336,3 +372,44 @@ extern "C" fn doStep(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_len:
     fb_builder.reset();
     ret_val
 }
+
+#[no_mangle]
+extern "C" fn resetStep(in_p: *const u8, in_len: u32, out_p: *mut u8, out_max_len: u32) -> u32
+{
+    let in_bytes = unsafe { slice::from_raw_parts(in_p, in_len as usize) };
+
+    let reset_res = decode_request::<DTAPI::ResetStepReq>(in_bytes, "resetStep").and_then(|reset_req| {
+        let status = SIM_MODULE.lock().unwrap().reset_step(reset_req.current_time(), reset_req.reset_time())?;
+
+        *CURRENT_TIME.lock().unwrap() = reset_req.reset_time();
+        Ok(status)
+    });
+
+    let ret_val: u32;
+    let mut fb_builder = FBBUILDER.lock().unwrap();
+    {
+        let (status, message, error_code) = match reset_res {
+            Err(err) => (DTT::Status::Error, Some(fb_builder.create_string(&err.to_string())), err.code()),
+            Ok(status) => (status.into(), None, 0)
+        };
+        let status_res = DTAPI::StatusRes::create(&mut fb_builder, &DTAPI::StatusResArgs{
+            status,
+            message,
+            error_code
+        });
+
+        fb_builder.finish(status_res, None);
+        let buf = fb_builder.finished_data(); 
+
+        let bytes = unsafe { slice::from_raw_parts_mut(out_p, out_max_len as usize) };
+
+        if buf.len() <= out_max_len as usize {
+            bytes[..buf.len()].copy_from_slice(buf);
+        }
+
+        ret_val = buf.len() as u32;
+    }
+
+    fb_builder.reset();
+    ret_val
+}
@@ -0,0 +1,62 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Backend for the `log` crate that passes log records to the host through the
+//! `dtasm.log` import (enabled with the `log` feature). The logger is installed
+//! when the module is initialized and only passes records up to the
+//! `loglevel_limit` of the init request; dtasm has no levels below `Info`, so
+//! `debug` and `trace` records are dropped.
+
+use dtasm_abi::dtasm_generated::dtasm_types as DTT;
+use dtasm_base::types::LogLevel;
+
+#[link(wasm_import_module = "dtasm")]
+extern "C" {
+    #[link_name = "log"]
+    fn dtasm_log(level: i32, ptr: *const u8, len: i32);
+}
+
+struct DtasmLogger;
+
+static LOGGER: DtasmLogger = DtasmLogger;
+
+impl log::Log for DtasmLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let level = match record.level() {
+            log::Level::Error => DTT::LogLevel::Error,
+            log::Level::Warn => DTT::LogLevel::Warn,
+            log::Level::Info | log::Level::Debug | log::Level::Trace => DTT::LogLevel::Info
+        };
+        let message = record.args().to_string();
+
+        unsafe { dtasm_log(level.0 as i32, message.as_ptr(), message.len() as i32) };
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the logger (if not done yet) and limit it to the given level
+pub(crate) fn init(level_limit: LogLevel) {
+    // fails if a logger has been installed before, which is fine
+    let _ = log::set_logger(&LOGGER);
+
+    log::set_max_level(match level_limit {
+        LogLevel::Error => log::LevelFilter::Error,
+        LogLevel::Warn => log::LevelFilter::Warn,
+        LogLevel::Info => log::LevelFilter::Info
+    });
+}
+
+/// Report an error to the host regardless of the level limit, e.g. before the 
+/// module has been initialized
+pub(crate) fn error(message: &str) {
+    unsafe { dtasm_log(DTT::LogLevel::Error.0 as i32, message.as_ptr(), message.len() as i32) };
+}
@@ -0,0 +1,69 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+/// Register the simulation module implemented by a crate. The expression is
+/// evaluated once, on the first call of a dtasm export, and the resulting
+/// `DtasmIf` implementation serves all subsequent calls.
+///
+/// ```ignore
+/// use dtasm_rs::dtasm_module;
+///
+/// pub struct AddMod;
+///
+/// impl DtasmIf for AddMod { ... }
+///
+/// dtasm_module!(AddMod);
+/// ```
+///
+/// The macro also states the dtasm ABI version implemented by `dtasm_rs` in
+/// the `dtasm.abi_version` custom section of the module.
+///
+/// Every module crate must invoke the macro exactly once: a second invocation
+/// fails to compile because `__DTASM_CREATE_MODULE` is defined twice, while a
+/// crate without any invocation fails to link with the undefined symbol
+/// `__DTASM_CREATE_MODULE__invoke_dtasm_module_macro_exactly_once`. The 
+/// registration is a static rather than a function, as the wasm linker turns 
+/// undefined functions into imports of the module but reports undefined data 
+/// symbols as errors.
+#[macro_export]
+macro_rules! dtasm_module {
+    ($module:expr) => {
+        // the symbol name is part of the link error of crates not invoking the 
+        // macro, keep in sync with the declaration in lib.rs
+        #[doc(hidden)]
+        #[export_name = "__DTASM_CREATE_MODULE__invoke_dtasm_module_macro_exactly_once"]
+        #[used]
+        pub static __DTASM_CREATE_MODULE: fn() -> ::std::boxed::Box<dyn $crate::interface::DtasmIf + ::std::marker::Send> =
+            || ::std::boxed::Box::new($module);
+
+        #[cfg(target_arch = "wasm32")]
+        #[doc(hidden)]
+        #[used]
+        #[link_section = "dtasm.abi_version"]
+        static __DTASM_ABI_VERSION_SECTION: [u8; $crate::abi::ABI_VERSION_SECTION_CONTENTS.len()] =
+            $crate::abi::abi_version_section_bytes();
+    };
+}
+
+/// Embed a model description FlatBuffer as `dtasm.model_description` custom
+/// section of the module, which lets runtimes read the model description
+/// without instantiating the module. The path is resolved like that of
+/// `include_bytes!`, e.g. for a buffer written by the build script of the
+/// module crate:
+///
+/// ```ignore
+/// dtasm_model_description_section!("../target/modelDescription.fb");
+/// ```
+///
+/// Models using `#[derive(DtasmModel)]` embed their model description
+/// automatically. The section is only emitted when compiling for wasm32.
+#[macro_export]
+macro_rules! dtasm_model_description_section {
+    ($path:expr) => {
+        #[cfg(target_arch = "wasm32")]
+        #[doc(hidden)]
+        #[used]
+        #[link_section = "dtasm.model_description"]
+        static __DTASM_MODEL_DESCRIPTION_SECTION: [u8; include_bytes!($path).len()] = *include_bytes!($path);
+    };
+}
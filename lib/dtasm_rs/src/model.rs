@@ -0,0 +1,194 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Simulation modules from plain Rust structs. `#[derive(DtasmModel)]`
+//! generates the model description and the access to the variables, the
+//! module author implements `DtasmStep` and registers the model with
+//! `ModelAdapter`:
+//!
+//! ```ignore
+//! use dtasm_rs::{dtasm_module, DtasmModel};
+//! use dtasm_rs::model::{DtasmStep, ModelAdapter};
+//!
+//! #[derive(Default, DtasmModel)]
+//! #[dtasm(name = "Add")]
+//! pub struct Add {
+//!     #[dtasm(id = 1, causality = "input", default = 1.0)]
+//!     real_in1: f64,
+//!     #[dtasm(id = 2, causality = "input", default = 2.0)]
+//!     real_in2: f64,
+//!     #[dtasm(id = 3, causality = "output")]
+//!     real_out: f64,
+//! }
+//!
+//! impl DtasmStep for Add {
+//!     fn do_step(&mut self, _current_time: f64, _timestep: f64) -> Result<Status, DtasmError> {
+//!         self.real_out = self.real_in1 + self.real_in2;
+//!         Ok(Status::OK)
+//!     }
+//! }
+//!
+//! dtasm_module!(ModelAdapter::<Add>::new());
+//! ```
+
+use crate::interface::DtasmIf;
+use crate::types::{DoStepResponse, DtasmVarValues, GetValuesResponse, LogLevel, Status};
+use crate::model_description::ModelDescription;
+use crate::errors::DtasmError;
+
+/// Model description and variable access of a module, usually derived with
+/// `#[derive(DtasmModel)]`
+pub trait DtasmModel {
+    /// Model description encoded as FlatBuffer
+    const MODEL_DESCRIPTION: &'static [u8];
+
+    /// Assign the default values from the model description to their fields
+    fn set_defaults(&mut self);
+
+    /// Insert the current values of the requested variables into `values`
+    fn get_values(&self, var_ids: &[i32], values: &mut DtasmVarValues) -> Result<(), DtasmError>;
+
+    /// Assign the given values to their fields
+    fn set_values(&mut self, values: &DtasmVarValues) -> Result<(), DtasmError>;
+}
+
+/// Behaviour of a module implemented by the module author
+pub trait DtasmStep {
+    /// Called once after defaults and initial values have been assigned
+    ///
+    /// * `tmin` - Start time of the simulation
+    /// * `tmax` - End time of the simulation, if known
+    fn initialize(&mut self, _tmin: f64, _tmax: Option<f64>) -> Result<Status, DtasmError> {
+        Ok(Status::OK)
+    }
+
+    /// Advance the model by one step; inputs have been assigned to their fields
+    /// beforehand and outputs are read from their fields afterwards
+    ///
+    /// * `current_time` - Time at the beginning of the step
+    /// * `timestep` - Length of the step
+    fn do_step(&mut self, current_time: f64, timestep: f64) -> Result<Status, DtasmError>;
+
+    /// Time of the event at which the last step stopped early, if any; queried
+    /// after each `do_step` (the step is complete if `None`)
+    fn event_time(&mut self) -> Option<f64> {
+        None
+    }
+
+    /// Time of the next time event known to the model, if any; announced to
+    /// the runtime after each `do_step`
+    fn next_time_event(&self) -> Option<f64> {
+        None
+    }
+
+    /// Called when tunable parameters were set between steps; the new values
+    /// have already been assigned to their fields
+    ///
+    /// * `param_ids` - Ids of the tunable parameters that were set
+    fn parameters_changed(&mut self, _param_ids: &[i32]) -> Result<Status, DtasmError> {
+        Ok(Status::OK)
+    }
+
+    /// Restore the state at the beginning of the last step; only needs to be
+    /// implemented by models declaring the `can_reset_step` capability. Use
+    /// [`same_time`] to compare `reset_time` with the time of the saved state.
+    ///
+    /// * `current_time` - Time reached by the last step
+    /// * `reset_time` - Time at the beginning of the last step
+    fn reset_step(&mut self, _current_time: f64, _reset_time: f64) -> Result<Status, DtasmError> {
+        Err(DtasmError::NotImplementedError("reset_step".to_string()))
+    }
+}
+
+/// `DtasmIf` implementation for a model struct, keeping track of the
+/// simulation time
+pub struct ModelAdapter<M> {
+    model: M,
+    t: f64
+}
+
+impl<M: Default> ModelAdapter<M> {
+    pub fn new() -> ModelAdapter<M> {
+        ModelAdapter {
+            model: M::default(),
+            t: 0.0
+        }
+    }
+}
+
+impl<M> DtasmIf for ModelAdapter<M>
+    where M: DtasmModel + DtasmStep + Default {
+
+    fn get_model_description(&mut self) -> Option<&'static [u8]> {
+        Some(M::MODEL_DESCRIPTION)
+    }
+
+    fn initialize(&mut self, _md: &ModelDescription, initial_vals: &DtasmVarValues, tmin: f64, tmax: Option<f64>,
+        _tol: Option<f64>, _log_level: LogLevel, _check: bool) -> Result<Status, DtasmError> {
+
+        self.model = M::default();
+        self.model.set_defaults();
+        self.model.set_values(initial_vals)?;
+        self.t = tmin;
+
+        self.model.initialize(tmin, tmax)
+    }
+
+    fn get_values(&self, var_ids: &Vec<i32>) -> Result<GetValuesResponse, DtasmError> {
+        let mut values = DtasmVarValues::new();
+        self.model.get_values(var_ids, &mut values)?;
+
+        Ok(GetValuesResponse {
+            current_time: self.t,
+            status: Status::OK,
+            values,
+            message: None,
+            error_code: None
+        })
+    }
+
+    fn set_values(&mut self, input_vals: &DtasmVarValues) -> Result<Status, DtasmError> {
+        self.model.set_values(input_vals)?;
+
+        Ok(Status::OK)
+    }
+
+    fn parameters_changed(&mut self, param_ids: &[i32]) -> Result<Status, DtasmError> {
+        self.model.parameters_changed(param_ids)
+    }
+
+    fn do_step(&mut self, current_time: f64, timestep: f64) -> Result<DoStepResponse, DtasmError> {
+        let status = self.model.do_step(current_time, timestep)?;
+        // a discarded or failed step does not advance the time
+        let event_time = match status {
+            Status::OK | Status::Warning => {
+                let event_time = self.model.event_time();
+                self.t = event_time.unwrap_or(current_time + timestep);
+                event_time
+            },
+            Status::Discard | Status::Error => None
+        };
+
+        Ok(DoStepResponse {
+            status,
+            updated_time: self.t,
+            message: None,
+            error_code: None,
+            event_occurred: event_time.is_some(),
+            next_time_event: self.model.next_time_event()
+        })
+    }
+
+    fn reset_step(&mut self, current_time: f64, reset_time: f64) -> Result<Status, DtasmError> {
+        let status = self.model.reset_step(current_time, reset_time)?;
+        self.t = reset_time;
+
+        Ok(status)
+    }
+}
+
+/// Whether two simulation times agree up to rounding errors accumulated by
+/// adding up time steps
+pub fn same_time(t1: f64, t2: f64) -> bool {
+    (t1 - t2).abs() <= 1e-9 * t1.abs().max(t2.abs()).max(1.0)
+}
@@ -0,0 +1,11 @@
+use dtasm_rs::DtasmModel;
+
+#[derive(Default, DtasmModel)]
+pub struct Model {
+    #[dtasm(id = 1, causality = "input", default = 1.0)]
+    real_in1: f64,
+    #[dtasm(id = 1, causality = "input", default = 2.0)]
+    real_in2: f64,
+}
+
+fn main() {}
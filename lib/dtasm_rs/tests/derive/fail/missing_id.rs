@@ -0,0 +1,9 @@
+use dtasm_rs::DtasmModel;
+
+#[derive(Default, DtasmModel)]
+pub struct Model {
+    #[dtasm(causality = "input")]
+    real_in: f64,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use dtasm_rs::DtasmModel;
+
+#[derive(Default, DtasmModel)]
+pub struct Model {
+    #[dtasm(id = 1, causality = "output")]
+    count: u16,
+}
+
+fn main() {}
@@ -0,0 +1,54 @@
+use dtasm_rs::{dtasm_module, DtasmModel};
+use dtasm_rs::model::{DtasmModel, DtasmStep, ModelAdapter};
+use dtasm_rs::types::{DtasmVarValues, Status};
+use dtasm_rs::errors::DtasmError;
+
+#[derive(Default, DtasmModel)]
+#[dtasm(name = "ArraysEnums")]
+pub struct ArraysEnums {
+    #[dtasm(id = 1, causality = "input", enumeration = "Mode", items = [Off = 0, On = 1], default = 1)]
+    mode_in: i32,
+    #[dtasm(id = 2, causality = "output", enumeration = "Mode")]
+    mode_out: i32,
+    #[dtasm(id = 3, causality = "input", dimensions = [2, 3], default = 0.5)]
+    matrix: Vec<f64>,
+    #[dtasm(id = 4, causality = "output", dimensions = [2])]
+    flags: Vec<bool>,
+}
+
+impl DtasmStep for ArraysEnums {
+    fn do_step(&mut self, _current_time: f64, _timestep: f64) -> Result<Status, DtasmError> {
+        self.mode_out = self.mode_in;
+        self.flags = vec![self.mode_in == 1, self.matrix.iter().sum::<f64>() > 0.0];
+
+        Ok(Status::OK)
+    }
+}
+
+dtasm_module!(ModelAdapter::<ArraysEnums>::new());
+
+fn main() {
+    let mut model = ArraysEnums::default();
+    model.set_defaults();
+    assert_eq!(model.mode_in, 1);
+    assert_eq!(model.matrix, vec![0.5; 6]);
+    assert_eq!(model.flags, vec![false, false]);
+
+    let mut values = DtasmVarValues::new();
+    model.get_values(&[1, 3, 4], &mut values).unwrap();
+    assert_eq!(values.int_values[&1], 1);
+    assert_eq!(values.real_arrays[&3], vec![0.5; 6]);
+    assert_eq!(values.bool_arrays[&4], vec![false, false]);
+
+    let mut new_values = DtasmVarValues::new();
+    new_values.real_arrays.insert(3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    model.set_values(&new_values).unwrap();
+    assert_eq!(model.matrix[5], 6.0);
+
+    // array values must match the dimensions, scalar values are rejected for arrays
+    new_values.real_arrays.insert(3, vec![1.0]);
+    assert!(matches!(model.set_values(&new_values), Err(DtasmError::VariableDimensionMismatch(1, 3))));
+    let mut scalar_values = DtasmVarValues::new();
+    scalar_values.real_values.insert(3, 1.0);
+    assert!(matches!(model.set_values(&scalar_values), Err(DtasmError::VariableDimensionMismatch(1, 3))));
+}
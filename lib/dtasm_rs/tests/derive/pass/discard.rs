@@ -0,0 +1,41 @@
+use dtasm_rs::{dtasm_module, DtasmModel};
+use dtasm_rs::interface::DtasmIf;
+use dtasm_rs::model::{DtasmStep, ModelAdapter};
+use dtasm_rs::model_description::ModelDescription;
+use dtasm_rs::types::{DtasmVarValues, LogLevel, Status};
+use dtasm_rs::errors::DtasmError;
+
+#[derive(Default, DtasmModel)]
+#[dtasm(name = "Discard")]
+pub struct Discard {
+    #[dtasm(id = 1, causality = "input", default = 0.1)]
+    max_step: f64,
+}
+
+impl DtasmStep for Discard {
+    fn do_step(&mut self, _current_time: f64, timestep: f64) -> Result<Status, DtasmError> {
+        match timestep > self.max_step {
+            true => Ok(Status::Discard),
+            false => Ok(Status::OK)
+        }
+    }
+}
+
+dtasm_module!(ModelAdapter::<Discard>::new());
+
+fn main() {
+    let md = ModelDescription::from_json(r#"{ "model": { "name": "Discard" }, "variables": [] }"#).unwrap();
+    let mut adapter = ModelAdapter::<Discard>::new();
+    adapter.initialize(&md, &DtasmVarValues::new(), 0.0, None, None, LogLevel::Warn, false).unwrap();
+
+    let res = adapter.do_step(0.0, 0.1).unwrap();
+    assert!(matches!(res.status, Status::OK));
+    assert_eq!(res.updated_time, 0.1);
+
+    // a discarded step does not advance the time
+    let res = adapter.do_step(0.1, 0.5).unwrap();
+    assert!(matches!(res.status, Status::Discard));
+    assert_eq!(res.updated_time, 0.1);
+    assert!(!res.event_occurred);
+    assert_eq!(adapter.get_values(&vec![1]).unwrap().current_time, 0.1);
+}
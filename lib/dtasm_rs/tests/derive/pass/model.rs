@@ -0,0 +1,72 @@
+use dtasm_rs::{dtasm_module, DtasmModel};
+use dtasm_rs::model::{DtasmModel, DtasmStep, ModelAdapter};
+use dtasm_rs::types::{DtasmVarValues, Status};
+use dtasm_rs::errors::DtasmError;
+
+#[derive(Default, DtasmModel)]
+#[dtasm(name = "Model", timestep_default = 0.1, time_unit = "s", can_reset_step)]
+pub struct Model {
+    #[dtasm(id = 1, causality = "input", unit = "m", default = 1.5, min = -10.0, max = 10.0)]
+    real_in: f64,
+    #[dtasm(id = 2, causality = "parameter", variability = "tunable", default = -3)]
+    int_param: i32,
+    #[dtasm(id = 3, causality = "input", variability = "discrete", default = true)]
+    bool_in: bool,
+    #[dtasm(id = 4, name = "label.text", causality = "parameter", variability = "fixed", default = "dtasm")]
+    label: String,
+    #[dtasm(id = 5, causality = "output", default = b"\x01\x02")]
+    bytes_out: Vec<u8>,
+    #[dtasm(id = 6, causality = "output", variability = "discrete", default = 1_000_000_000_000)]
+    count: i64,
+    #[dtasm(id = 7, causality = "local", default = 0.5)]
+    ratio: f32,
+
+    steps: u32
+}
+
+impl DtasmStep for Model {
+    fn do_step(&mut self, _current_time: f64, _timestep: f64) -> Result<Status, DtasmError> {
+        self.steps += 1;
+        self.count += self.int_param as i64;
+        self.ratio = (self.real_in as f32) * self.ratio;
+        self.bytes_out = self.label.as_bytes().to_vec();
+        if !self.bool_in {
+            self.bytes_out.clear();
+        }
+
+        Ok(Status::OK)
+    }
+}
+
+dtasm_module!(ModelAdapter::<Model>::new());
+
+fn main() {
+    assert!(!Model::MODEL_DESCRIPTION.is_empty());
+
+    let mut model = Model::default();
+    model.set_defaults();
+    assert_eq!(model.real_in, 1.5);
+    assert_eq!(model.int_param, -3);
+    assert_eq!(model.label, "dtasm");
+    assert_eq!(model.bytes_out, vec![1, 2]);
+    assert_eq!(model.count, 1_000_000_000_000);
+
+    let mut values = DtasmVarValues::new();
+    model.get_values(&[1, 4, 6, 7], &mut values).unwrap();
+    assert_eq!(values.real_values[&1], 1.5);
+    assert_eq!(values.string_values[&4], "dtasm");
+    assert_eq!(values.int64_values[&6], 1_000_000_000_000);
+    assert_eq!(values.float32_values[&7], 0.5);
+
+    let mut new_values = DtasmVarValues::new();
+    new_values.real_values.insert(1, 2.5);
+    new_values.bool_values.insert(3, false);
+    model.set_values(&new_values).unwrap();
+    assert_eq!(model.real_in, 2.5);
+    assert!(!model.bool_in);
+
+    // unknown ids and values of the wrong type are rejected
+    assert!(model.get_values(&[8], &mut values).is_err());
+    new_values.int_values.insert(1, 2);
+    assert!(model.set_values(&new_values).is_err());
+}
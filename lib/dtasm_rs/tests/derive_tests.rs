@@ -0,0 +1,8 @@
+// compile tests of `#[derive(DtasmModel)]`; expected compiler errors of the
+// fail cases are stored next to them in `.stderr` files
+#[test]
+fn it_derives_dtasm_models() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/derive/pass/*.rs");
+    t.compile_fail("tests/derive/fail/*.rs");
+}
@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: MIT
 
 use std::path::PathBuf;
-use std::process::Command;
 use std::env;
+use std::fs;
+
+use dtasm_base::model_description::ModelDescription;
+use dtasm_base::model_conversion::model_description_to_bytes;
 
 fn main() {
     let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
@@ -11,39 +14,17 @@ fn main() {
     md_json_path.push("src");
     md_json_path.push("modelDescription.json");
 
-    let mut flatc_path = PathBuf::from(crate_dir.clone());
-    flatc_path.push("..");
-    flatc_path.push("..");
-    flatc_path.push("third_party");
-    flatc_path.push("flatbuffers.git");
-    flatc_path.push("flatc");
-
-    let mut fbs_path = PathBuf::from(crate_dir.clone());
-    fbs_path.push("..");
-    fbs_path.push("..");
-    fbs_path.push("lib");
-    fbs_path.push("dtasm_abi");
-    fbs_path.push("schema");
-    fbs_path.push("dtasm.fbs");
-
-    let out_path = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("target");
-    let mut out_file_tmp = out_path.clone();
-    out_file_tmp.push("modelDescription.bin");
+    println!("cargo:rerun-if-changed={}", md_json_path.display());
+
+    let md = ModelDescription::read_json(&md_json_path)
+        .expect("Failed to read model description");
+
+    let out_path = PathBuf::from(crate_dir).join("target");
+    fs::create_dir_all(&out_path).expect("Failed to create output directory");
+
     let mut out_file = out_path.clone();
     out_file.push("modelDescription.fb");
 
-    let mut convert = Command::new(flatc_path);
-    convert.arg("-b")
-        .arg("-o")
-        .arg(out_path)
-        .arg(fbs_path)
-        .arg(md_json_path)
-        .output()
-        .expect("Failed to execute command");
-
-    let mut mv = Command::new("mv");
-    mv.arg(out_file_tmp)
-        .arg(out_file)
-        .output()
-        .expect("Failed to execute command");
-}
\ No newline at end of file
+    fs::write(out_file, model_description_to_bytes(&md))
+        .expect("Failed to write model description");
+}
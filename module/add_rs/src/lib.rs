@@ -4,14 +4,14 @@
 mod types;
 
 extern crate alloc;
-use alloc::boxed::Box;
 use alloc::vec::Vec;
 use alloc::format;
 
 use std::sync::Mutex;
 
-use once_cell::sync::{OnceCell,Lazy};
+use once_cell::sync::OnceCell;
 
+use dtasm_rs::{dtasm_module, dtasm_model_description_section, same_time};
 use dtasm_rs::interface::DtasmIf;
 use dtasm_rs::model_description::{ModelDescription};
 use dtasm_rs::types::{DoStepResponse, DtasmVarValues, GetValuesResponse, Status};
@@ -19,14 +19,15 @@ use dtasm_rs::errors::DtasmError;
 
 use types::{AddState, AddVar};
 
-// TODO: encapsulate this into custom macro, e.g.
-// dtasm_module!(DpendMod)
-#[no_mangle]
-pub static mut SIM_MODULE: Lazy<Box<dyn DtasmIf + Sync + Send>> = Lazy::new(|| Box::new(AddMod));
+dtasm_module!(AddMod { prev_state: None });
+dtasm_model_description_section!("../target/modelDescription.fb");
 static ADD_STATE: OnceCell<Mutex<AddState>> = OnceCell::new();
 
 
-pub struct AddMod;
+pub struct AddMod {
+    // copy of ADD_STATE taken by do_step
+    prev_state: Option<AddState>
+}
 
 impl DtasmIf for AddMod {
     fn get_model_description(&mut self) -> Option<&'static [u8]> {
@@ -77,7 +78,9 @@ impl DtasmIf for AddMod {
         Ok(GetValuesResponse{
             current_time: state.t, 
             status: Status::OK, 
-            values: var_vals
+            values: var_vals,
+            message: None,
+            error_code: None
         })
     }
 
@@ -114,6 +117,7 @@ impl DtasmIf for AddMod {
     fn do_step(&mut self, _current_time: f64, timestep: f64) -> Result<dtasm_rs::types::DoStepResponse, dtasm_rs::errors::DtasmError> {
     
         let mut state = ADD_STATE.get().unwrap().lock().unwrap();
+        self.prev_state = Some(state.clone());
 
         let r_out = state.real_values[&AddVar::RI1] + state.real_values[&AddVar::RI2];
         state.real_values.insert(AddVar::RO, r_out);
@@ -131,10 +135,26 @@ impl DtasmIf for AddMod {
         
         let do_step_res = DoStepResponse {
             status: Status::OK, 
-            updated_time: state.t
+            updated_time: state.t,
+            message: None,
+            error_code: None,
+            event_occurred: false,
+            next_time_event: None
         }; 
 
         Ok(do_step_res)
     }
+
+    fn reset_step(&mut self, _current_time: f64, reset_time: f64) -> Result<Status, DtasmError> {
+        match &self.prev_state {
+            Some(prev_state) if same_time(prev_state.t, reset_time) => (),
+            _ => return Err(DtasmError::InvalidCallingOrder)
+        }
+
+        let mut state = ADD_STATE.get().unwrap().lock().unwrap();
+        *state = self.prev_state.take().unwrap();
+
+        Ok(Status::OK)
+    }
 } 
 
@@ -11,7 +11,7 @@ use dtasm_rs::model_description::ModelDescription;
 #[derive(Eq, PartialOrd, Ord, PartialEq, Clone, Copy, Debug)]
 pub enum AddVar {RI1, RI2, RO, II1, II2, IO, BI1, BI2, BO, SI1, SI2, SO}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AddVarMaps
 {
     pub map_id_var: BTreeMap<i32, AddVar>
@@ -25,7 +25,7 @@ impl AddVarMaps {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AddState 
 {
     pub t: f64,
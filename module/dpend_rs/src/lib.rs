@@ -1,153 +1,116 @@
 // Copyright 2021 Siemens AG
 // SPDX-License-Identifier: MIT
 
-mod types;
 mod dpend;
 
-use std::sync::Mutex;
-
-use once_cell::sync::Lazy;
-
-use dtasm_rs::interface::DtasmIf;
-use dtasm_rs::model_description::{ModelDescription};
-use dtasm_rs::types::{DoStepResponse, DtasmVarValues, GetValuesResponse, Status};
+use dtasm_rs::{dtasm_module, same_time, DtasmModel};
+use dtasm_rs::model::{DtasmStep, ModelAdapter};
+use dtasm_rs::types::Status;
 use dtasm_rs::errors::DtasmError;
 
-use types::{DpState,DpVar,create_var_maps,create_default_vals};
-
-// TODO: encapsulate this into custom macro, e.g.
-// dtasm_module!(DpendMod)
-#[no_mangle]
-pub static mut SIM_MODULE: Lazy<Box<dyn DtasmIf + Sync + Send>> = Lazy::new(|| Box::new(DpendMod));
-
-static DP_STATE: Lazy<Mutex<DpState>> = Lazy::new(|| Mutex::new(DpState::new()));
-
+dtasm_module!(ModelAdapter::<Dpend>::new());
+
+
+#[derive(Default, Clone, DtasmModel)]
+#[dtasm(name = "Double Pendulum", id = "{8ad29d6a-2525-4576-95be-e1facde6860e}",
+    description = "Double pendulum simulation by solving equations of motion using a simple Runge-Kutta scheme (http://www.physics.usyd.edu.au/~wheat/dpend_html/solve_dpend.c)",
+    generation_datetime = "2020-01-23 10:54:00 PM",
+    can_handle_variable_step_size, can_reset_step,
+    timestep_default = 0.01, starttime_default = 0.0, endtime_default = 10.0, time_unit = "s")]
+pub struct Dpend {
+    #[dtasm(id = 1, name = "theta1", causality = "output", unit = "rad",
+        description = "Angle of first joint", default = 3.14159265359)]
+    th1: f64,
+    #[dtasm(id = 2, name = "joint1.velocity", causality = "output", unit = "rad/s",
+        description = "Angular velocity of first joint", default = 0.0)]
+    w1: f64,
+    #[dtasm(id = 3, name = "joint1.acceleration", causality = "input", unit = "rad/s^2",
+        description = "Additional acceleration of first joint", default = 0.0)]
+    a1: f64,
+    #[dtasm(id = 4, name = "theta2", causality = "output", unit = "rad",
+        description = "Angle of second joint", default = 0.0174532925199)]
+    th2: f64,
+    #[dtasm(id = 5, name = "joint2.velocity", causality = "output", unit = "rad/s",
+        description = "Angular velocity of second joint", default = 0.0)]
+    w2: f64,
+    #[dtasm(id = 6, name = "joint2.acceleration", causality = "input", unit = "rad/s^2",
+        description = "Additional acceleration of second joint", default = 0.0)]
+    a2: f64,
+    #[dtasm(id = 7, name = "m1_Value", causality = "parameter", unit = "kg",
+        description = "Mass of first pendulum", default = 1.0)]
+    m1: f64,
+    #[dtasm(id = 8, name = "l1_Value", causality = "parameter", unit = "m",
+        description = "Length of first pendulum", default = 1.0)]
+    l1: f64,
+    #[dtasm(id = 9, name = "m2_Value", causality = "parameter", unit = "kg",
+        description = "Mass of second pendulum", default = 1.0)]
+    m2: f64,
+    #[dtasm(id = 10, name = "l2_Value", causality = "parameter", unit = "m",
+        description = "Length of second pendulum", default = 1.0)]
+    l2: f64,
+    #[dtasm(id = 11, name = "theta1_0_Value", causality = "parameter", unit = "rad",
+        description = "Initial angle of first pendulum")]
+    th1_0: f64,
+    #[dtasm(id = 12, name = "theta2_0_Value", causality = "parameter", unit = "rad",
+        description = "Initial angle of second pendulum")]
+    th2_0: f64,
+
+    t: f64,
+    // pendulum as of the beginning of the last step
+    prev_state: Option<Box<Dpend>>
+}
+
+impl DtasmStep for Dpend {
+    fn initialize(&mut self, tmin: f64, _tmax: Option<f64>) -> Result<Status, DtasmError> {
+        self.t = tmin;
 
-pub struct DpendMod;
-
-impl DtasmIf for DpendMod {
-    fn get_model_description(&mut self) -> Option<&'static [u8]> {
-        Some(include_bytes!("../../dpend/target/modelDescription.fb"))
+        Ok(Status::OK)
     }
 
-    fn initialize(&mut self, md: &ModelDescription, initial_vals: &dtasm_rs::types::DtasmVarValues, tmin: f64, _tmax: Option<f64>, 
-        _tol: Option<f64>, _log_level: dtasm_rs::types::LogLevel, _check: bool) -> Result<Status, DtasmError> {
-        
-        let mut state = DP_STATE.lock().unwrap();
-        state.t = tmin;
-
-        create_var_maps(&md, &mut state.var_maps);
-        create_default_vals(&md, &mut state);
-        
-        for (id, val) in &initial_vals.real_values {
-            let dp_var = state.var_maps.map_id_var.get(&id).unwrap().clone();
-            state.var_values.insert(dp_var, *val);
-        };
-        
-        if state.var_values.contains_key(&DpVar::M1){
-            state.params.m1 = *state.var_values.get(&DpVar::M1).unwrap();
-        }
-    
-        if state.var_values.contains_key(&DpVar::M2){
-            state.params.m2 = *state.var_values.get(&DpVar::M2).unwrap();
-        }
-    
-        if state.var_values.contains_key(&DpVar::L1){
-            state.params.l1 = *state.var_values.get(&DpVar::L1).unwrap();
-        }
-    
-        if state.var_values.contains_key(&DpVar::L2){
-            state.params.l2 = *state.var_values.get(&DpVar::L2).unwrap();
-        }
+    fn do_step(&mut self, current_time: f64, timestep: f64) -> Result<Status, DtasmError> {
+        self.prev_state = None;
+        self.prev_state = Some(Box::new(self.clone()));
 
-        Ok(Status::OK)
-    }
+        let mut dp_state = dpend::DpendState {
+            t: current_time,
+            th1: self.th1,
+            th2: self.th2,
+            w1: self.w1,
+            w2: self.w2
+        };
 
-    fn get_values(&self, var_ids: &Vec<i32>) -> Result<GetValuesResponse, DtasmError> {
-        let state = DP_STATE.lock().unwrap();
+        let params = dpend::DpendParams {
+            m1: self.m1,
+            m2: self.m2,
+            l1: self.l1,
+            l2: self.l2
+        };
 
-        let mut var_vals = DtasmVarValues::new();
-        let id_vals = &mut var_vals.real_values;
+        let input = dpend::DpendInput {
+            dt: timestep,
+            a1: self.a1,
+            a2: self.a2
+        };
 
-        for id in var_ids {
-            if !state.var_maps.map_id_var.contains_key(&id) {
-                return Err(DtasmError::UnknownVariableId(*id));
-            }
+        dpend::dp_step(&params, &mut dp_state, &input);
 
-            let var = state.var_maps.map_id_var[&id];
-            let val = state.var_values[&var];
-            id_vals.insert(*id, val);
-        }
+        self.t = dp_state.t;
+        self.th1 = dp_state.th1;
+        self.th2 = dp_state.th2;
+        self.w1 = dp_state.w1;
+        self.w2 = dp_state.w2;
 
-        Ok(GetValuesResponse{
-            current_time: state.t, 
-            status: Status::OK, 
-            values: var_vals
-        })
+        Ok(Status::OK)
     }
 
-    fn set_values(&mut self, _input_vals: &dtasm_rs::types::DtasmVarValues) -> Result<dtasm_rs::types::Status, dtasm_rs::errors::DtasmError> {
-        let mut state = DP_STATE.lock().unwrap();
-
-        for id in _input_vals.real_values.keys(){
-            let var = state.var_maps.map_id_var[id];
-            let val = _input_vals.real_values[id];
-            match var {
-                DpVar::A1 => { state.var_values.insert(DpVar::A1, val); },
-                DpVar::A2 => { state.var_values.insert(DpVar::A2, val); },
-                _ => ()
-            }
+    fn reset_step(&mut self, _current_time: f64, reset_time: f64) -> Result<Status, DtasmError> {
+        match &self.prev_state {
+            Some(prev_state) if same_time(prev_state.t, reset_time) => (),
+            _ => return Err(DtasmError::InvalidCallingOrder)
         }
 
-        Ok(dtasm_rs::types::Status::OK)
-    }
+        *self = *self.prev_state.take().unwrap();
 
-    fn do_step(&mut self, current_time: f64, timestep: f64) -> Result<dtasm_rs::types::DoStepResponse, dtasm_rs::errors::DtasmError> {
-    
-        let mut state = DP_STATE.lock().unwrap();
-        // if (state.t - (float)currentTime > 1.0e-5)
-        // {
-        //     // printf("Supplied timestep does not match internal state");
-        //     exit(1);
-        // }
-    
-        let mut dp_state = dpend::DpendState {
-            t: current_time, 
-            th1: state.var_values[&DpVar::TH1], 
-            th2: state.var_values[&DpVar::TH2], 
-            w1: state.var_values[&DpVar::W1], 
-            w2: state.var_values[&DpVar::W2]
-        };
-    
-        let params = dpend::DpendParams {
-            m1: state.params.m1, 
-            m2: state.params.m2, 
-            l1: state.params.l1, 
-            l2: state.params.l2
-        };
-    
-        let input = dpend::DpendInput {
-            dt: timestep, 
-            a1: state.var_values[&DpVar::A1],
-            a2: state.var_values[&DpVar::A2]
-        };
-    
-        // println!("Before: {:?}", st);
-        dpend::dp_step(&params, &mut dp_state, &input);
-        // println!("After: {:?}", st);
-    
-        state.t = dp_state.t;
-        state.var_values.insert(DpVar::TH1, dp_state.th1);
-        state.var_values.insert(DpVar::TH2, dp_state.th2);
-        state.var_values.insert(DpVar::W1, dp_state.w1);
-        state.var_values.insert(DpVar::W2, dp_state.w2);
-
-        let do_step_res = DoStepResponse {
-            status: Status::OK, 
-            updated_time: state.t
-        }; 
-
-        Ok(do_step_res)
+        Ok(Status::OK)
     }
-} 
-
+}
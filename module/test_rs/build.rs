@@ -0,0 +1,30 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+use std::path::PathBuf;
+use std::env;
+use std::fs;
+
+use dtasm_base::model_description::ModelDescription;
+use dtasm_base::model_conversion::model_description_to_bytes;
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let mut md_json_path = PathBuf::from(crate_dir.clone());
+    md_json_path.push("src");
+    md_json_path.push("modelDescription.json");
+
+    println!("cargo:rerun-if-changed={}", md_json_path.display());
+
+    let md = ModelDescription::read_json(&md_json_path)
+        .expect("Failed to read model description");
+
+    let out_path = PathBuf::from(crate_dir).join("target");
+    fs::create_dir_all(&out_path).expect("Failed to create output directory");
+
+    let mut out_file = out_path.clone();
+    out_file.push("modelDescription.fb");
+
+    fs::write(out_file, model_description_to_bytes(&md))
+        .expect("Failed to write model description");
+}
@@ -0,0 +1,104 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Module exercising the checks and error handling of the dtasmtime runtime
+//! in its tests
+
+use dtasm_rs::{dtasm_module, dtasm_model_description_section};
+use dtasm_rs::model::{DtasmModel, DtasmStep, ModelAdapter};
+use dtasm_rs::types::{DtasmVarValues, Status};
+use dtasm_rs::errors::DtasmError;
+
+#[link(wasm_import_module = "dtasm")]
+extern "C" {
+    #[link_name = "log"]
+    fn dtasm_log(level: i32, ptr: *const u8, len: i32);
+}
+
+dtasm_module!(ModelAdapter::<Test>::new());
+dtasm_model_description_section!("../target/modelDescription.fb");
+
+const MODE_IN: i32 = 1;
+const MODE_OUT: i32 = 2;
+const FAIL_STEP: i32 = 3;
+const LOG_MESSAGES: i32 = 4;
+const LOG_OUT_OF_BOUNDS: i32 = 5;
+
+
+#[derive(Default)]
+pub struct Test {
+    mode_in: i32,
+    mode_out: i32,
+    fail_step: bool,
+    log_messages: bool,
+    log_out_of_bounds: bool
+}
+
+impl DtasmModel for Test {
+    const MODEL_DESCRIPTION: &'static [u8] = include_bytes!("../target/modelDescription.fb");
+
+    fn set_defaults(&mut self) {
+        self.mode_in = 0;
+        self.mode_out = 0;
+        self.fail_step = false;
+        self.log_messages = false;
+        self.log_out_of_bounds = false;
+    }
+
+    fn get_values(&self, var_ids: &[i32], values: &mut DtasmVarValues) -> Result<(), DtasmError> {
+        for id in var_ids {
+            match *id {
+                MODE_IN => { values.int_values.insert(*id, self.mode_in); },
+                MODE_OUT => { values.int_values.insert(*id, self.mode_out); },
+                FAIL_STEP => { values.bool_values.insert(*id, self.fail_step); },
+                LOG_MESSAGES => { values.bool_values.insert(*id, self.log_messages); },
+                LOG_OUT_OF_BOUNDS => { values.bool_values.insert(*id, self.log_out_of_bounds); },
+                _ => return Err(DtasmError::UnknownVariableId(*id))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_values(&mut self, values: &DtasmVarValues) -> Result<(), DtasmError> {
+        for (id, value) in &values.int_values {
+            match *id {
+                MODE_IN => self.mode_in = *value,
+                _ => return Err(DtasmError::VariableInvalidForSet(*id))
+            }
+        }
+        for (id, value) in &values.bool_values {
+            match *id {
+                FAIL_STEP => self.fail_step = *value,
+                LOG_MESSAGES => self.log_messages = *value,
+                LOG_OUT_OF_BOUNDS => self.log_out_of_bounds = *value,
+                _ => return Err(DtasmError::VariableInvalidForSet(*id))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl DtasmStep for Test {
+    fn do_step(&mut self, current_time: f64, _timestep: f64) -> Result<Status, DtasmError> {
+        if self.fail_step {
+            return Err(DtasmError::DtasmInternalError(format!("Step at t = {} failed as requested", current_time)));
+        }
+
+        if self.log_messages {
+            log::error!("error at t = {}", current_time);
+            log::warn!("warning at t = {}", current_time);
+            log::info!("info at t = {}", current_time);
+            log::debug!("debug at t = {}", current_time);
+        }
+        if self.log_out_of_bounds {
+            // a message reaching past the end of the 32-bit address space
+            unsafe { dtasm_log(2, 0xFFFF_FF00usize as *const u8, 0x1000) };
+        }
+
+        self.mode_out = self.mode_in;
+
+        Ok(Status::OK)
+    }
+}
@@ -52,6 +52,9 @@ pub enum DtasmVariableType {
     DtasmInt,
     DtasmBool,
     DtasmString,
+    DtasmBinary,
+    DtasmInt64,
+    DtasmFloat32,
 }
 
 impl From<MD::VariableType> for DtasmVariableType {
@@ -61,6 +64,9 @@ impl From<MD::VariableType> for DtasmVariableType {
             MD::VariableType::DtasmBool => DtasmVariableType::DtasmBool,
             MD::VariableType::DtasmInt => DtasmVariableType::DtasmInt,
             MD::VariableType::DtasmString => DtasmVariableType::DtasmString,
+            MD::VariableType::DtasmBinary => DtasmVariableType::DtasmBinary,
+            MD::VariableType::DtasmInt64 => DtasmVariableType::DtasmInt64,
+            MD::VariableType::DtasmFloat32 => DtasmVariableType::DtasmFloat32,
         }
     }
 }
@@ -84,18 +90,47 @@ impl From<MD::CausalityType> for DtasmCausalityType {
     }
 }
 
+#[repr(C)]
+pub enum DtasmVariabilityType {
+    Constant,
+    Fixed,
+    Tunable,
+    Discrete,
+    Continuous,
+}
+
+impl From<MD::VariabilityType> for DtasmVariabilityType {
+    fn from(var: MD::VariabilityType) -> Self {
+        match var {
+            MD::VariabilityType::Constant => DtasmVariabilityType::Constant,
+            MD::VariabilityType::Fixed => DtasmVariabilityType::Fixed,
+            MD::VariabilityType::Tunable => DtasmVariabilityType::Tunable,
+            MD::VariabilityType::Discrete => DtasmVariabilityType::Discrete,
+            MD::VariabilityType::Continuous => DtasmVariabilityType::Continuous,
+        }
+    }
+}
+
 #[repr(C)]
 pub struct DtasmVariableValue {
     pub real_val: f64,
     pub int_val: i32,
     pub bool_val: bool,
     pub string_val: *mut c_char,
+    pub binary_val: *mut u8,
+    pub n_binary_val: size_t,
+    pub int64_val: i64,
+    pub float32_val: f32,
 }
 
 impl Default for DtasmVariableValue {
     fn default() -> Self {
         DtasmVariableValue {
             string_val: ptr::null_mut(),
+            binary_val: ptr::null_mut(),
+            n_binary_val: 0,
+            int64_val: i64::default(),
+            float32_val: f32::default(),
             bool_val: bool::default(),
             int_val: i32::default(),
             real_val: f64::default(),
@@ -113,7 +148,10 @@ pub struct DtasmModelVariable {
     pub causality: DtasmCausalityType,
     pub derivative_of_id: i32,
     pub default: DtasmVariableValue,
-    pub has_default: bool
+    pub has_default: bool,
+    pub dimensions: *mut i32,
+    pub n_dimensions: size_t,
+    pub variability: DtasmVariabilityType
 }
 
 #[repr(C)]
@@ -145,6 +183,25 @@ impl From<dtasm_base::types::Status> for DtasmStatus {
     }
 }
 
+/// Result of a C API call; apart from `DtasmtimeOk`, `DtasmtimeInvalidArgument`,
+/// `DtasmtimeEngineError` and `DtasmtimePanic` the codes mirror the variants of
+/// `DtasmtimeError`
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[repr(C)]
+pub enum DtasmtimeResult {
+    DtasmtimeOk,
+    DtasmtimeInvalidArgument,
+    DtasmtimeEngineError,
+    DtasmtimeModuleError,
+    DtasmtimeModuleTrapError,
+    DtasmtimeDtasmError,
+    DtasmtimeIoError,
+    DtasmtimeResultFormatError,
+    DtasmtimeConformanceError,
+    DtasmtimeColumnarError,
+    DtasmtimePanic,
+}
+
 #[repr(C)]
 pub enum DtasmLogLevel {
     DtasmLogError,
@@ -177,6 +234,44 @@ pub struct DtasmVarValues {
     pub string_values: *mut *mut c_char,
     pub string_ids: *mut i32,
     pub n_strings: i32,
+    // array values: elements of all arrays concatenated in `*_array_values`, 
+    // `*_array_lens` holds the number of elements of each array
+    pub real_array_values: *mut f64,
+    pub real_array_lens: *mut i32,
+    pub real_array_ids: *mut i32,
+    pub n_real_arrays: i32,
+    pub int_array_values: *mut i32,
+    pub int_array_lens: *mut i32,
+    pub int_array_ids: *mut i32,
+    pub n_int_arrays: i32,
+    pub bool_array_values: *mut bool,
+    pub bool_array_lens: *mut i32,
+    pub bool_array_ids: *mut i32,
+    pub n_bool_arrays: i32,
+    pub string_array_values: *mut *mut c_char,
+    pub string_array_lens: *mut i32,
+    pub string_array_ids: *mut i32,
+    pub n_string_arrays: i32,
+    // binary values: bytes of all values concatenated in `binary_values`, 
+    // `binary_lens` holds the number of bytes of each value
+    pub binary_values: *mut u8,
+    pub binary_lens: *mut i32,
+    pub binary_ids: *mut i32,
+    pub n_binaries: i32,
+    pub int64_values: *mut i64,
+    pub int64_ids: *mut i32,
+    pub n_int64s: i32,
+    pub float32_values: *mut f32,
+    pub float32_ids: *mut i32,
+    pub n_float32s: i32,
+    pub int64_array_values: *mut i64,
+    pub int64_array_lens: *mut i32,
+    pub int64_array_ids: *mut i32,
+    pub n_int64_arrays: i32,
+    pub float32_array_values: *mut f32,
+    pub float32_array_lens: *mut i32,
+    pub float32_array_ids: *mut i32,
+    pub n_float32_arrays: i32,
 }
 
 #[repr(C)]
@@ -190,4 +285,7 @@ pub struct DtasmGetValuesResponse {
 pub struct DtasmDoStepResponse {
     pub status: DtasmStatus,
     pub updated_time: f64,
+    pub event_occurred: bool,
+    pub next_time_event: f64,
+    pub has_next_time_event: bool,
 }
@@ -0,0 +1,93 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Error reporting of the C API: all fallible functions return a
+//! `DtasmtimeResult` and keep the message of a failure in a thread-local,
+//! panics are caught before they reach the caller.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use libc::c_char;
+
+use dtasmtime::errors::DtasmtimeError;
+
+use crate::c_types::DtasmtimeResult;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<(DtasmtimeResult, CString)>> = RefCell::new(None);
+}
+
+#[derive(Debug)]
+pub struct CApiError {
+    pub code: DtasmtimeResult,
+    pub message: String
+}
+
+impl CApiError {
+    pub fn invalid_argument(message: &str) -> CApiError {
+        CApiError { code: DtasmtimeResult::DtasmtimeInvalidArgument, message: message.to_string() }
+    }
+}
+
+impl From<DtasmtimeError> for CApiError {
+    fn from(err: DtasmtimeError) -> Self {
+        let code = match err {
+            DtasmtimeError::ModuleError(_) => DtasmtimeResult::DtasmtimeModuleError,
+            DtasmtimeError::ModuleTrapError(_) => DtasmtimeResult::DtasmtimeModuleTrapError,
+            DtasmtimeError::DtasmError(_) => DtasmtimeResult::DtasmtimeDtasmError,
+            DtasmtimeError::IoError(_) => DtasmtimeResult::DtasmtimeIoError,
+            DtasmtimeError::ResultFormatError(_) => DtasmtimeResult::DtasmtimeResultFormatError,
+            DtasmtimeError::ConformanceError(_) => DtasmtimeResult::DtasmtimeConformanceError,
+            #[cfg(feature = "columnar")]
+            DtasmtimeError::ArrowError(_) => DtasmtimeResult::DtasmtimeColumnarError,
+            #[cfg(feature = "columnar")]
+            DtasmtimeError::ParquetError(_) => DtasmtimeResult::DtasmtimeColumnarError
+        };
+
+        CApiError { code, message: err.to_string() }
+    }
+}
+
+/// Run the body of a C API function, recording its error (or panic) as last
+/// error of the calling thread
+pub fn ffi_call<F: FnOnce() -> Result<(), CApiError>>(f: F) -> DtasmtimeResult {
+    let err = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return DtasmtimeResult::DtasmtimeOk,
+        Ok(Err(err)) => err,
+        Err(payload) => CApiError { code: DtasmtimeResult::DtasmtimePanic, message: panic_message(payload) }
+    };
+
+    let message = CString::new(err.message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some((err.code, message)));
+
+    err.code
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(msg) => msg.to_string(),
+            Err(_) => "Unknown panic".to_string()
+        }
+    }
+}
+
+/// Code of the last failed call on the calling thread (`DtasmtimeOk` if no
+/// call has failed yet)
+#[no_mangle]
+pub extern "C" fn dtasmtime_last_error_code() -> DtasmtimeResult {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(DtasmtimeResult::DtasmtimeOk, |(code, _)| *code))
+}
+
+/// Message of the last failed call on the calling thread, or NULL if no call
+/// has failed yet; the string stays valid until the next call fails on this
+/// thread and must not be freed
+#[no_mangle]
+pub extern "C" fn dtasmtime_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |(_, message)| message.as_ptr()))
+}
@@ -1,9 +1,9 @@
 // Copyright 2021 Siemens AG
 // SPDX-License-Identifier: MIT
 
-use std::{ffi::{CStr, CString}, iter::FromIterator};
-use std::{mem, mem::ManuallyDrop};
+use std::ffi::{CStr, CString};
 use std::ptr;
+use std::slice;
 use std::path::PathBuf;
 use std::collections::HashMap;
 
@@ -15,90 +15,116 @@ use dtasm_base::types::{DtasmVarValues as VarValues};
 pub mod c_types;
 use c_types::*;
 
+pub mod errors;
+use errors::{ffi_call, CApiError};
 
-#[no_mangle]
-pub extern "C" fn dtasmtime_engine_new() -> *mut Engine {
-    let engine = Engine::new().expect("Could not create dtasmtime engine");
 
-    Box::into_raw(Box::new(engine))
+/// Reference to an object created by this API and passed back in by the caller
+fn object<'a, T>(ptr: *mut T, name: &str) -> Result<&'a mut T, CApiError> {
+    unsafe { ptr.as_mut() }.ok_or_else(|| CApiError::invalid_argument(&format!("Invalid {} received", name)))
 }
 
-#[no_mangle]
-pub extern "C" fn dtasmtime_engine_free(ptr: *mut Engine) {
-    if ptr.is_null() {
-        return;
-    }
-    unsafe {
-        Box::from_raw(ptr);
+/// Out-pointer receiving the result of a call
+fn out_ptr<T>(ptr: *mut T) -> Result<*mut T, CApiError> {
+    match ptr.is_null() {
+        true => Err(CApiError::invalid_argument("Invalid out-pointer received")),
+        false => Ok(ptr)
     }
 }
 
+#[no_mangle]
+pub extern "C" fn dtasmtime_engine_new(engine_out: *mut *mut Engine) -> DtasmtimeResult {
+    ffi_call(|| {
+        let out = out_ptr(engine_out)?;
+        let engine = Engine::new().map_err(|e| CApiError { 
+            code: DtasmtimeResult::DtasmtimeEngineError, 
+            message: format!("Could not create dtasmtime engine: {}", e) 
+        })?;
+
+        unsafe { out.write(Box::into_raw(Box::new(engine))) };
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dtasmtime_engine_free(ptr: *mut Engine) -> DtasmtimeResult {
+    ffi_call(|| {
+        if !ptr.is_null() {
+            unsafe { drop(Box::from_raw(ptr)); }
+        }
+        Ok(())
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn dtasmtime_module_new(filepath: *const c_char, 
-    eng_ptr: *mut Engine) -> *mut Module<'static> {
+    eng_ptr: *mut Engine, module_out: *mut *mut Module<'static>) -> DtasmtimeResult {
     
-    let engine = unsafe {
-        assert!(!eng_ptr.is_null(), "Invalid module received");
-        &mut *eng_ptr
-    };
-
-    let c_path = unsafe {
-        assert!(!filepath.is_null());
-        CStr::from_ptr(filepath)
-    };
+    ffi_call(|| {
+        let out = out_ptr(module_out)?;
+        let engine = object(eng_ptr, "engine")?;
 
-    let path = c_path.to_str().unwrap();
-    let pathbuf = PathBuf::from(path);
+        if filepath.is_null() {
+            return Err(CApiError::invalid_argument("Invalid file path received"));
+        }
+        let path = unsafe { CStr::from_ptr(filepath) }.to_str()
+            .map_err(|_| CApiError::invalid_argument("File path is not valid UTF-8"))?;
 
-    let module = Module::new(pathbuf, engine).expect("Could not load dtasm module");
-    Box::into_raw(Box::new(module))
+        let module = Module::new(PathBuf::from(path), engine)?;
+        unsafe { out.write(Box::into_raw(Box::new(module))) };
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_module_free(ptr: *mut Module) {
-    if ptr.is_null() {
-        return
-    }
-    unsafe {
-        Box::from_raw(ptr);
-    }
+pub extern "C" fn dtasmtime_module_free(ptr: *mut Module) -> DtasmtimeResult {
+    ffi_call(|| {
+        if !ptr.is_null() {
+            unsafe { drop(Box::from_raw(ptr)); }
+        }
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_module_instantiate(mod_ptr: *mut Module) -> *mut Instance {
-    let module = unsafe {
-        assert!(!mod_ptr.is_null(), "Invalid module received");
-        &mut *mod_ptr
-    };
-
-    let inst = module.instantiate().expect("Could not instantiate module");
-
-    Box::into_raw(Box::new(inst))
+pub extern "C" fn dtasmtime_module_instantiate(mod_ptr: *mut Module, instance_out: *mut *mut Instance) -> DtasmtimeResult {
+    ffi_call(|| {
+        let out = out_ptr(instance_out)?;
+        let module = object(mod_ptr, "module")?;
+
+        let inst = module.instantiate()?;
+        unsafe { out.write(Box::into_raw(Box::new(inst))) };
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_instance_free(inst_ptr: *mut Instance) {
-    if inst_ptr.is_null() {
-        return
-    }
-    unsafe {
-        Box::from_raw(inst_ptr);
-    }
+pub extern "C" fn dtasmtime_instance_free(inst_ptr: *mut Instance) -> DtasmtimeResult {
+    ffi_call(|| {
+        if !inst_ptr.is_null() {
+            unsafe { drop(Box::from_raw(inst_ptr)); }
+        }
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_modeldescription_get(inst_ptr: *mut Instance) -> DtasmModelDescription {
-    let inst = unsafe {
-        assert!(!inst_ptr.is_null(), "Invalid instance received");
-        &mut *inst_ptr
-    };
-
-    let md = inst.get_model_description().expect("Error reading model description");
+pub extern "C" fn dtasmtime_modeldescription_get(inst_ptr: *mut Instance, md_out: *mut DtasmModelDescription) -> DtasmtimeResult {
+    ffi_call(|| {
+        let out = out_ptr(md_out)?;
+        let inst = object(inst_ptr, "instance")?;
+
+        let md = inst.get_model_description()?;
+        unsafe { out.write(get_model_description_raw(&md)) };
+        Ok(())
+    })
+}
 
+fn get_model_description_raw(md: &MD::ModelDescription) -> DtasmModelDescription {
     let exp_info =
         if let Some(ei) = &md.experiment {
             DtasmExperimentInfo{
-                time_unit: CString::new(ei.time_unit.to_string()).unwrap().into_raw(), 
+                time_unit: c_string(&ei.time_unit), 
                 end_time_default: ei.end_time_default, 
                 start_time_default: ei.start_time_default, 
                 time_step_default: ei.time_step_default, 
@@ -111,12 +137,12 @@ pub extern "C" fn dtasmtime_modeldescription_get(inst_ptr: *mut Instance) -> Dta
         };
 
     let model_info = DtasmModelInfo{
-            description: CString::new(md.model.description.to_string()).unwrap().into_raw(), 
-            generation_date_time: CString::new(md.model.generation_date_time.to_string()).unwrap().into_raw(), 
-            generation_tool: CString::new(md.model.generation_tool.to_string()).unwrap().into_raw(), 
-            id: CString::new(md.model.id.to_string()).unwrap().into_raw(), 
-            name: CString::new(md.model.name.to_string()).unwrap().into_raw(),
-            name_delimiter: CString::new(md.model.name_delimiter.to_string()).unwrap().into_raw(), 
+            description: c_string(&md.model.description), 
+            generation_date_time: c_string(&md.model.generation_date_time), 
+            generation_tool: c_string(&md.model.generation_tool), 
+            id: c_string(&md.model.id), 
+            name: c_string(&md.model.name),
+            name_delimiter: c_string(&md.model.name_delimiter), 
             capabilities: DtasmCapabilities {
                 can_handle_variable_step_size: md.model.capabilities.can_handle_variable_step_size, 
                 can_interpolate_inputs: md.model.capabilities.can_interpolate_inputs, 
@@ -126,7 +152,7 @@ pub extern "C" fn dtasmtime_modeldescription_get(inst_ptr: *mut Instance) -> Dta
 
     let n_vars = md.variables.len();
 
-    let vars = get_model_variables_raw(&md);
+    let vars = get_model_variables_raw(md);
 
     DtasmModelDescription {
         experiment: exp_info, 
@@ -138,204 +164,386 @@ pub extern "C" fn dtasmtime_modeldescription_get(inst_ptr: *mut Instance) -> Dta
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_modeldescription_free(md: DtasmModelDescription) {
-    if md.has_experiment {
-        unsafe {
-            CString::from_raw(md.experiment.time_unit);
+pub extern "C" fn dtasmtime_modeldescription_free(md: DtasmModelDescription) -> DtasmtimeResult {
+    ffi_call(|| {
+        if md.has_experiment {
+            unsafe {
+                CString::from_raw(md.experiment.time_unit);
+            }
         }
-    }
-
-    unsafe {
-        CString::from_raw(md.model.description);
-        CString::from_raw(md.model.generation_date_time);
-        CString::from_raw(md.model.generation_tool);
-        CString::from_raw(md.model.id);
-        CString::from_raw(md.model.name);
-        CString::from_raw(md.model.name_delimiter);
-    }
-
-    let variables: Vec<DtasmModelVariable> = unsafe {
-        Vec::from_raw_parts(md.variables, md.n_variables, md.n_variables)
-    };
 
-    for var in variables {
         unsafe {
-            CString::from_raw(var.description);
-            CString::from_raw(var.name);
-            CString::from_raw(var.unit);
+            CString::from_raw(md.model.description);
+            CString::from_raw(md.model.generation_date_time);
+            CString::from_raw(md.model.generation_tool);
+            CString::from_raw(md.model.id);
+            CString::from_raw(md.model.name);
+            CString::from_raw(md.model.name_delimiter);
         }
 
-        if var.has_default {
+        let variables: Vec<DtasmModelVariable> = unsafe {
+            Vec::from_raw_parts(md.variables, md.n_variables, md.n_variables)
+        };
+
+        for var in variables {
             unsafe {
-                CString::from_raw(var.default.string_val);
+                CString::from_raw(var.description);
+                CString::from_raw(var.name);
+                CString::from_raw(var.unit);
+                drop(Box::from_raw(ptr::slice_from_raw_parts_mut(var.dimensions, var.n_dimensions)));
+            }
+
+            if var.has_default {
+                unsafe {
+                    CString::from_raw(var.default.string_val);
+                    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(var.default.binary_val, var.default.n_binary_val)));
+                }
             }
         }
-    }
+
+        Ok(())
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn dtasmtime_initialize(inst_ptr: *mut Instance, initial_vals: DtasmVarValues, tmin: f64, tmax_set: bool, tmax: f64, 
-    tol_set: bool, tol: f64, log_level: DtasmLogLevel, check: bool) -> DtasmStatus {
-    let inst = unsafe {
-        assert!(!inst_ptr.is_null(), "Invalid instance received");
-        &mut *inst_ptr
-    };
+    tol_set: bool, tol: f64, log_level: DtasmLogLevel, check: bool, status_out: *mut DtasmStatus) -> DtasmtimeResult {
+    ffi_call(|| {
+        let out = out_ptr(status_out)?;
+        let inst = object(inst_ptr, "instance")?;
 
-    let init_vals = cvarvalues_to_dtasmvarvalues(initial_vals);
+        let init_vals = cvarvalues_to_dtasmvarvalues(initial_vals)?;
 
-    let tmax_opt = if tmax_set { Some(tmax) } else { None };
-    let tol_opt = if tol_set { Some(tol) } else { None };
+        let tmax_opt = if tmax_set { Some(tmax) } else { None };
+        let tol_opt = if tol_set { Some(tol) } else { None };
 
-    inst.initialize(&init_vals, tmin, tmax_opt, tol_opt, log_level.into(), check).expect("initialize failed").into()
+        let res = inst.initialize(&init_vals, tmin, tmax_opt, tol_opt, log_level.into(), check)?;
+        unsafe { out.write(res.status.into()) };
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_setvalues(inst_ptr: *mut Instance, set_vals: DtasmVarValues) -> DtasmStatus {
-    let set_values = cvarvalues_to_dtasmvarvalues(set_vals);
+pub extern "C" fn dtasmtime_setvalues(inst_ptr: *mut Instance, set_vals: DtasmVarValues, status_out: *mut DtasmStatus) -> DtasmtimeResult {
+    ffi_call(|| {
+        let out = out_ptr(status_out)?;
+        let inst = object(inst_ptr, "instance")?;
 
-    let inst = unsafe {
-        assert!(!inst_ptr.is_null(), "Invalid instance received");
-        &mut *inst_ptr
-    };
+        let set_values = cvarvalues_to_dtasmvarvalues(set_vals)?;
 
-    inst.set_values(&set_values).expect("Set values failed").into()
+        let res = inst.set_values(&set_values)?;
+        unsafe { out.write(res.status.into()) };
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_getvalues(inst_ptr: *mut Instance, var_ids: *mut i32, var_count: i32) -> DtasmGetValuesResponse {
-    let inst = unsafe {
-        assert!(!inst_ptr.is_null(), "Invalid instance received");
-        &mut *inst_ptr
-    };
-
-    // clone pointed-to array in such a way that it's not freed when this function returns
-    let ids = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(var_ids, var_count as usize, var_count as usize)
-    }).clone());
-
-    let res = inst.get_values(&ids).expect("Error on invoking instance's get_values");
-
-    DtasmGetValuesResponse {
-        current_time: res.current_time, 
-        status: res.status.into(),
-        values: get_var_values_raw(&res.values)
-    }
+pub extern "C" fn dtasmtime_getvalues(inst_ptr: *mut Instance, var_ids: *const i32, var_count: i32, 
+    response_out: *mut DtasmGetValuesResponse) -> DtasmtimeResult {
+    ffi_call(|| {
+        let out = out_ptr(response_out)?;
+        let inst = object(inst_ptr, "instance")?;
+
+        let ids = c_slice(var_ids, var_count.max(0) as usize)?.to_vec();
+
+        let res = inst.get_values(&ids)?;
+
+        unsafe { out.write(DtasmGetValuesResponse {
+            current_time: res.current_time, 
+            status: res.status.into(),
+            values: get_var_values_raw(&res.values)
+        }) };
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_getvalues_free(get_values: DtasmGetValuesResponse) {
-    drop(unsafe {
-        Vec::from_raw_parts(get_values.values.real_ids, get_values.values.n_reals as usize, get_values.values.n_reals as usize)
-    });
-    drop(unsafe {
-        Vec::from_raw_parts(get_values.values.real_values, get_values.values.n_reals as usize, get_values.values.n_reals as usize)
-    });
-    drop(unsafe {
-        Vec::from_raw_parts(get_values.values.int_ids, get_values.values.n_ints as usize, get_values.values.n_ints as usize)
-    });
-    drop(unsafe {
-        Vec::from_raw_parts(get_values.values.int_values, get_values.values.n_ints as usize, get_values.values.n_ints as usize)
-    });
-    drop(unsafe {
-        Vec::from_raw_parts(get_values.values.bool_ids, get_values.values.n_bools as usize, get_values.values.n_bools as usize)
-    });
-    drop(unsafe {
-        Vec::from_raw_parts(get_values.values.bool_values, get_values.values.n_bools as usize, get_values.values.n_bools as usize)
-    });
-
-    // TODO: string variables
+pub extern "C" fn dtasmtime_getvalues_free(get_values: DtasmGetValuesResponse) -> DtasmtimeResult {
+    ffi_call(|| {
+        let vals = get_values.values;
+
+        free_c_values(vals.real_ids, vals.real_values, vals.n_reals);
+        free_c_values(vals.int_ids, vals.int_values, vals.n_ints);
+        free_c_values(vals.bool_ids, vals.bool_values, vals.n_bools);
+        free_c_values(vals.int64_ids, vals.int64_values, vals.n_int64s);
+        free_c_values(vals.float32_ids, vals.float32_values, vals.n_float32s);
+        free_c_strings(vals.string_values, vals.n_strings);
+        free_c_values(vals.string_ids, vals.string_values, vals.n_strings);
+
+        free_c_arrays(vals.real_array_ids, vals.real_array_lens, vals.real_array_values, vals.n_real_arrays);
+        free_c_arrays(vals.int_array_ids, vals.int_array_lens, vals.int_array_values, vals.n_int_arrays);
+        free_c_arrays(vals.bool_array_ids, vals.bool_array_lens, vals.bool_array_values, vals.n_bool_arrays);
+        free_c_arrays(vals.binary_ids, vals.binary_lens, vals.binary_values, vals.n_binaries);
+        free_c_arrays(vals.int64_array_ids, vals.int64_array_lens, vals.int64_array_values, vals.n_int64_arrays);
+        free_c_arrays(vals.float32_array_ids, vals.float32_array_lens, vals.float32_array_values, vals.n_float32_arrays);
+        free_c_string_arrays(vals.string_array_lens, vals.string_array_values, vals.n_string_arrays);
+        free_c_arrays(vals.string_array_ids, vals.string_array_lens, vals.string_array_values, vals.n_string_arrays);
+
+        Ok(())
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn dtasmtime_dostep(inst_ptr: *mut Instance, t: f64, dt: f64) -> DtasmDoStepResponse {
-    let inst = unsafe {
-        assert!(!inst_ptr.is_null(), "Invalid instance received");
-        &mut *inst_ptr
-    };
-
-    let dostep_res = inst.do_step(t,dt).expect("Error on invoking instance's gedo_stept_values");
-    DtasmDoStepResponse {
-        status: dostep_res.status.into(),
-        updated_time: dostep_res.updated_time
+pub extern "C" fn dtasmtime_dostep(inst_ptr: *mut Instance, t: f64, dt: f64, response_out: *mut DtasmDoStepResponse) -> DtasmtimeResult {
+    ffi_call(|| {
+        let out = out_ptr(response_out)?;
+        let inst = object(inst_ptr, "instance")?;
+
+        let dostep_res = inst.do_step(t,dt)?;
+        unsafe { out.write(DtasmDoStepResponse {
+            status: dostep_res.status.into(),
+            updated_time: dostep_res.updated_time,
+            event_occurred: dostep_res.event_occurred,
+            next_time_event: dostep_res.next_time_event.unwrap_or(0.0),
+            has_next_time_event: dostep_res.next_time_event.is_some()
+        }) };
+        Ok(())
+    })
+}
+
+fn cvarvalues_to_dtasmvarvalues(vals: DtasmVarValues) -> Result<VarValues, CApiError> {
+    let c_strings = c_values_to_map(vals.string_ids, vals.string_values, vals.n_strings)?;
+    let string_varvals = c_strings.into_iter()
+        .map(|(id, c_str)| Ok((id, c_str_to_string(c_str)?)))
+        .collect::<Result<HashMap<i32, String>, CApiError>>()?;
+
+    let c_string_arrays = c_arrays_to_map(vals.string_array_ids, vals.string_array_lens, vals.string_array_values, vals.n_string_arrays)?;
+    let string_arrays = c_string_arrays.into_iter()
+        .map(|(id, c_strs)| Ok((id, c_strs.iter().map(|&x| c_str_to_string(x)).collect::<Result<Vec<String>, CApiError>>()?)))
+        .collect::<Result<HashMap<i32, Vec<String>>, CApiError>>()?;
+
+    Ok(VarValues {
+        real_values: c_values_to_map(vals.real_ids, vals.real_values, vals.n_reals)?,
+        int_values: c_values_to_map(vals.int_ids, vals.int_values, vals.n_ints)?,
+        bool_values: c_values_to_map(vals.bool_ids, vals.bool_values, vals.n_bools)?,
+        string_values: string_varvals,
+        real_arrays: c_arrays_to_map(vals.real_array_ids, vals.real_array_lens, vals.real_array_values, vals.n_real_arrays)?,
+        int_arrays: c_arrays_to_map(vals.int_array_ids, vals.int_array_lens, vals.int_array_values, vals.n_int_arrays)?,
+        bool_arrays: c_arrays_to_map(vals.bool_array_ids, vals.bool_array_lens, vals.bool_array_values, vals.n_bool_arrays)?,
+        string_arrays,
+        binary_values: c_arrays_to_map(vals.binary_ids, vals.binary_lens, vals.binary_values, vals.n_binaries)?,
+        int64_values: c_values_to_map(vals.int64_ids, vals.int64_values, vals.n_int64s)?,
+        float32_values: c_values_to_map(vals.float32_ids, vals.float32_values, vals.n_float32s)?,
+        int64_arrays: c_arrays_to_map(vals.int64_array_ids, vals.int64_array_lens, vals.int64_array_values, vals.n_int64_arrays)?,
+        float32_arrays: c_arrays_to_map(vals.float32_array_ids, vals.float32_array_lens, vals.float32_array_values, vals.n_float32_arrays)?,
+    })
+}
+
+fn c_str_to_string(ptr: *const c_char) -> Result<String, CApiError> {
+    if ptr.is_null() {
+        return Err(CApiError::invalid_argument("Invalid string value received"));
     }
+
+    unsafe { CStr::from_ptr(ptr) }.to_str()
+        .map(|s| s.to_string())
+        .map_err(|_| CApiError::invalid_argument("String value is not valid UTF-8"))
 }
 
-fn cvarvalues_to_dtasmvarvalues(vals: DtasmVarValues) -> VarValues {
-    // clone pointed-to arrays in such a way that they're not freed when this function returns
-    let real_ids = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(vals.real_ids, vals.n_reals as usize, vals.n_reals as usize)
-    }).clone());
+/// Borrow an array passed in by the caller; NULL is accepted for empty arrays
+fn c_slice<'a, T>(ptr: *const T, n: usize) -> Result<&'a [T], CApiError> {
+    match n {
+        0 => Ok(&[]),
+        _ if ptr.is_null() => Err(CApiError::invalid_argument("Invalid values received")),
+        _ => Ok(unsafe { slice::from_raw_parts(ptr, n) })
+    }
+}
 
-    let real_vals = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(vals.real_values, vals.n_reals as usize, vals.n_reals as usize)
-    }).clone());
+/// Pair scalar values given as ids and values into a map by variable id
+fn c_values_to_map<T: Clone>(ids: *const i32, values: *const T, n: i32) -> Result<HashMap<i32, T>, CApiError> {
+    let n = n.max(0) as usize;
+    let ids = c_slice(ids, n)?;
+    let values = c_slice(values, n)?;
 
-    let real_varvals: HashMap<i32, f64> = real_ids.into_iter().zip(real_vals.into_iter()).collect();
+    Ok(ids.iter().cloned().zip(values.iter().cloned()).collect())
+}
 
-    let int_ids = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(vals.int_ids, vals.n_ints as usize, vals.n_ints as usize)
-    }).clone());
+/// Split array values given as ids, lengths and concatenated elements into one 
+/// vector per variable id
+fn c_arrays_to_map<T: Clone>(ids: *const i32, lens: *const i32, values: *const T, n: i32) -> Result<HashMap<i32, Vec<T>>, CApiError> {
+    let mut arrays: HashMap<i32, Vec<T>> = HashMap::new();
+    let n = n.max(0) as usize;
 
-    let int_vals = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(vals.int_values, vals.n_ints as usize, vals.n_ints as usize)
-    }).clone());
+    let ids = c_slice(ids, n)?;
+    let lens = c_slice(lens, n)?;
+    if lens.iter().any(|len| *len < 0) {
+        return Err(CApiError::invalid_argument("Invalid array length received"));
+    }
 
-    let int_varvals: HashMap<i32, i32> = int_ids.into_iter().zip(int_vals.into_iter()).collect();
+    let n_values: usize = lens.iter().map(|len| *len as usize).sum();
+    let values = c_slice(values, n_values)?;
 
-    let bool_ids = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(vals.bool_ids, vals.n_bools as usize, vals.n_bools as usize)
-    }).clone());
+    let mut offset = 0;
+    for (id, len) in ids.iter().zip(lens) {
+        let len = *len as usize;
+        arrays.insert(*id, values[offset..offset+len].to_vec());
+        offset += len;
+    }
 
-    let bool_vals = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(vals.bool_values, vals.n_bools as usize, vals.n_bools as usize)
-    }).clone());
+    Ok(arrays)
+}
 
-    let bool_varvals: HashMap<i32, bool> = bool_ids.into_iter().zip(bool_vals.into_iter()).collect();
+/// Split scalar values into ids and values, to be released by `free_c_values`
+fn map_to_c_values<T: Clone>(values: &HashMap<i32, T>) -> (*mut i32, *mut T, i32) {
+    let ids: Vec<i32> = values.keys().cloned().collect();
+    let vals: Vec<T> = values.values().cloned().collect();
 
-    let string_ids = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(vals.string_ids, vals.n_strings as usize, vals.n_strings as usize)
-    }).clone());
+    let n = ids.len() as i32;
+    (c_slice_into_raw(ids), c_slice_into_raw(vals), n)
+}
 
-    let c_string_vals = ManuallyDrop::into_inner(ManuallyDrop::new(unsafe {
-        Vec::from_raw_parts(vals.string_values, vals.n_strings as usize, vals.n_strings as usize)
-    }).clone());
-    
-    let string_vals: Vec<String> = c_string_vals.iter().map(|&x| unsafe { CStr::from_ptr(x).to_str()
-        .expect("String conversion error").to_string() }).collect();
+/// Flatten array values into ids, lengths and concatenated elements, to be 
+/// released by `free_c_arrays`
+fn map_to_c_arrays<T: Clone>(arrays: &HashMap<i32, Vec<T>>) -> (*mut i32, *mut i32, *mut T, i32) {
+    let mut ids: Vec<i32> = Vec::new();
+    let mut lens: Vec<i32> = Vec::new();
+    let mut values: Vec<T> = Vec::new();
+
+    for (id, val) in arrays {
+        ids.push(*id);
+        lens.push(val.len() as i32);
+        values.extend_from_slice(val);
+    }
 
-    let string_varvals: HashMap<i32, String> = string_ids.into_iter().zip(string_vals.into_iter()).collect();
+    let n = ids.len() as i32;
+    (c_slice_into_raw(ids), c_slice_into_raw(lens), c_slice_into_raw(values), n)
+}
 
-    VarValues {
-        real_values: real_varvals,
-        int_values: int_varvals,
-        bool_values: bool_varvals,
-        string_values: string_varvals,
+/// Hand a vector over to C as boxed slice, to be released by `free_c_slice`
+fn c_slice_into_raw<T>(values: Vec<T>) -> *mut T {
+    Box::into_raw(values.into_boxed_slice()) as *mut T
+}
+
+/// Release a boxed slice of `n` elements created by `c_slice_into_raw`
+unsafe fn free_c_slice<T>(ptr: *mut T, n: usize) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, n)));
+}
+
+fn free_c_values<T>(ids: *mut i32, values: *mut T, n: i32) {
+    if ids.is_null() || values.is_null() {
+        return;
+    }
+
+    unsafe {
+        free_c_slice(values, n as usize);
+        free_c_slice(ids, n as usize);
+    }
+}
+
+fn free_c_arrays<T>(ids: *mut i32, lens: *mut i32, values: *mut T, n: i32) {
+    if ids.is_null() || lens.is_null() || values.is_null() {
+        return;
+    }
+
+    unsafe {
+        let n_values: usize = slice::from_raw_parts(lens, n as usize).iter().map(|len| *len as usize).sum();
+
+        free_c_slice(values, n_values);
+        free_c_slice(lens, n as usize);
+        free_c_slice(ids, n as usize);
+    }
+}
+
+/// Release the strings of scalar values created by `map_to_c_values`; the 
+/// values themselves are released by `free_c_values`
+fn free_c_strings(values: *mut *mut c_char, n: i32) {
+    if values.is_null() {
+        return;
+    }
+
+    unsafe {
+        for string_value in slice::from_raw_parts(values, n as usize) {
+            drop(CString::from_raw(*string_value));
+        }
+    }
+}
+
+/// Release the strings of array values created by `map_to_c_arrays`; the 
+/// arrays themselves are released by `free_c_arrays`
+fn free_c_string_arrays(lens: *mut i32, values: *mut *mut c_char, n: i32) {
+    if lens.is_null() || values.is_null() {
+        return;
+    }
+
+    unsafe {
+        let n_values: usize = slice::from_raw_parts(lens, n as usize).iter().map(|len| *len as usize).sum();
+        for string_value in slice::from_raw_parts(values, n_values) {
+            drop(CString::from_raw(*string_value));
+        }
     }
 }
 
 
 fn get_var_values_raw(vals: &VarValues) -> DtasmVarValues {
-    let mut real_ids = ManuallyDrop::new(Vec::from_iter(vals.real_values.keys().cloned()).into_boxed_slice());
-    let mut real_values = ManuallyDrop::new(Vec::from_iter(vals.real_values.values().cloned()).into_boxed_slice());
-    let mut int_ids = ManuallyDrop::new(Vec::from_iter(vals.int_values.keys().cloned()).into_boxed_slice());
-    let mut int_values = ManuallyDrop::new(Vec::from_iter(vals.int_values.values().cloned()).into_boxed_slice());
-    let mut bool_ids = ManuallyDrop::new(Vec::from_iter(vals.bool_values.keys().cloned()).into_boxed_slice());
-    let mut bool_values = ManuallyDrop::new(Vec::from_iter(vals.bool_values.values().cloned()).into_boxed_slice());
+    let c_strings: HashMap<i32, *mut c_char> = vals.string_values.iter()
+        .map(|(id, s)| (*id, c_string(s)))
+        .collect();
+
+    let (real_ids, real_values, n_reals) = map_to_c_values(&vals.real_values);
+    let (int_ids, int_values, n_ints) = map_to_c_values(&vals.int_values);
+    let (bool_ids, bool_values, n_bools) = map_to_c_values(&vals.bool_values);
+    let (string_ids, string_values, n_strings) = map_to_c_values(&c_strings);
+    let (int64_ids, int64_values, n_int64s) = map_to_c_values(&vals.int64_values);
+    let (float32_ids, float32_values, n_float32s) = map_to_c_values(&vals.float32_values);
+
+    let c_string_arrays: HashMap<i32, Vec<*mut c_char>> = vals.string_arrays.iter()
+        .map(|(id, strs)| (*id, strs.iter().map(|s| c_string(s)).collect()))
+        .collect();
+
+    let (real_array_ids, real_array_lens, real_array_values, n_real_arrays) = map_to_c_arrays(&vals.real_arrays);
+    let (int_array_ids, int_array_lens, int_array_values, n_int_arrays) = map_to_c_arrays(&vals.int_arrays);
+    let (bool_array_ids, bool_array_lens, bool_array_values, n_bool_arrays) = map_to_c_arrays(&vals.bool_arrays);
+    let (string_array_ids, string_array_lens, string_array_values, n_string_arrays) = map_to_c_arrays(&c_string_arrays);
+    let (binary_ids, binary_lens, binary_values, n_binaries) = map_to_c_arrays(&vals.binary_values);
+    let (int64_array_ids, int64_array_lens, int64_array_values, n_int64_arrays) = map_to_c_arrays(&vals.int64_arrays);
+    let (float32_array_ids, float32_array_lens, float32_array_values, n_float32_arrays) = map_to_c_arrays(&vals.float32_arrays);
 
     DtasmVarValues {
-        real_ids: real_ids.as_mut_ptr(),
-        real_values: real_values.as_mut_ptr(),
-        int_ids: int_ids.as_mut_ptr(),
-        int_values: int_values.as_mut_ptr(),
-        bool_ids: bool_ids.as_mut_ptr(),
-        bool_values: bool_values.as_mut_ptr(),
-        // TODO: strings are more complicated
-        string_ids: ptr::null_mut(), 
-        string_values: ptr::null_mut(), 
-        n_reals: real_values.len() as i32,
-        n_bools: bool_values.len() as i32,
-        n_ints: int_values.len() as i32,
-        n_strings: 0
+        real_ids,
+        real_values,
+        int_ids,
+        int_values,
+        bool_ids,
+        bool_values,
+        string_ids, 
+        string_values, 
+        n_reals,
+        n_bools,
+        n_ints,
+        n_strings,
+        real_array_ids,
+        real_array_lens,
+        real_array_values,
+        n_real_arrays,
+        int_array_ids,
+        int_array_lens,
+        int_array_values,
+        n_int_arrays,
+        bool_array_ids,
+        bool_array_lens,
+        bool_array_values,
+        n_bool_arrays,
+        string_array_ids,
+        string_array_lens,
+        string_array_values,
+        n_string_arrays,
+        binary_ids,
+        binary_lens,
+        binary_values,
+        n_binaries,
+        int64_ids,
+        int64_values,
+        n_int64s,
+        float32_ids,
+        float32_values,
+        n_float32s,
+        int64_array_ids,
+        int64_array_lens,
+        int64_array_values,
+        n_int64_arrays,
+        float32_array_ids,
+        float32_array_lens,
+        float32_array_values,
+        n_float32_arrays
     }
 }
 
@@ -349,7 +557,11 @@ fn get_model_variables_raw(md: &MD::ModelDescription) -> *mut DtasmModelVariable
                     bool_val: def.bool_val, 
                     real_val: def.real_val, 
                     int_val: def.int_val, 
-                    string_val: CString::new(def.string_val.to_string()).unwrap().into_raw()
+                    string_val: c_string(&def.string_val),
+                    binary_val: Box::into_raw(def.binary_val.clone().into_boxed_slice()) as *mut u8,
+                    n_binary_val: def.binary_val.len(),
+                    int64_val: def.int64_val,
+                    float32_val: def.float32_val
                 }
             }
             else {
@@ -359,22 +571,26 @@ fn get_model_variables_raw(md: &MD::ModelDescription) -> *mut DtasmModelVariable
         let c_var = DtasmModelVariable {
             causality: var.causality.into(), 
             derivative_of_id: var.derivative_of_id, 
-            description: CString::new(var.description.to_string()).unwrap().into_raw(), 
+            description: c_string(&var.description), 
             id: var.id, 
-            name: CString::new(var.name.to_string()).unwrap().into_raw(), 
-            unit: CString::new(var.unit.to_string()).unwrap().into_raw(), 
+            name: c_string(&var.name), 
+            unit: c_string(&var.unit), 
             value_type: var.value_type.into(), 
             default: c_var_default,
-            has_default: var.default.is_some()
+            has_default: var.default.is_some(),
+            dimensions: Box::into_raw(var.dimensions.clone().into_boxed_slice()) as *mut i32,
+            n_dimensions: var.dimensions.len(),
+            variability: var.variability.into()
         };
 
         vec_vars.push(c_var);
     }
 
-    vec_vars.shrink_to_fit();
-    assert!(vec_vars.len() == vec_vars.capacity());
-    let ptr = vec_vars.as_mut_ptr();
-    mem::forget(vec_vars);
+    Box::into_raw(vec_vars.into_boxed_slice()) as *mut DtasmModelVariable
+}
 
-    ptr
+/// C string to be released with `CString::from_raw`; interior NUL bytes are 
+/// dropped as they cannot be represented
+fn c_string(s: &str) -> *mut c_char {
+    CString::new(s.replace('\0', "")).unwrap_or_default().into_raw()
 }
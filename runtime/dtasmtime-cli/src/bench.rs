@@ -0,0 +1,98 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+use dtasmtime::runtime::Module;
+use dtasmtime::model_description as MD;
+use dtasmtime::types::LogLevel;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::{create_engine, print_diagnostics};
+use crate::values::{extract_default_vals, var_ids};
+
+#[derive(Debug, StructOpt)]
+pub struct BenchOpt {
+    /// Number of steps to simulate
+    #[structopt(long, default_value = "10000")]
+    steps: u32,
+    /// Time step (default: from the model's experiment info, else 0.02)
+    #[structopt(long)]
+    dt: Option<f64>,
+    /// dtasm module (.wasm)
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+pub fn bench(opt: BenchOpt) -> Result<()> {
+    let engine = create_engine()?;
+
+    let start = Instant::now();
+    let mut dtasm_module = Module::new(opt.input, &engine)?;
+    let t_load = start.elapsed();
+
+    let start = Instant::now();
+    let mut inst = dtasm_module.instantiate()?;
+    let t_instantiate = start.elapsed();
+
+    let start = Instant::now();
+    let md = inst.get_model_description()?;
+    let t_md = start.elapsed();
+    print_diagnostics(&inst);
+
+    let (tmin, def_dt) = match &md.experiment {
+        Some(exp) => (exp.start_time_default, exp.time_step_default),
+        None => (0.0, 0.02)
+    };
+    let dt = opt.dt.unwrap_or(def_dt);
+
+    let init_vals = extract_default_vals(&md.variables,
+        &vec![ MD::CausalityType::Local, MD::CausalityType::Input ]);
+    let def_inputs = extract_default_vals(&md.variables,
+        &vec![ MD::CausalityType::Input ]);
+    let out_ids = var_ids(&md.variables,
+        &vec![ MD::CausalityType::Output, MD::CausalityType::Local ]);
+
+    let start = Instant::now();
+    inst.initialize(&init_vals, tmin, None, None, LogLevel::Warn, true)?;
+    let t_init = start.elapsed();
+
+    let mut t_set = Duration::ZERO;
+    let mut t_step = Duration::ZERO;
+    let mut t_get = Duration::ZERO;
+
+    let mut t = tmin;
+    let start_sim = Instant::now();
+    for _ in 0..opt.steps {
+        let start = Instant::now();
+        inst.set_values(&def_inputs)?;
+        t_set += start.elapsed();
+
+        let start = Instant::now();
+        let dostep_res = inst.do_step(t, dt)?;
+        t_step += start.elapsed();
+
+        let start = Instant::now();
+        inst.get_values(&out_ids)?;
+        t_get += start.elapsed();
+
+        t = dostep_res.updated_time;
+    }
+    let t_sim = start_sim.elapsed();
+
+    let n = opt.steps.max(1);
+    println!("Load module:            {:>12.3?}", t_load);
+    println!("Instantiate:            {:>12.3?}", t_instantiate);
+    println!("Get model description:  {:>12.3?}", t_md);
+    println!("Initialize:             {:>12.3?}", t_init);
+    println!("Set values (per call):  {:>12.3?}", t_set / n);
+    println!("Do step (per call):     {:>12.3?}", t_step / n);
+    println!("Get values (per call):  {:>12.3?}", t_get / n);
+    println!("Simulated {} steps in {:.3?} ({:.0} steps/s)",
+        opt.steps, t_sim, opt.steps as f64 / t_sim.as_secs_f64());
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+use dtasm_base::fmi::read_fmi_model_description;
+use dtasm_base::validation::{has_errors, validate};
+
+use anyhow::{anyhow, Result};
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+#[derive(Debug, StructOpt)]
+pub struct ImportFmiOpt {
+    /// Output file for the dtasm model description (JSON); printed to stdout if omitted
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// FMI 2.0 or 3.0 model description (modelDescription.xml)
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+pub fn import_fmi(opt: ImportFmiOpt) -> Result<()> {
+    let (md, warnings) = read_fmi_model_description(&opt.input)?;
+    for warning in &warnings {
+        eprintln!("{}", warning);
+    }
+
+    let diagnostics = validate(&md);
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if has_errors(&diagnostics) {
+        return Err(anyhow!("Converted model description is not valid"));
+    }
+
+    match opt.output {
+        Some(path) => md.write_json(path)?,
+        None => println!("{}", md.to_json()?)
+    }
+
+    Ok(())
+}
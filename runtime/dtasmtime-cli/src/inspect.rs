@@ -0,0 +1,149 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+use dtasmtime::runtime::Module;
+use dtasmtime::model_description as MD;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+use crate::create_engine;
+use crate::values::format_hex;
+
+#[derive(Debug, StructOpt)]
+pub struct InspectOpt {
+    /// Print the model description in the JSON format of `modelDescription.json` files
+    #[structopt(long)]
+    json: bool,
+    /// dtasm module (.wasm)
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+pub fn inspect(opt: InspectOpt) -> Result<()> {
+    let engine = create_engine()?;
+    let mut dtasm_module = Module::new(opt.input, &engine)?;
+    let md = dtasm_module.model_description()?;
+
+    if opt.json {
+        println!("{}", md.to_json()?);
+    }
+    else {
+        println!("ABI version:      {}", dtasm_module.abi_version());
+        print_model_description(&md);
+    }
+
+    Ok(())
+}
+
+fn print_model_description(md: &MD::ModelDescription) {
+    println!("Model:            {}", md.model.name);
+    println!("Id:               {}", md.model.id);
+    println!("Description:      {}", md.model.description);
+    println!("Generation tool:  {}", md.model.generation_tool);
+    println!("Generated:        {}", md.model.generation_date_time);
+    println!("Name delimiter:   {}", md.model.name_delimiter);
+    println!("Capabilities:     variable step size: {}, reset step: {}, interpolate inputs: {}",
+        md.model.capabilities.can_handle_variable_step_size,
+        md.model.capabilities.can_reset_step,
+        md.model.capabilities.can_interpolate_inputs);
+
+    if let Some(exp) = &md.experiment {
+        println!("Experiment:       t = [{}, {}] {}, dt = {} (min {}, max {})",
+            exp.start_time_default, exp.end_time_default, exp.time_unit,
+            exp.time_step_default, exp.time_step_min, exp.time_step_max);
+    }
+
+    for enum_type in &md.enumeration_types {
+        let items: Vec<String> = enum_type.items.iter()
+            .map(|item| format!("{}={}", item.name, item.value))
+            .collect();
+        println!("Enumeration:      {} ({})", enum_type.name, items.join(", "));
+    }
+
+    let header = ["Id", "Name", "Type", "Causality", "Unit", "Default", "Description"];
+    let mut rows: Vec<[String; 7]> = sorted_variables(md).iter()
+        .map(|v| [
+            v.id.to_string(),
+            v.name.clone(),
+            type_string(v),
+            causality_name(v.causality).to_string(),
+            v.unit.clone(),
+            default_string(v),
+            v.description.clone()
+        ])
+        .collect();
+    rows.insert(0, header.map(|h| h.to_string()));
+
+    let mut widths = [0usize; 7];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    println!();
+    for row in &rows {
+        let cells: Vec<String> = row.iter().zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+fn sorted_variables(md: &MD::ModelDescription) -> Vec<&MD::ModelVariable> {
+    let mut variables: Vec<&MD::ModelVariable> = md.variables.iter().collect();
+    variables.sort_by(|a,b| a.id.cmp(&b.id));
+
+    variables
+}
+
+fn type_name(value_type: MD::VariableType) -> &'static str {
+    match value_type {
+        MD::VariableType::DtasmReal => "DtasmReal",
+        MD::VariableType::DtasmInt => "DtasmInt",
+        MD::VariableType::DtasmBool => "DtasmBool",
+        MD::VariableType::DtasmString => "DtasmString",
+        MD::VariableType::DtasmBinary => "DtasmBinary",
+        MD::VariableType::DtasmInt64 => "DtasmInt64",
+        MD::VariableType::DtasmFloat32 => "DtasmFloat32"
+    }
+}
+
+/// Type name (or enumeration type name), followed by the dimensions for array 
+/// variables
+fn type_string(variable: &MD::ModelVariable) -> String {
+    let dims: Vec<String> = variable.dimensions.iter().map(|d| format!("[{}]", d)).collect();
+    let name = match variable.enumeration_type.is_empty() {
+        true => type_name(variable.value_type),
+        false => &variable.enumeration_type
+    };
+
+    format!("{}{}", name, dims.concat())
+}
+
+fn causality_name(causality: MD::CausalityType) -> &'static str {
+    match causality {
+        MD::CausalityType::Local => "Local",
+        MD::CausalityType::Parameter => "Parameter",
+        MD::CausalityType::Input => "Input",
+        MD::CausalityType::Output => "Output"
+    }
+}
+
+fn default_string(variable: &MD::ModelVariable) -> String {
+    match &variable.default {
+        None => String::new(),
+        Some(default) => match variable.value_type {
+            MD::VariableType::DtasmReal => default.real_val.to_string(),
+            MD::VariableType::DtasmInt => default.int_val.to_string(),
+            MD::VariableType::DtasmBool => default.bool_val.to_string(),
+            MD::VariableType::DtasmString => default.string_val.clone(),
+            MD::VariableType::DtasmBinary => format_hex(&default.binary_val),
+            MD::VariableType::DtasmInt64 => default.int64_val.to_string(),
+            MD::VariableType::DtasmFloat32 => default.float32_val.to_string()
+        }
+    }
+}
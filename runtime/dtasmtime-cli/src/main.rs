@@ -0,0 +1,61 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+mod bench;
+mod import_fmi;
+mod inspect;
+mod output;
+mod run;
+mod validate;
+mod values;
+
+use dtasmtime::runtime::{Engine, Instance, LogRecord};
+
+use anyhow::{anyhow, Result};
+use structopt::StructOpt;
+
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "dtasmtime", about = "A wasmtime-based runtime for dtasm modules")]
+enum Opt {
+    /// Print model info and variables of a dtasm module
+    Inspect(inspect::InspectOpt),
+    /// Simulate a dtasm module and record its results
+    Run(run::RunOpt),
+    /// Check that a dtasm module implements the dtasm interface correctly
+    Validate(validate::ValidateOpt),
+    /// Measure instantiation and per-call overhead of a dtasm module
+    Bench(bench::BenchOpt),
+    /// Convert an FMI 2.0 or 3.0 modelDescription.xml into a dtasm model description
+    ImportFmi(import_fmi::ImportFmiOpt),
+}
+
+
+fn main() -> Result<()> {
+
+    let opt = Opt::from_args();
+
+    match opt {
+        Opt::Inspect(opt) => inspect::inspect(opt),
+        Opt::Run(opt) => run::run(opt),
+        Opt::Validate(opt) => validate::validate(opt),
+        Opt::Bench(opt) => bench::bench(opt),
+        Opt::ImportFmi(opt) => import_fmi::import_fmi(opt),
+    }
+}
+
+fn create_engine() -> Result<Engine> {
+    Engine::new().map_err(|e| anyhow!("Could not instantiate dtasm engine: {}", e))
+}
+
+/// Print the log messages of the instance to stderr
+fn print_log_messages(inst: &mut Instance) {
+    inst.set_log_callback(Box::new(|record: &LogRecord|
+        eprintln!("{} [{:?}] {}", record.instance, record.level, record.message)));
+}
+
+fn print_diagnostics(inst: &Instance) {
+    for diagnostic in inst.model_description_diagnostics() {
+        eprintln!("Model description {}", diagnostic);
+    }
+}
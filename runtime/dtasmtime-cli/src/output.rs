@@ -0,0 +1,114 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+use dtasmtime::model_description as MD;
+use dtasmtime::types::DtasmVarValues;
+use dtasmtime::results::mat::MatResultWriter;
+use dtasmtime::results::columnar::{ArrowRecorder, ColumnarFormat};
+
+use anyhow::{anyhow, Result};
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::values::format_value;
+
+/// Destination for simulation results; the format of a result file is chosen
+/// by its extension
+pub enum ResultWriter {
+    Stdout(Vec<MD::ModelVariable>),
+    Csv(csv::Writer<File>, Vec<MD::ModelVariable>),
+    Mat(MatResultWriter),
+    Columnar(ArrowRecorder),
+}
+
+impl ResultWriter {
+    /// Open a result file (`.csv`, `.mat`, `.arrow`/`.ipc` or `.parquet`)
+    ///
+    /// * `filepath` - path of the result file
+    /// * `md` - model description of the simulated module
+    /// * `parameters` - parameter values of the simulation run
+    /// * `tmin` - start time of the simulation
+    /// * `batch_size` - number of time points per record batch for columnar formats
+    pub fn create(filepath: PathBuf, md: &MD::ModelDescription, parameters: &DtasmVarValues,
+        tmin: f64, batch_size: usize) -> Result<ResultWriter> {
+
+        let extension = filepath.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "csv" => {
+                let mut wtr = csv::Writer::from_path(filepath)?;
+                let variables = csv_variables(md);
+                wtr.write_record(header(&variables))?;
+                Ok(ResultWriter::Csv(wtr, variables))
+            },
+            "mat" => Ok(ResultWriter::Mat(MatResultWriter::create(filepath, md, parameters, tmin)?)),
+            "arrow" | "ipc" | "feather" => Ok(ResultWriter::Columnar(
+                ArrowRecorder::create(filepath, ColumnarFormat::ArrowIpc, md, batch_size)?)),
+            "parquet" => Ok(ResultWriter::Columnar(
+                ArrowRecorder::create(filepath, ColumnarFormat::Parquet, md, batch_size)?)),
+            _ => Err(anyhow!("Unsupported result file format: {}", filepath.display()))
+        }
+    }
+
+    /// Print outputs and local variables to stdout
+    pub fn stdout(md: &MD::ModelDescription) -> ResultWriter {
+        let variables = csv_variables(md);
+        println!("{:?}", header(&variables));
+        ResultWriter::Stdout(variables)
+    }
+
+    /// Write the values of one time point
+    pub fn write(&mut self, var_values: &DtasmVarValues, t: f64) -> Result<()> {
+        match self {
+            ResultWriter::Stdout(variables) => {
+                println!("{:?}", record(variables, var_values, t));
+            },
+            ResultWriter::Csv(wtr, variables) => {
+                wtr.write_record(record(variables, var_values, t))?;
+            },
+            ResultWriter::Mat(wtr) => wtr.write_record(var_values, t)?,
+            ResultWriter::Columnar(recorder) => recorder.record(var_values, t)?
+        };
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        match self {
+            ResultWriter::Stdout(_) => {},
+            ResultWriter::Csv(mut wtr, _) => wtr.flush()?,
+            ResultWriter::Mat(wtr) => wtr.finish()?,
+            ResultWriter::Columnar(recorder) => recorder.finish()?
+        };
+
+        Ok(())
+    }
+}
+
+fn csv_variables(md: &MD::ModelDescription) -> Vec<MD::ModelVariable> {
+    let mut variables: Vec<MD::ModelVariable> = md.variables.iter()
+        .filter(|v| v.causality == MD::CausalityType::Output || v.causality == MD::CausalityType::Local)
+        .cloned()
+        .collect();
+    variables.sort_by(|a,b| a.id.cmp(&b.id));
+
+    variables
+}
+
+fn header(variables: &Vec<MD::ModelVariable>) -> Vec<String> {
+    let mut header: Vec<String> = vec!["t".to_string()];
+    header.extend(variables.iter().map(|v| v.name.to_string()));
+
+    header
+}
+
+fn record(variables: &Vec<MD::ModelVariable>, var_values: &DtasmVarValues, t: f64) -> Vec<String> {
+    let mut line: Vec<String> = vec![format!("{:.8}", t)];
+    line.extend(variables.iter().map(|v| format_value(v, var_values)));
+
+    line
+}
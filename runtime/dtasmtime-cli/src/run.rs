@@ -0,0 +1,159 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+use dtasmtime::runtime::{Instance, Module};
+use dtasmtime::model_description as MD;
+use dtasmtime::types::LogLevel;
+use dtasmtime::bounds::BoundsPolicy;
+use dtasmtime::stepping::Stepper;
+
+use anyhow::{anyhow, Result};
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+use crate::{create_engine, print_diagnostics, print_log_messages};
+use crate::output::ResultWriter;
+use crate::values::{extract_default_vals, parse_cmd_parameters, update_var_values, var_ids};
+
+#[derive(Debug, StructOpt)]
+pub struct RunOpt {
+    /// Start time (default: from the model's experiment info, else 0)
+    #[structopt(long)]
+    tmin: Option<f64>,
+    /// End time (default: from the model's experiment info, else 10)
+    #[structopt(long)]
+    tmax: Option<f64>,
+    /// Time step (default: from the model's experiment info, else 0.02)
+    #[structopt(long)]
+    dt: Option<f64>,
+    /// Result file; the format is chosen by extension (.csv, .mat, .arrow/.ipc, .parquet).
+    /// May be given multiple times. Results are printed to stdout if omitted.
+    #[structopt(short, long = "output", parse(from_os_str))]
+    outputs: Vec<PathBuf>,
+    /// Number of time points per record batch for .arrow and .parquet results
+    #[structopt(long, default_value = "1024")]
+    batch_size: usize,
+    /// Save the module state to this file after the simulation
+    #[structopt(long, parse(from_os_str))]
+    state_to: Option<PathBuf>,
+    /// Load the module state from this file before the simulation
+    #[structopt(long, parse(from_os_str))]
+    state_from: Option<PathBuf>,
+    /// Treatment of values outside the bounds of their variable (reject, clamp or warn)
+    #[structopt(long, default_value = "reject", parse(try_from_str = parse_bounds_policy))]
+    bounds: BoundsPolicy,
+    /// dtasm module (.wasm)
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+    /// Variable values as `name=value`
+    parameters: Vec<String>
+}
+
+pub fn run(opt: RunOpt) -> Result<()> {
+    let engine = create_engine()?;
+    let mut dtasm_module = Module::new(opt.input, &engine)?;
+    let mut inst = dtasm_module.instantiate()?;
+    print_log_messages(&mut inst);
+    inst.set_bounds_policy(opt.bounds);
+
+    let md = inst.get_model_description()?;
+    print_diagnostics(&inst);
+
+    let (def_tmin, def_tmax, def_dt) = match &md.experiment {
+        Some(exp) => (exp.start_time_default, exp.end_time_default, exp.time_step_default),
+        None => (0.0, 10.0, 0.02)
+    };
+    let tmin = opt.tmin.unwrap_or(def_tmin);
+    let tmax = opt.tmax.unwrap_or(def_tmax);
+    let dt = opt.dt.unwrap_or(def_dt);
+
+    let mut t = tmin;
+
+    let mut init_vals = extract_default_vals(&md.variables,
+        &vec![ MD::CausalityType::Local, MD::CausalityType::Input ]);
+
+    let def_inputs = extract_default_vals(&md.variables,
+        &vec![ MD::CausalityType::Input ]);
+
+    let cmd_vals = parse_cmd_parameters(&opt.parameters, &md)?;
+    update_var_values(&mut init_vals, &cmd_vals);
+
+    let mut param_vals = extract_default_vals(&md.variables,
+        &vec![ MD::CausalityType::Parameter ]);
+    update_var_values(&mut param_vals, &cmd_vals);
+
+    let mut writers: Vec<ResultWriter> = Vec::new();
+    for output in opt.outputs {
+        writers.push(ResultWriter::create(output, &md, &param_vals, tmin, opt.batch_size)?);
+    }
+    if writers.is_empty() {
+        writers.push(ResultWriter::stdout(&md));
+    }
+
+    let init_res = inst.initialize(&init_vals, tmin, Some(tmax), None, LogLevel::Warn, true)?;
+    if let Some(message) = init_res.message {
+        eprintln!("init returned status {:?}: {}", init_res.status, message);
+    }
+    print_bounds_violations(&inst);
+
+    if let Some(state_from) = opt.state_from {
+        inst.load_state(state_from.clone())?;
+        t = inst.get_values(&Vec::new())?.current_time;
+        eprintln!("Loaded state from {}", state_from.display());
+    }
+
+    let out_ids = var_ids(&md.variables,
+        &vec![ MD::CausalityType::Output, MD::CausalityType::Local ]);
+
+    let mut get_vals = inst.get_values(&out_ids)?;
+    update_var_values(&mut get_vals.values, &def_inputs);
+    for writer in writers.iter_mut() {
+        writer.write(&get_vals.values, get_vals.current_time)?;
+    }
+
+    // steps end early at time events announced by the module
+    let mut stepper = Stepper::new(dt);
+    while t + 0.5 * dt < tmax {
+        inst.set_values(&def_inputs)?;
+        print_bounds_violations(&inst);
+        let dostep_res = stepper.do_step(&mut inst, t)?;
+        if let Some(message) = &dostep_res.message {
+            eprintln!("doStep at t = {} returned status {:?}: {}", t, dostep_res.status, message);
+        }
+        get_vals = inst.get_values(&out_ids)?;
+        update_var_values(&mut get_vals.values, &def_inputs);
+        for writer in writers.iter_mut() {
+            writer.write(&get_vals.values, get_vals.current_time)?;
+        }
+        t = dostep_res.updated_time;
+    }
+
+    for writer in writers {
+        writer.finish()?;
+    }
+
+    if let Some(state_to) = opt.state_to {
+        inst.save_state(state_to.clone())?;
+        eprintln!("Wrote state to {}", state_to.display());
+    }
+
+    Ok(())
+}
+
+/// Print the values outside their bounds that were accepted or clamped by the last
+/// `initialize` or `set_values` call
+fn print_bounds_violations(inst: &Instance) {
+    for violation in inst.bounds_violations() {
+        eprintln!("Warning: {}", violation);
+    }
+}
+
+fn parse_bounds_policy(policy: &str) -> Result<BoundsPolicy> {
+    match policy {
+        "reject" => Ok(BoundsPolicy::Reject),
+        "clamp" => Ok(BoundsPolicy::Clamp),
+        "warn" => Ok(BoundsPolicy::Warn),
+        _ => Err(anyhow!("Unknown bounds policy: {}", policy))
+    }
+}
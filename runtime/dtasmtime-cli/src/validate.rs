@@ -0,0 +1,34 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+use dtasmtime::runtime::Module;
+use dtasmtime::conformance::check_module;
+
+use anyhow::{anyhow, Result};
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+use crate::create_engine;
+
+#[derive(Debug, StructOpt)]
+pub struct ValidateOpt {
+    /// dtasm module (.wasm)
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+pub fn validate(opt: ValidateOpt) -> Result<()> {
+    let engine = create_engine()?;
+    let mut dtasm_module = Module::new(opt.input, &engine)?;
+
+    let report = check_module(&mut dtasm_module);
+    println!("{}", report);
+
+    if report.passed() {
+        Ok(())
+    }
+    else {
+        Err(anyhow!("Module does not conform to the dtasm ABI"))
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+use dtasmtime::model_description as MD;
+use dtasmtime::types::DtasmVarValues;
+
+use anyhow::{anyhow, Result};
+
+use std::collections::HashMap;
+
+pub fn extract_default_vals(vars: &Vec<MD::ModelVariable>, causalities: &Vec<MD::CausalityType>) -> DtasmVarValues {
+    let mut default_vals = DtasmVarValues::new();
+
+    for variable in vars {
+        if !causalities.contains(&variable.causality) {
+            continue;
+        }
+
+        match &variable.default {
+            None => {},
+            Some(default) => default_vals.insert_default(variable, default)
+        };
+    }
+
+    default_vals
+}
+
+/// Parse `name=value` pairs given on the command line into values of the
+/// corresponding variables; binary values are given as hex strings, values of
+/// enumeration variables by item name or value
+pub fn parse_cmd_parameters(params: &Vec<String>, md: &MD::ModelDescription) -> Result<DtasmVarValues> {
+    let mut kv_pairs: HashMap<String, String> = HashMap::new();
+    let mut id_vals = DtasmVarValues::new();
+
+    for kv_str in params {
+        let kv_vec: Vec<&str> = kv_str.splitn(2, '=').collect();
+        if kv_vec.len() != 2 {
+            return Err(anyhow!("Invalid parameter format: {}", kv_str));
+        }
+
+        kv_pairs.insert(kv_vec[0].to_string(), kv_vec[1].to_string());
+    }
+
+    for variable in &md.variables {
+        if let Some(val_str) = kv_pairs.remove(&variable.name) {
+            let parse_err = || anyhow!("Could not parse value `{}` for variable {}", val_str, variable.name);
+
+            match variable.value_type {
+                MD::VariableType::DtasmReal => {
+                    let val: f64 = val_str.parse().map_err(|_| parse_err())?;
+                    id_vals.real_values.insert(variable.id, val);
+                },
+                MD::VariableType::DtasmInt => {
+                    let val: i32 = match md.enumeration_type(variable) {
+                        Some(enum_type) => enum_type.item_value(&val_str)
+                            .or_else(|| val_str.parse().ok())
+                            .ok_or_else(parse_err)?,
+                        None => val_str.parse().map_err(|_| parse_err())?
+                    };
+                    id_vals.int_values.insert(variable.id, val);
+                },
+                MD::VariableType::DtasmBool => {
+                    let val: bool = val_str.parse().map_err(|_| parse_err())?;
+                    id_vals.bool_values.insert(variable.id, val);
+                },
+                MD::VariableType::DtasmString => {
+                    id_vals.string_values.insert(variable.id, val_str.to_string());
+                },
+                MD::VariableType::DtasmBinary => {
+                    let val = parse_hex(&val_str).ok_or_else(parse_err)?;
+                    id_vals.binary_values.insert(variable.id, val);
+                },
+                MD::VariableType::DtasmInt64 => {
+                    let val: i64 = val_str.parse().map_err(|_| parse_err())?;
+                    id_vals.int64_values.insert(variable.id, val);
+                },
+                MD::VariableType::DtasmFloat32 => {
+                    let val: f32 = val_str.parse().map_err(|_| parse_err())?;
+                    id_vals.float32_values.insert(variable.id, val);
+                }
+            }
+        }
+    }
+
+    if let Some(name) = kv_pairs.keys().next() {
+        return Err(anyhow!("Unknown variable: {}", name));
+    }
+
+    Ok(id_vals)
+}
+
+pub fn update_var_values(values: &mut DtasmVarValues, other: &DtasmVarValues) {
+    for (id, val) in &other.real_values {
+        values.real_values.insert(*id, *val);
+    }
+    for (id, val) in &other.int_values {
+        values.int_values.insert(*id, *val);
+    }
+    for (id, val) in &other.bool_values {
+        values.bool_values.insert(*id, *val);
+    }
+    for (id, val) in &other.string_values {
+        values.string_values.insert(*id, val.clone());
+    }
+    for (id, val) in &other.real_arrays {
+        values.real_arrays.insert(*id, val.clone());
+    }
+    for (id, val) in &other.int_arrays {
+        values.int_arrays.insert(*id, val.clone());
+    }
+    for (id, val) in &other.bool_arrays {
+        values.bool_arrays.insert(*id, val.clone());
+    }
+    for (id, val) in &other.string_arrays {
+        values.string_arrays.insert(*id, val.clone());
+    }
+    for (id, val) in &other.binary_values {
+        values.binary_values.insert(*id, val.clone());
+    }
+    for (id, val) in &other.int64_values {
+        values.int64_values.insert(*id, *val);
+    }
+    for (id, val) in &other.float32_values {
+        values.float32_values.insert(*id, *val);
+    }
+    for (id, val) in &other.int64_arrays {
+        values.int64_arrays.insert(*id, val.clone());
+    }
+    for (id, val) in &other.float32_arrays {
+        values.float32_arrays.insert(*id, val.clone());
+    }
+}
+
+/// Ids of all variables with one of the given causalities, sorted by id
+pub fn var_ids(vars: &Vec<MD::ModelVariable>, causalities: &Vec<MD::CausalityType>) -> Vec<i32> {
+    let mut ids: Vec<i32> = vars.iter()
+        .filter(|v| causalities.contains(&v.causality))
+        .map(|v| v.id)
+        .collect();
+    ids.sort();
+
+    ids
+}
+
+pub fn format_value(variable: &MD::ModelVariable, values: &DtasmVarValues) -> String {
+    let id = &variable.id;
+
+    let value = match (variable.value_type, variable.is_array()) {
+        (MD::VariableType::DtasmReal, false) => values.real_values.get(id).map(|v| format!("{:.8}", v)),
+        (MD::VariableType::DtasmInt, false) => values.int_values.get(id).map(|v| v.to_string()),
+        (MD::VariableType::DtasmBool, false) => values.bool_values.get(id).map(|v| v.to_string()),
+        (MD::VariableType::DtasmString, false) => values.string_values.get(id).cloned(),
+        (MD::VariableType::DtasmReal, true) => values.real_arrays.get(id).map(|v| format_array(v.iter().map(|x| format!("{:.8}", x)))),
+        (MD::VariableType::DtasmInt, true) => values.int_arrays.get(id).map(|v| format_array(v.iter().map(|x| x.to_string()))),
+        (MD::VariableType::DtasmBool, true) => values.bool_arrays.get(id).map(|v| format_array(v.iter().map(|x| x.to_string()))),
+        (MD::VariableType::DtasmString, true) => values.string_arrays.get(id).map(|v| format_array(v.iter().cloned())),
+        (MD::VariableType::DtasmBinary, _) => values.binary_values.get(id).map(|v| format_hex(v)),
+        (MD::VariableType::DtasmInt64, false) => values.int64_values.get(id).map(|v| v.to_string()),
+        (MD::VariableType::DtasmFloat32, false) => values.float32_values.get(id).map(|v| format!("{:.8}", v)),
+        (MD::VariableType::DtasmInt64, true) => values.int64_arrays.get(id).map(|v| format_array(v.iter().map(|x| x.to_string()))),
+        (MD::VariableType::DtasmFloat32, true) => values.float32_arrays.get(id).map(|v| format_array(v.iter().map(|x| format!("{:.8}", x))))
+    };
+
+    value.unwrap_or_default()
+}
+
+fn format_array(elements: impl Iterator<Item = String>) -> String {
+    format!("[{}]", elements.collect::<Vec<String>>().join(" "))
+}
+
+/// Lowercase hex representation of a binary value
+pub fn format_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..hex.len()).step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    /// Model description of the test_rs module, whose `mode_in` input is of
+    /// enumeration type `Mode` with items `Off` (0) and `On` (1)
+    fn test_model_description() -> MD::ModelDescription {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../../module/test_rs/src/modelDescription.json");
+
+        MD::ModelDescription::read_json(&path).expect("Failed to read model description")
+    }
+
+    fn parse_mode(val_str: &str) -> Result<i32> {
+        let md = test_model_description();
+        let values = parse_cmd_parameters(&vec![format!("mode_in={}", val_str)], &md)?;
+
+        Ok(values.int_values[&1])
+    }
+
+    #[test]
+    fn it_parses_enumeration_items_by_name() {
+        assert_eq!(parse_mode("Off").unwrap(), 0);
+        assert_eq!(parse_mode("On").unwrap(), 1);
+    }
+
+    #[test]
+    fn it_parses_enumeration_items_by_value() {
+        assert_eq!(parse_mode("1").unwrap(), 1);
+    }
+
+    #[test]
+    fn it_rejects_unknown_enumeration_item_names() {
+        let err = parse_mode("Standby").unwrap_err();
+
+        assert!(err.to_string().contains("Could not parse value `Standby` for variable mode_in"));
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Packager turning a dtasm module into an FMI 2.0 co-simulation FMU for
+//! Linux x86_64: the FMU contains the generated `modelDescription.xml`, the
+//! FMI library of this crate as `binaries/linux64/<modelIdentifier>.so` and
+//! the module as `resources/model.wasm`.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use structopt::StructOpt;
+use zip::write::FileOptions;
+
+use dtasmtime::runtime::{Engine, Module};
+use dtasmtime_fmi2::model_description_xml::{guid, model_description_xml, model_identifier, unsupported_variables};
+use dtasmtime_fmi2::MODULE_RESOURCE;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "dtasm2fmu", about = "Package a dtasm module as FMI 2.0 co-simulation FMU")]
+struct Opt {
+    /// FMI library built from the dtasmtime-fmi2 crate (defaults to
+    /// libdtasmtime_fmi2.so next to this executable)
+    #[structopt(long, parse(from_os_str))]
+    binary: Option<PathBuf>,
+    /// Output file (defaults to <modelIdentifier>.fmu)
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// dtasm module (.wasm)
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::from_args();
+
+    let wasm = std::fs::read(&opt.input)?;
+    let engine = Engine::new().map_err(|e| anyhow!(e.to_string()))?;
+    let md = Module::new(opt.input.clone(), &engine)?.model_description()?;
+
+    for var in unsupported_variables(&md) {
+        eprintln!("Warning: variable `{}` (id {}) cannot be represented in FMI 2.0 and is left out", var.name, var.id);
+    }
+
+    let identifier = model_identifier(&md);
+    let xml = model_description_xml(&md, &identifier, &guid(&wasm));
+
+    let binary_path = match opt.binary {
+        Some(path) => path,
+        None => std::env::current_exe()?.with_file_name("libdtasmtime_fmi2.so")
+    };
+    if !binary_path.is_file() {
+        bail!("FMI library `{}` not found, build the dtasmtime-fmi2 crate or pass --binary", binary_path.display());
+    }
+    let binary = std::fs::read(&binary_path)?;
+
+    let output = opt.output.unwrap_or_else(|| PathBuf::from(format!("{}.fmu", identifier)));
+    let mut fmu = zip::ZipWriter::new(File::create(&output)?);
+    let options = FileOptions::default();
+
+    fmu.start_file("modelDescription.xml", options)?;
+    fmu.write_all(xml.as_bytes())?;
+    fmu.start_file(format!("binaries/linux64/{}.so", identifier), options.unix_permissions(0o755))?;
+    fmu.write_all(&binary)?;
+    fmu.start_file(format!("resources/{}", MODULE_RESOURCE), options)?;
+    fmu.write_all(&wasm)?;
+    fmu.finish()?;
+
+    println!("Wrote {}", output.display());
+
+    Ok(())
+}
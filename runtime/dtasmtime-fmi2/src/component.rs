@@ -0,0 +1,399 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! An FMU instance: a dtasm instance together with the state of the FMI 2.0
+//! co-simulation state machine. Values set before the end of initialization
+//! are collected and passed to the `init` call of the module in
+//! `fmi2ExitInitializationMode`; until then, getters return these values or
+//! the defaults of the variables.
+
+use std::ffi::CString;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use libc::c_char;
+
+use dtasmtime::model_description as MD;
+use dtasmtime::runtime::{Engine, Instance, LogRecord, Module, Snapshot};
+use dtasmtime::types::{DtasmVarValues, LogLevel, Status};
+
+use crate::fmi2_types::*;
+use crate::model_description_xml::{guid, is_fmi2_variable};
+
+/// File name of the wrapped module in the `resources` directory of the FMU
+pub const MODULE_RESOURCE: &str = "model.wasm";
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub(crate) enum ComponentState {
+    Instantiated,
+    Initialization,
+    Stepping,
+    Terminated
+}
+
+/// State of a component as stored by `fmi2GetFMUstate`
+pub(crate) struct FmuState {
+    snapshot: Snapshot,
+    state: ComponentState,
+    time: f64,
+    initial_vals: DtasmVarValues
+}
+
+pub(crate) struct Component {
+    instance_name: CString,
+    callbacks: fmi2CallbackFunctions,
+    logging_on: bool,
+    inst: Instance,
+    md: MD::ModelDescription,
+    state: ComponentState,
+    start_time: f64,
+    stop_time: Option<f64>,
+    tolerance: Option<f64>,
+    time: f64,
+    initial_vals: DtasmVarValues,
+    initial_snapshot: Snapshot,
+    /// Strings returned by the last `fmi2GetString` call
+    strings: Vec<CString>
+}
+
+/// Forwards log messages of the module to the logger callback of the environment
+struct ModuleLogger {
+    callbacks: fmi2CallbackFunctions,
+    instance_name: CString
+}
+
+// the environment is responsible for the callbacks being usable from the
+// thread the component is used on
+unsafe impl Send for ModuleLogger {}
+
+impl Component {
+    /// Load the module from the resources of the FMU and instantiate it
+    ///
+    /// * `instance_name` - name of the instance given by the environment
+    /// * `resource_location` - URI of the `resources` directory of the FMU
+    /// * `fmu_guid` - GUID of the model description the environment read
+    /// * `callbacks` - callback functions of the environment
+    /// * `logging_on` - whether debug logging is enabled
+    pub(crate) fn new(instance_name: &str, resource_location: &str, fmu_guid: &str,
+        callbacks: fmi2CallbackFunctions, logging_on: bool) -> Result<Component> {
+
+        let wasm_path = resource_dir(resource_location).join(MODULE_RESOURCE);
+        let wasm = std::fs::read(&wasm_path)?;
+        if guid(&wasm) != fmu_guid {
+            bail!("GUID `{}` does not match the module `{}`", fmu_guid, wasm_path.display());
+        }
+
+        let engine = Engine::new().map_err(|e| anyhow!(e.to_string()))?;
+        let mut module = Module::new(wasm_path, &engine)?;
+        let mut inst = module.instantiate()?;
+        let md = inst.get_model_description()?;
+
+        let instance_name = CString::new(instance_name)?;
+        inst.set_name(&instance_name.to_string_lossy());
+        let logger = ModuleLogger { callbacks, instance_name: instance_name.clone() };
+        inst.set_log_callback(Box::new(move |record: &LogRecord| logger.log(record)));
+
+        let initial_snapshot = inst.snapshot();
+
+        Ok(Component {
+            instance_name,
+            callbacks,
+            logging_on,
+            inst,
+            md,
+            state: ComponentState::Instantiated,
+            start_time: 0.0,
+            stop_time: None,
+            tolerance: None,
+            time: 0.0,
+            initial_vals: DtasmVarValues::new(),
+            initial_snapshot,
+            strings: Vec::new()
+        })
+    }
+
+    /// Report a message through the logger callback of the environment
+    pub(crate) fn log(&self, status: fmi2Status, category: &str, message: &str) {
+        log(&self.callbacks, &self.instance_name, status, category, message);
+    }
+
+    pub(crate) fn set_debug_logging(&mut self, logging_on: bool) {
+        self.logging_on = logging_on;
+    }
+
+    pub(crate) fn setup_experiment(&mut self, tolerance: Option<f64>, start_time: f64, stop_time: Option<f64>) -> Result<fmi2Status> {
+        self.expect_state(&[ComponentState::Instantiated], "fmi2SetupExperiment")?;
+
+        self.tolerance = tolerance;
+        self.start_time = start_time;
+        self.stop_time = stop_time;
+        self.time = start_time;
+
+        Ok(fmi2Status::fmi2OK)
+    }
+
+    pub(crate) fn enter_initialization_mode(&mut self) -> Result<fmi2Status> {
+        self.expect_state(&[ComponentState::Instantiated], "fmi2EnterInitializationMode")?;
+        self.state = ComponentState::Initialization;
+
+        Ok(fmi2Status::fmi2OK)
+    }
+
+    /// Initialize the module with the values set so far
+    pub(crate) fn exit_initialization_mode(&mut self) -> Result<fmi2Status> {
+        self.expect_state(&[ComponentState::Initialization], "fmi2ExitInitializationMode")?;
+
+        let log_level = if self.logging_on { LogLevel::Info } else { LogLevel::Warn };
+        let res = self.inst.initialize(&self.initial_vals, self.start_time, self.stop_time,
+            self.tolerance, log_level, false)?;
+
+        let status = self.status(res.status, res.message.as_deref());
+        if status == fmi2Status::fmi2OK || status == fmi2Status::fmi2Warning {
+            self.state = ComponentState::Stepping;
+            self.time = self.start_time;
+        }
+
+        Ok(status)
+    }
+
+    pub(crate) fn do_step(&mut self, current_time: f64, step_size: f64) -> Result<fmi2Status> {
+        self.expect_state(&[ComponentState::Stepping], "fmi2DoStep")?;
+
+        let res = self.inst.do_step(current_time, step_size)?;
+        let status = self.status(res.status, res.message.as_deref());
+        if status == fmi2Status::fmi2OK || status == fmi2Status::fmi2Warning {
+            self.time = res.updated_time;
+        }
+
+        Ok(status)
+    }
+
+    pub(crate) fn terminate(&mut self) -> Result<fmi2Status> {
+        self.expect_state(&[ComponentState::Stepping], "fmi2Terminate")?;
+        self.state = ComponentState::Terminated;
+
+        Ok(fmi2Status::fmi2OK)
+    }
+
+    /// Return to the state after instantiation
+    pub(crate) fn reset(&mut self) -> Result<fmi2Status> {
+        self.inst.restore_snapshot(&self.initial_snapshot)?;
+        self.state = ComponentState::Instantiated;
+        self.start_time = 0.0;
+        self.stop_time = None;
+        self.tolerance = None;
+        self.time = 0.0;
+        self.initial_vals = DtasmVarValues::new();
+
+        Ok(fmi2Status::fmi2OK)
+    }
+
+    /// Time reached by the last successful step
+    pub(crate) fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Variable ids for the value references, which must refer to variables
+    /// of the given type
+    pub(crate) fn variable_ids(&self, vrs: &[fmi2ValueReference], value_type: MD::VariableType) -> Result<Vec<i32>> {
+        vrs.iter()
+            .map(|vr| {
+                self.md.variables.iter()
+                    .find(|v| v.id as i64 == *vr as i64 && is_fmi2_variable(v) && v.value_type == value_type)
+                    .map(|v| v.id)
+                    .ok_or_else(|| anyhow!("Unknown value reference {} of type {:?}", vr, value_type))
+            })
+            .collect()
+    }
+
+    pub(crate) fn get_values(&mut self, ids: &[i32]) -> Result<DtasmVarValues> {
+        match self.state {
+            ComponentState::Instantiated | ComponentState::Initialization => {
+                let mut values = DtasmVarValues::new();
+                for id in ids {
+                    let var = self.md.variables.iter().find(|v| v.id == *id).ok_or_else(|| anyhow!("Unknown variable id {}", id))?;
+                    match initial_value(&self.initial_vals, var).or_else(|| var.default.clone()) {
+                        Some(value) => values.insert_default(var, &value),
+                        None => bail!("Value of variable `{}` is not available before initialization", var.name)
+                    }
+                }
+
+                Ok(values)
+            },
+            ComponentState::Stepping | ComponentState::Terminated => {
+                let res = self.inst.get_values(&ids.to_vec())?;
+                self.status(res.status, res.message.as_deref());
+
+                Ok(res.values)
+            }
+        }
+    }
+
+    pub(crate) fn set_values(&mut self, values: DtasmVarValues) -> Result<fmi2Status> {
+        match self.state {
+            ComponentState::Instantiated | ComponentState::Initialization => {
+                self.initial_vals.real_values.extend(values.real_values);
+                self.initial_vals.int_values.extend(values.int_values);
+                self.initial_vals.bool_values.extend(values.bool_values);
+                self.initial_vals.string_values.extend(values.string_values);
+
+                Ok(fmi2Status::fmi2OK)
+            },
+            ComponentState::Stepping => {
+                let res = self.inst.set_values(&values)?;
+
+                Ok(self.status(res.status, res.message.as_deref()))
+            },
+            ComponentState::Terminated => bail!("Values cannot be set after fmi2Terminate")
+        }
+    }
+
+    /// Keep strings returned to the environment alive until the next call
+    pub(crate) fn hold_strings(&mut self, strings: Vec<CString>) -> Vec<*const c_char> {
+        self.strings = strings;
+        self.strings.iter().map(|s| s.as_ptr()).collect()
+    }
+
+    pub(crate) fn get_state(&mut self) -> FmuState {
+        FmuState {
+            snapshot: self.inst.snapshot(),
+            state: self.state,
+            time: self.time,
+            initial_vals: self.initial_vals.clone()
+        }
+    }
+
+    pub(crate) fn set_state(&mut self, fmu_state: &FmuState) -> Result<fmi2Status> {
+        self.inst.restore_snapshot(&fmu_state.snapshot)?;
+        self.state = fmu_state.state;
+        self.time = fmu_state.time;
+        self.initial_vals = fmu_state.initial_vals.clone();
+
+        Ok(fmi2Status::fmi2OK)
+    }
+
+    fn expect_state(&self, states: &[ComponentState], function: &str) -> Result<()> {
+        if !states.contains(&self.state) {
+            bail!("{} must not be called in state {:?}", function, self.state);
+        }
+
+        Ok(())
+    }
+
+    /// FMI status for the status of a module response, logging its message
+    fn status(&self, status: Status, message: Option<&str>) -> fmi2Status {
+        let status = match status {
+            Status::OK => fmi2Status::fmi2OK,
+            Status::Warning => fmi2Status::fmi2Warning,
+            Status::Discard => fmi2Status::fmi2Discard,
+            Status::Error => fmi2Status::fmi2Error
+        };
+
+        if let Some(message) = message {
+            let category = if status == fmi2Status::fmi2OK { "logAll" } else { "logStatusWarning" };
+            self.log(status, category, message);
+        }
+
+        status
+    }
+}
+
+impl FmuState {
+    /// Serialized state: component state (1 byte) and time (8 bytes, little
+    /// endian) followed by the snapshot; values set before initialization are
+    /// not part of the serialized state
+    pub(crate) fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + self.snapshot.as_bytes().len());
+        bytes.push(self.state as u8);
+        bytes.extend_from_slice(&self.time.to_le_bytes());
+        bytes.extend_from_slice(self.snapshot.as_bytes());
+
+        bytes
+    }
+
+    pub(crate) fn deserialize(bytes: &[u8]) -> Result<FmuState> {
+        if bytes.len() < 9 {
+            bail!("Invalid serialized FMU state of {} bytes", bytes.len());
+        }
+
+        let state = match bytes[0] {
+            0 => ComponentState::Instantiated,
+            1 => ComponentState::Initialization,
+            2 => ComponentState::Stepping,
+            3 => ComponentState::Terminated,
+            s => bail!("Invalid component state {} in serialized FMU state", s)
+        };
+        let mut time = [0u8; 8];
+        time.copy_from_slice(&bytes[1..9]);
+
+        Ok(FmuState {
+            snapshot: Snapshot::from_bytes(bytes[9..].to_vec()),
+            state,
+            time: f64::from_le_bytes(time),
+            initial_vals: DtasmVarValues::new()
+        })
+    }
+}
+
+impl ModuleLogger {
+    fn log(&self, record: &LogRecord) {
+        let status = match record.level {
+            LogLevel::Error => fmi2Status::fmi2Error,
+            LogLevel::Warn => fmi2Status::fmi2Warning,
+            LogLevel::Info => fmi2Status::fmi2OK
+        };
+
+        log(&self.callbacks, &self.instance_name, status, "logAll", record.message);
+    }
+}
+
+/// Report a message through the logger callback, if the environment passed one
+pub(crate) fn log(callbacks: &fmi2CallbackFunctions, instance_name: &CString, status: fmi2Status, category: &str, message: &str) {
+    let logger = match callbacks.logger {
+        Some(logger) => logger,
+        None => return
+    };
+
+    let category = CString::new(category).unwrap_or_default();
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    unsafe {
+        logger(callbacks.componentEnvironment, instance_name.as_ptr(), status, category.as_ptr(),
+            b"%s\0".as_ptr() as *const c_char, message.as_ptr());
+    }
+}
+
+/// Value of a variable set before initialization
+fn initial_value(values: &DtasmVarValues, var: &MD::ModelVariable) -> Option<MD::VariableValue> {
+    let id = var.id;
+    match var.value_type {
+        MD::VariableType::DtasmReal => values.real_values.get(&id).map(|v| MD::VariableValue { real_val: *v, ..Default::default() }),
+        MD::VariableType::DtasmInt => values.int_values.get(&id).map(|v| MD::VariableValue { int_val: *v, ..Default::default() }),
+        MD::VariableType::DtasmBool => values.bool_values.get(&id).map(|v| MD::VariableValue { bool_val: *v, ..Default::default() }),
+        MD::VariableType::DtasmString => values.string_values.get(&id).map(|v| MD::VariableValue { string_val: v.clone(), ..Default::default() }),
+        _ => None
+    }
+}
+
+/// Directory of a `file:` URI as passed in `fmuResourceLocation`, with percent
+/// encoded characters decoded
+fn resource_dir(location: &str) -> PathBuf {
+    let path = location.strip_prefix("file://localhost")
+        .or_else(|| location.strip_prefix("file://"))
+        .or_else(|| location.strip_prefix("file:"))
+        .unwrap_or(location);
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => { decoded.push(byte); i += 3; },
+            (byte, _) => { decoded.push(byte); i += 1; }
+        }
+    }
+
+    PathBuf::from(String::from_utf8_lossy(&decoded).into_owned())
+}
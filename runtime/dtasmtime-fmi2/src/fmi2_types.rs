@@ -0,0 +1,69 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Types of the FMI 2.0 C interface (`fmi2TypesPlatform.h` and
+//! `fmi2FunctionTypes.h`)
+
+#![allow(non_camel_case_types, non_upper_case_globals, non_snake_case)]
+
+use libc::{c_char, c_int, c_uint, c_void, size_t};
+
+pub type fmi2Component = *mut c_void;
+pub type fmi2ComponentEnvironment = *mut c_void;
+pub type fmi2FMUstate = *mut c_void;
+pub type fmi2ValueReference = c_uint;
+pub type fmi2Real = f64;
+pub type fmi2Integer = c_int;
+pub type fmi2Boolean = c_int;
+pub type fmi2Char = c_char;
+pub type fmi2String = *const fmi2Char;
+pub type fmi2Byte = c_char;
+
+pub const fmi2True: fmi2Boolean = 1;
+pub const fmi2False: fmi2Boolean = 0;
+
+pub const fmi2TypesPlatform: &[u8] = b"default\0";
+pub const fmi2Version: &[u8] = b"2.0\0";
+
+#[repr(C)]
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum fmi2Status {
+    fmi2OK,
+    fmi2Warning,
+    fmi2Discard,
+    fmi2Error,
+    fmi2Fatal,
+    fmi2Pending
+}
+
+#[repr(C)]
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum fmi2Type {
+    fmi2ModelExchange,
+    fmi2CoSimulation
+}
+
+#[repr(C)]
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum fmi2StatusKind {
+    fmi2DoStepStatus,
+    fmi2PendingStatus,
+    fmi2LastSuccessfulTime,
+    fmi2Terminated
+}
+
+pub type fmi2CallbackLogger = Option<unsafe extern "C" fn(fmi2ComponentEnvironment, fmi2String, fmi2Status,
+    fmi2String, fmi2String, ...)>;
+pub type fmi2CallbackAllocateMemory = Option<unsafe extern "C" fn(size_t, size_t) -> *mut c_void>;
+pub type fmi2CallbackFreeMemory = Option<unsafe extern "C" fn(*mut c_void)>;
+pub type fmi2StepFinished = Option<unsafe extern "C" fn(fmi2ComponentEnvironment, fmi2Status)>;
+
+#[repr(C)]
+#[derive(Debug,Clone,Copy)]
+pub struct fmi2CallbackFunctions {
+    pub logger: fmi2CallbackLogger,
+    pub allocateMemory: fmi2CallbackAllocateMemory,
+    pub freeMemory: fmi2CallbackFreeMemory,
+    pub stepFinished: fmi2StepFinished,
+    pub componentEnvironment: fmi2ComponentEnvironment
+}
@@ -0,0 +1,437 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! FMI 2.0 co-simulation interface for dtasm modules. Built as shared library,
+//! this crate implements the `fmi2*` functions on top of dtasmtime, running the
+//! module found as `resources/model.wasm` in the FMU; the `dtasm2fmu` packager
+//! generates the `modelDescription.xml` from the model description of a module
+//! and zips it together with the library and the module.
+//!
+//! FMU states are snapshots of the linear memory of the module. Directional
+//! derivatives, input derivatives and asynchronous steps are not supported.
+
+#![allow(non_snake_case)]
+
+use std::ffi::{CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use anyhow::{anyhow, Result};
+use libc::{c_char, size_t};
+
+use dtasmtime::model_description as MD;
+use dtasmtime::types::DtasmVarValues;
+
+pub mod fmi2_types;
+pub mod model_description_xml;
+mod component;
+
+pub use component::MODULE_RESOURCE;
+use component::{Component, FmuState};
+use fmi2_types::*;
+
+
+#[no_mangle]
+pub extern "C" fn fmi2GetTypesPlatform() -> fmi2String {
+    fmi2TypesPlatform.as_ptr() as fmi2String
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetVersion() -> fmi2String {
+    fmi2Version.as_ptr() as fmi2String
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SetDebugLogging(c: fmi2Component, loggingOn: fmi2Boolean,
+    _nCategories: size_t, _categories: *const fmi2String) -> fmi2Status {
+    with_component(c, |comp| {
+        comp.set_debug_logging(loggingOn != fmi2False);
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2Instantiate(instanceName: fmi2String, fmuType: fmi2Type, fmuGUID: fmi2String,
+    fmuResourceLocation: fmi2String, functions: *const fmi2CallbackFunctions,
+    _visible: fmi2Boolean, loggingOn: fmi2Boolean) -> fmi2Component {
+
+    if functions.is_null() || instanceName.is_null() {
+        return ptr::null_mut();
+    }
+    let callbacks = unsafe { *functions };
+    let instance_name = unsafe { CStr::from_ptr(instanceName) }.to_string_lossy().into_owned();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| -> Result<Component> {
+        if fmuType != fmi2Type::fmi2CoSimulation {
+            return Err(anyhow!("Only co-simulation is supported"));
+        }
+
+        Component::new(&instance_name, &c_string(fmuResourceLocation)?, &c_string(fmuGUID)?,
+            callbacks, loggingOn != fmi2False)
+    }));
+
+    match result {
+        Ok(Ok(comp)) => Box::into_raw(Box::new(comp)) as fmi2Component,
+        Ok(Err(err)) => {
+            let name = CString::new(instance_name.replace('\0', " ")).unwrap_or_default();
+            component::log(&callbacks, &name, fmi2Status::fmi2Error, "logStatusError",
+                &format!("Could not instantiate FMU: {}", err));
+            ptr::null_mut()
+        },
+        Err(_) => ptr::null_mut()
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2FreeInstance(c: fmi2Component) {
+    if c.is_null() {
+        return;
+    }
+    unsafe {
+        drop(Box::from_raw(c as *mut Component));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SetupExperiment(c: fmi2Component, toleranceDefined: fmi2Boolean, tolerance: fmi2Real,
+    startTime: fmi2Real, stopTimeDefined: fmi2Boolean, stopTime: fmi2Real) -> fmi2Status {
+    with_component(c, |comp| {
+        let tolerance = if toleranceDefined != fmi2False { Some(tolerance) } else { None };
+        let stop_time = if stopTimeDefined != fmi2False { Some(stopTime) } else { None };
+
+        comp.setup_experiment(tolerance, startTime, stop_time)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2EnterInitializationMode(c: fmi2Component) -> fmi2Status {
+    with_component(c, |comp| comp.enter_initialization_mode())
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2ExitInitializationMode(c: fmi2Component) -> fmi2Status {
+    with_component(c, |comp| comp.exit_initialization_mode())
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2Terminate(c: fmi2Component) -> fmi2Status {
+    with_component(c, |comp| comp.terminate())
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2Reset(c: fmi2Component) -> fmi2Status {
+    with_component(c, |comp| comp.reset())
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetReal(c: fmi2Component, vr: *const fmi2ValueReference, nvr: size_t,
+    value: *mut fmi2Real) -> fmi2Status {
+    with_component(c, |comp| {
+        let ids = comp.variable_ids(c_slice(vr, nvr)?, MD::VariableType::DtasmReal)?;
+        let vals = comp.get_values(&ids)?;
+        for (out, id) in c_slice_mut(value, nvr)?.iter_mut().zip(&ids) {
+            *out = *vals.real_values.get(id).ok_or_else(|| missing_value(*id))?;
+        }
+
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetInteger(c: fmi2Component, vr: *const fmi2ValueReference, nvr: size_t,
+    value: *mut fmi2Integer) -> fmi2Status {
+    with_component(c, |comp| {
+        let ids = comp.variable_ids(c_slice(vr, nvr)?, MD::VariableType::DtasmInt)?;
+        let vals = comp.get_values(&ids)?;
+        for (out, id) in c_slice_mut(value, nvr)?.iter_mut().zip(&ids) {
+            *out = *vals.int_values.get(id).ok_or_else(|| missing_value(*id))?;
+        }
+
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetBoolean(c: fmi2Component, vr: *const fmi2ValueReference, nvr: size_t,
+    value: *mut fmi2Boolean) -> fmi2Status {
+    with_component(c, |comp| {
+        let ids = comp.variable_ids(c_slice(vr, nvr)?, MD::VariableType::DtasmBool)?;
+        let vals = comp.get_values(&ids)?;
+        for (out, id) in c_slice_mut(value, nvr)?.iter_mut().zip(&ids) {
+            *out = if *vals.bool_values.get(id).ok_or_else(|| missing_value(*id))? { fmi2True } else { fmi2False };
+        }
+
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetString(c: fmi2Component, vr: *const fmi2ValueReference, nvr: size_t,
+    value: *mut fmi2String) -> fmi2Status {
+    with_component(c, |comp| {
+        let ids = comp.variable_ids(c_slice(vr, nvr)?, MD::VariableType::DtasmString)?;
+        let vals = comp.get_values(&ids)?;
+        let strings = ids.iter()
+            .map(|id| {
+                let val = vals.string_values.get(id).ok_or_else(|| missing_value(*id))?;
+                Ok(CString::new(val.replace('\0', " "))?)
+            })
+            .collect::<Result<Vec<CString>>>()?;
+
+        let ptrs = comp.hold_strings(strings);
+        c_slice_mut(value, nvr)?.copy_from_slice(&ptrs);
+
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SetReal(c: fmi2Component, vr: *const fmi2ValueReference, nvr: size_t,
+    value: *const fmi2Real) -> fmi2Status {
+    with_component(c, |comp| {
+        let ids = comp.variable_ids(c_slice(vr, nvr)?, MD::VariableType::DtasmReal)?;
+        let mut vals = DtasmVarValues::new();
+        vals.real_values.extend(ids.into_iter().zip(c_slice(value, nvr)?.iter().copied()));
+
+        comp.set_values(vals)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SetInteger(c: fmi2Component, vr: *const fmi2ValueReference, nvr: size_t,
+    value: *const fmi2Integer) -> fmi2Status {
+    with_component(c, |comp| {
+        let ids = comp.variable_ids(c_slice(vr, nvr)?, MD::VariableType::DtasmInt)?;
+        let mut vals = DtasmVarValues::new();
+        vals.int_values.extend(ids.into_iter().zip(c_slice(value, nvr)?.iter().copied()));
+
+        comp.set_values(vals)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SetBoolean(c: fmi2Component, vr: *const fmi2ValueReference, nvr: size_t,
+    value: *const fmi2Boolean) -> fmi2Status {
+    with_component(c, |comp| {
+        let ids = comp.variable_ids(c_slice(vr, nvr)?, MD::VariableType::DtasmBool)?;
+        let mut vals = DtasmVarValues::new();
+        vals.bool_values.extend(ids.into_iter().zip(c_slice(value, nvr)?.iter().map(|v| *v != fmi2False)));
+
+        comp.set_values(vals)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SetString(c: fmi2Component, vr: *const fmi2ValueReference, nvr: size_t,
+    value: *const fmi2String) -> fmi2Status {
+    with_component(c, |comp| {
+        let ids = comp.variable_ids(c_slice(vr, nvr)?, MD::VariableType::DtasmString)?;
+        let strings = c_slice(value, nvr)?.iter()
+            .map(|s| c_string(*s))
+            .collect::<Result<Vec<String>>>()?;
+        let mut vals = DtasmVarValues::new();
+        vals.string_values.extend(ids.into_iter().zip(strings));
+
+        comp.set_values(vals)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetFMUstate(c: fmi2Component, FMUstate: *mut fmi2FMUstate) -> fmi2Status {
+    with_component(c, |comp| {
+        let fmu_state = comp.get_state();
+        store_fmu_state(FMUstate, fmu_state)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SetFMUstate(c: fmi2Component, FMUstate: fmi2FMUstate) -> fmi2Status {
+    with_component(c, |comp| {
+        let fmu_state = unsafe { (FMUstate as *const FmuState).as_ref() }.ok_or_else(|| anyhow!("Invalid FMU state"))?;
+        comp.set_state(fmu_state)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2FreeFMUstate(c: fmi2Component, FMUstate: *mut fmi2FMUstate) -> fmi2Status {
+    with_component(c, |_| {
+        if !FMUstate.is_null() {
+            unsafe {
+                if !(*FMUstate).is_null() {
+                    drop(Box::from_raw(*FMUstate as *mut FmuState));
+                }
+                *FMUstate = ptr::null_mut();
+            }
+        }
+
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SerializedFMUstateSize(c: fmi2Component, FMUstate: fmi2FMUstate,
+    size: *mut size_t) -> fmi2Status {
+    with_component(c, |_| {
+        let fmu_state = unsafe { (FMUstate as *const FmuState).as_ref() }.ok_or_else(|| anyhow!("Invalid FMU state"))?;
+        c_slice_mut(size, 1)?[0] = fmu_state.serialize().len();
+
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SerializeFMUstate(c: fmi2Component, FMUstate: fmi2FMUstate,
+    serializedState: *mut fmi2Byte, size: size_t) -> fmi2Status {
+    with_component(c, |_| {
+        let fmu_state = unsafe { (FMUstate as *const FmuState).as_ref() }.ok_or_else(|| anyhow!("Invalid FMU state"))?;
+        let bytes = fmu_state.serialize();
+        if bytes.len() > size {
+            return Err(anyhow!("Buffer of {} bytes cannot hold serialized FMU state of {} bytes", size, bytes.len()));
+        }
+
+        c_slice_mut(serializedState as *mut u8, bytes.len())?.copy_from_slice(&bytes);
+
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2DeSerializeFMUstate(c: fmi2Component, serializedState: *const fmi2Byte,
+    size: size_t, FMUstate: *mut fmi2FMUstate) -> fmi2Status {
+    with_component(c, |_| {
+        let fmu_state = FmuState::deserialize(c_slice(serializedState as *const u8, size)?)?;
+        store_fmu_state(FMUstate, fmu_state)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetDirectionalDerivative(c: fmi2Component, _vUnknown_ref: *const fmi2ValueReference,
+    _nUnknown: size_t, _vKnown_ref: *const fmi2ValueReference, _nKnown: size_t, _dvKnown: *const fmi2Real,
+    _dvUnknown: *mut fmi2Real) -> fmi2Status {
+    not_supported(c, "fmi2GetDirectionalDerivative")
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2SetRealInputDerivatives(c: fmi2Component, _vr: *const fmi2ValueReference, _nvr: size_t,
+    _order: *const fmi2Integer, _value: *const fmi2Real) -> fmi2Status {
+    not_supported(c, "fmi2SetRealInputDerivatives")
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetRealOutputDerivatives(c: fmi2Component, _vr: *const fmi2ValueReference, _nvr: size_t,
+    _order: *const fmi2Integer, _value: *mut fmi2Real) -> fmi2Status {
+    not_supported(c, "fmi2GetRealOutputDerivatives")
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2DoStep(c: fmi2Component, currentCommunicationPoint: fmi2Real,
+    communicationStepSize: fmi2Real, _noSetFMUStatePriorToCurrentPoint: fmi2Boolean) -> fmi2Status {
+    with_component(c, |comp| comp.do_step(currentCommunicationPoint, communicationStepSize))
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2CancelStep(c: fmi2Component) -> fmi2Status {
+    not_supported(c, "fmi2CancelStep")
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetStatus(c: fmi2Component, _s: fmi2StatusKind, _value: *mut fmi2Status) -> fmi2Status {
+    with_component(c, |_| Ok(fmi2Status::fmi2Discard))
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetRealStatus(c: fmi2Component, s: fmi2StatusKind, value: *mut fmi2Real) -> fmi2Status {
+    with_component(c, |comp| {
+        if s != fmi2StatusKind::fmi2LastSuccessfulTime {
+            return Ok(fmi2Status::fmi2Discard);
+        }
+
+        c_slice_mut(value, 1)?[0] = comp.time();
+        Ok(fmi2Status::fmi2OK)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetIntegerStatus(c: fmi2Component, _s: fmi2StatusKind, _value: *mut fmi2Integer) -> fmi2Status {
+    with_component(c, |_| Ok(fmi2Status::fmi2Discard))
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetBooleanStatus(c: fmi2Component, _s: fmi2StatusKind, _value: *mut fmi2Boolean) -> fmi2Status {
+    with_component(c, |_| Ok(fmi2Status::fmi2Discard))
+}
+
+#[no_mangle]
+pub extern "C" fn fmi2GetStringStatus(c: fmi2Component, _s: fmi2StatusKind, _value: *mut fmi2String) -> fmi2Status {
+    with_component(c, |_| Ok(fmi2Status::fmi2Discard))
+}
+
+/// Run `f` on the component; errors are reported through the logger callback
+/// and returned as `fmi2Error`, panics as `fmi2Fatal`
+fn with_component<F>(c: fmi2Component, f: F) -> fmi2Status
+    where F: FnOnce(&mut Component) -> Result<fmi2Status> {
+    let comp = match unsafe { (c as *mut Component).as_mut() } {
+        Some(comp) => comp,
+        None => return fmi2Status::fmi2Error
+    };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&mut *comp))) {
+        Ok(Ok(status)) => status,
+        Ok(Err(err)) => {
+            comp.log(fmi2Status::fmi2Error, "logStatusError", &err.to_string());
+            fmi2Status::fmi2Error
+        },
+        Err(_) => fmi2Status::fmi2Fatal
+    }
+}
+
+fn not_supported(c: fmi2Component, function: &str) -> fmi2Status {
+    with_component(c, |_| Err(anyhow!("{} is not supported", function)))
+}
+
+fn missing_value(id: i32) -> anyhow::Error {
+    anyhow!("Module did not return a value for variable id {}", id)
+}
+
+/// Store the state in `*target`, reusing an existing state object
+fn store_fmu_state(target: *mut fmi2FMUstate, fmu_state: FmuState) -> Result<fmi2Status> {
+    if target.is_null() {
+        return Err(anyhow!("Invalid FMU state pointer"));
+    }
+
+    unsafe {
+        match (*target as *mut FmuState).as_mut() {
+            Some(existing) => *existing = fmu_state,
+            None => *target = Box::into_raw(Box::new(fmu_state)) as fmi2FMUstate
+        }
+    }
+
+    Ok(fmi2Status::fmi2OK)
+}
+
+fn c_string(s: *const c_char) -> Result<String> {
+    if s.is_null() {
+        return Err(anyhow!("Unexpected null string"));
+    }
+
+    Ok(unsafe { CStr::from_ptr(s) }.to_str()?.to_string())
+}
+
+/// Slice of `len` elements at `ptr`, which may only be null if `len` is 0
+fn c_slice<'a, T>(ptr: *const T, len: size_t) -> Result<&'a [T]> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(anyhow!("Unexpected null pointer for {} elements", len)),
+        (false, _) => Ok(unsafe { slice::from_raw_parts(ptr, len) })
+    }
+}
+
+/// Mutable slice of `len` elements at `ptr`, which may only be null if `len` is 0
+fn c_slice_mut<'a, T>(ptr: *mut T, len: size_t) -> Result<&'a mut [T]> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Ok(&mut []),
+        (true, _) => Err(anyhow!("Unexpected null pointer for {} elements", len)),
+        (false, _) => Ok(unsafe { slice::from_raw_parts_mut(ptr, len) })
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Generation of the FMI 2.0 `modelDescription.xml` for a dtasm module. Scalar
+//! variables of type `DtasmReal`, `DtasmInt` (as `Enumeration` if they refer to
+//! an enumeration type), `DtasmBool` and `DtasmString` become scalar variables
+//! of the FMU with their variable id as value reference; all other variables
+//! cannot be expressed in FMI 2.0 and are left out (see
+//! `unsupported_variables`).
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use dtasm_base::model_description as MD;
+use dtasm_base::units::{UnitRegistry, BASE_UNITS};
+
+/// Whether the variable can be represented as FMI 2.0 scalar variable
+pub fn is_fmi2_variable(var: &MD::ModelVariable) -> bool {
+    var.id >= 0 && !var.is_array() && matches!(var.value_type,
+        MD::VariableType::DtasmReal | MD::VariableType::DtasmInt | MD::VariableType::DtasmBool | MD::VariableType::DtasmString)
+}
+
+/// Variables left out of the FMU because FMI 2.0 cannot represent them
+pub fn unsupported_variables(md: &MD::ModelDescription) -> Vec<&MD::ModelVariable> {
+    md.variables.iter().filter(|v| !is_fmi2_variable(v)).collect()
+}
+
+/// Model identifier of the FMU (the name of its shared library), derived from
+/// the model name by replacing all characters not allowed in C identifiers
+pub fn model_identifier(md: &MD::ModelDescription) -> String {
+    let mut identifier: String = md.model.name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if identifier.is_empty() {
+        identifier = "dtasm_model".to_string();
+    }
+    else if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+
+    identifier
+}
+
+/// GUID of the FMU, derived from the contents of the wrapped module so that
+/// the FMU can check that it runs the module the model description belongs to
+///
+/// * `wasm` - contents of the `.wasm` file
+pub fn guid(wasm: &[u8]) -> String {
+    let fnv1a = |offset_basis: u64| wasm.iter()
+        .fold(offset_basis, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3));
+    let (high, low) = (fnv1a(0xcbf29ce484222325), fnv1a(0x84222325cbf29ce4));
+
+    format!("{{{:08x}-{:04x}-{:04x}-{:04x}-{:012x}}}", high >> 32, (high >> 16) & 0xffff, high & 0xffff,
+        low >> 48, low & 0xffff_ffff_ffff)
+}
+
+/// Generate the `modelDescription.xml` of a co-simulation FMU wrapping a module
+///
+/// * `md` - model description of the module
+/// * `model_identifier` - model identifier of the FMU, see `model_identifier`
+/// * `guid` - GUID of the FMU, see `guid`
+pub fn model_description_xml(md: &MD::ModelDescription, model_identifier: &str, guid: &str) -> String {
+    let variables: Vec<&MD::ModelVariable> = md.variables.iter().filter(|v| is_fmi2_variable(v)).collect();
+    let mut xml = String::new();
+
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(xml, "<fmiModelDescription fmiVersion=\"2.0\" modelName=\"{}\" guid=\"{}\" description=\"{}\" \
+        generationTool=\"{}\" variableNamingConvention=\"flat\" numberOfEventIndicators=\"0\">",
+        escape(&md.model.name), escape(guid), escape(&md.model.description), escape(&generation_tool(md)));
+    let _ = writeln!(xml, "  <CoSimulation modelIdentifier=\"{}\" canHandleVariableCommunicationStepSize=\"{}\" \
+        canGetAndSetFMUstate=\"true\" canSerializeFMUstate=\"true\" canNotUseMemoryManagementFunctions=\"true\"/>",
+        escape(model_identifier), md.model.capabilities.can_handle_variable_step_size);
+
+    write_unit_definitions(&mut xml, md, &variables);
+    write_type_definitions(&mut xml, md);
+
+    if let Some(exp) = &md.experiment {
+        let _ = writeln!(xml, "  <DefaultExperiment startTime=\"{}\" stopTime=\"{}\" stepSize=\"{}\"/>",
+            double(exp.start_time_default), double(exp.end_time_default), double(exp.time_step_default));
+    }
+
+    xml.push_str("  <ModelVariables>\n");
+    for var in &variables {
+        write_variable(&mut xml, md, var);
+    }
+    xml.push_str("  </ModelVariables>\n");
+
+    // outputs are calculated during initialization, so they are initial unknowns as well
+    let outputs: Vec<usize> = variables.iter().enumerate()
+        .filter(|(_, v)| v.causality == MD::CausalityType::Output)
+        .map(|(i, _)| i + 1)
+        .collect();
+    xml.push_str("  <ModelStructure>\n");
+    if !outputs.is_empty() {
+        for element in &["Outputs", "InitialUnknowns"] {
+            let _ = writeln!(xml, "    <{}>", element);
+            for index in &outputs {
+                let _ = writeln!(xml, "      <Unknown index=\"{}\"/>", index);
+            }
+            let _ = writeln!(xml, "    </{}>", element);
+        }
+    }
+    xml.push_str("  </ModelStructure>\n");
+    xml.push_str("</fmiModelDescription>\n");
+
+    xml
+}
+
+fn generation_tool(md: &MD::ModelDescription) -> String {
+    match md.model.generation_tool.is_empty() {
+        true => "dtasm2fmu".to_string(),
+        false => format!("dtasm2fmu ({})", md.model.generation_tool)
+    }
+}
+
+/// Units of all real variables, with their base units if they can be resolved
+fn write_unit_definitions(xml: &mut String, md: &MD::ModelDescription, variables: &[&MD::ModelVariable]) {
+    let units: BTreeSet<&str> = variables.iter()
+        .filter(|v| v.value_type == MD::VariableType::DtasmReal && !v.unit.is_empty())
+        .map(|v| v.unit.as_str())
+        .collect();
+    if units.is_empty() {
+        return;
+    }
+
+    let registry = UnitRegistry::with_model_description(md);
+    xml.push_str("  <UnitDefinitions>\n");
+    for name in units {
+        match registry.resolve(name) {
+            Ok(unit) => {
+                let _ = writeln!(xml, "    <Unit name=\"{}\">", escape(name));
+                xml.push_str("      <BaseUnit");
+                for (symbol, exponent) in BASE_UNITS.iter().zip(unit.exponents.iter()) {
+                    if *exponent != 0 {
+                        let _ = write!(xml, " {}=\"{}\"", symbol, exponent);
+                    }
+                }
+                if unit.factor != 1.0 {
+                    let _ = write!(xml, " factor=\"{}\"", double(unit.factor));
+                }
+                if unit.offset != 0.0 {
+                    let _ = write!(xml, " offset=\"{}\"", double(unit.offset));
+                }
+                xml.push_str("/>\n    </Unit>\n");
+            },
+            Err(_) => { let _ = writeln!(xml, "    <Unit name=\"{}\"/>", escape(name)); }
+        }
+    }
+    xml.push_str("  </UnitDefinitions>\n");
+}
+
+fn write_type_definitions(xml: &mut String, md: &MD::ModelDescription) {
+    if md.enumeration_types.is_empty() {
+        return;
+    }
+
+    xml.push_str("  <TypeDefinitions>\n");
+    for enum_type in &md.enumeration_types {
+        let _ = writeln!(xml, "    <SimpleType name=\"{}\" description=\"{}\">", escape(&enum_type.name), escape(&enum_type.description));
+        xml.push_str("      <Enumeration>\n");
+        for item in &enum_type.items {
+            let _ = writeln!(xml, "        <Item name=\"{}\" value=\"{}\" description=\"{}\"/>",
+                escape(&item.name), item.value, escape(&item.description));
+        }
+        xml.push_str("      </Enumeration>\n    </SimpleType>\n");
+    }
+    xml.push_str("  </TypeDefinitions>\n");
+}
+
+fn write_variable(xml: &mut String, md: &MD::ModelDescription, var: &MD::ModelVariable) {
+    let causality = match var.causality {
+        MD::CausalityType::Parameter => "parameter",
+        MD::CausalityType::Input => "input",
+        MD::CausalityType::Output => "output",
+        MD::CausalityType::Local => "local"
+    };
+    let variability = variability(var);
+
+    let _ = writeln!(xml, "    <ScalarVariable name=\"{}\" valueReference=\"{}\" description=\"{}\" causality=\"{}\" variability=\"{}\">",
+        escape(&var.name), var.id, escape(&var.description), causality, variability);
+
+    // parameters, inputs and constants require a start value
+    let needs_start = var.causality == MD::CausalityType::Parameter || var.causality == MD::CausalityType::Input
+        || variability == "constant";
+    let start = match needs_start {
+        true => Some(var.default.clone().unwrap_or_else(|| zero_value(md, var))),
+        false => None
+    };
+
+    let mut attributes = String::new();
+    let element = match var.value_type {
+        MD::VariableType::DtasmReal => {
+            if !var.unit.is_empty() {
+                let _ = write!(attributes, " unit=\"{}\"", escape(&var.unit));
+            }
+            if let Some(min) = &var.min { let _ = write!(attributes, " min=\"{}\"", double(min.real_val)); }
+            if let Some(max) = &var.max { let _ = write!(attributes, " max=\"{}\"", double(max.real_val)); }
+            if let Some(nominal) = &var.nominal { let _ = write!(attributes, " nominal=\"{}\"", double(nominal.real_val)); }
+            if let Some(start) = &start { let _ = write!(attributes, " start=\"{}\"", double(start.real_val)); }
+            "Real"
+        },
+        MD::VariableType::DtasmInt => {
+            if !var.enumeration_type.is_empty() {
+                let _ = write!(attributes, " declaredType=\"{}\"", escape(&var.enumeration_type));
+            }
+            if let Some(min) = &var.min { let _ = write!(attributes, " min=\"{}\"", min.int_val); }
+            if let Some(max) = &var.max { let _ = write!(attributes, " max=\"{}\"", max.int_val); }
+            if let Some(start) = &start { let _ = write!(attributes, " start=\"{}\"", start.int_val); }
+            if var.enumeration_type.is_empty() { "Integer" } else { "Enumeration" }
+        },
+        MD::VariableType::DtasmBool => {
+            if let Some(start) = &start { let _ = write!(attributes, " start=\"{}\"", start.bool_val); }
+            "Boolean"
+        },
+        _ => {
+            if let Some(start) = &start { let _ = write!(attributes, " start=\"{}\"", escape(&start.string_val)); }
+            "String"
+        }
+    };
+
+    let _ = writeln!(xml, "      <{}{}/>", element, attributes);
+    xml.push_str("    </ScalarVariable>\n");
+}
+
+/// FMI 2.0 variability; parameters are fixed unless tunable, and only real
+/// variables can be continuous
+fn variability(var: &MD::ModelVariable) -> &'static str {
+    let is_real = var.value_type == MD::VariableType::DtasmReal;
+
+    match (var.causality, var.variability) {
+        (MD::CausalityType::Parameter, MD::VariabilityType::Tunable) => "tunable",
+        (MD::CausalityType::Parameter, _) => "fixed",
+        (MD::CausalityType::Input, MD::VariabilityType::Discrete) => "discrete",
+        (MD::CausalityType::Input, _) if is_real => "continuous",
+        (MD::CausalityType::Input, _) => "discrete",
+        (_, MD::VariabilityType::Constant) => "constant",
+        (_, MD::VariabilityType::Continuous) if is_real => "continuous",
+        _ => "discrete"
+    }
+}
+
+/// Start value for variables without default: zero, or the first item of an enumeration
+fn zero_value(md: &MD::ModelDescription, var: &MD::ModelVariable) -> MD::VariableValue {
+    let int_val = md.enumeration_types.iter()
+        .find(|e| e.name == var.enumeration_type)
+        .and_then(|e| e.items.first())
+        .map(|item| item.value)
+        .unwrap_or(0);
+
+    MD::VariableValue { int_val, ..Default::default() }
+}
+
+fn double(value: f64) -> String {
+    match value {
+        v if v.is_nan() => "NaN".to_string(),
+        v if v == f64::INFINITY => "INF".to_string(),
+        v if v == f64::NEG_INFINITY => "-INF".to_string(),
+        v => v.to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c)
+        }
+    }
+
+    escaped
+}
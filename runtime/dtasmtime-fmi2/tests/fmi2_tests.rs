@@ -0,0 +1,180 @@
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use tempfile::TempDir;
+
+#[path = "../../dtasmtime/tests/common/mod.rs"]
+mod common;
+use common::add_wasm_path;
+
+use dtasmtime::runtime::{Engine, Module};
+use dtasmtime_fmi2::*;
+use dtasmtime_fmi2::fmi2_types::*;
+use dtasmtime_fmi2::model_description_xml::{guid, model_description_xml, model_identifier, unsupported_variables};
+
+
+/// Resources directory of an unpacked FMU wrapping the add_rs module; the FMU 
+/// is removed when the returned `TempDir` is dropped
+fn add_resources() -> (TempDir, PathBuf, String) {
+    let wasm = std::fs::read(add_wasm_path()).expect("Could not read add_rs.wasm");
+    let fmu_dir = tempfile::tempdir().expect("Could not create FMU directory");
+    let dir = fmu_dir.path().join("resources");
+    std::fs::create_dir_all(&dir).expect("Could not create resources directory");
+    std::fs::write(dir.join(MODULE_RESOURCE), &wasm).expect("Could not write module");
+
+    (fmu_dir, dir, guid(&wasm))
+}
+
+fn instantiate(dir: &Path, guid: &str) -> fmi2Component {
+    let name = CString::new("add").unwrap();
+    let guid = CString::new(guid).unwrap();
+    let location = CString::new(format!("file://{}", dir.display())).unwrap();
+    let callbacks = fmi2CallbackFunctions {
+        logger: None,
+        allocateMemory: None,
+        freeMemory: None,
+        stepFinished: None,
+        componentEnvironment: ptr::null_mut()
+    };
+
+    fmi2Instantiate(name.as_ptr(), fmi2Type::fmi2CoSimulation, guid.as_ptr(), location.as_ptr(), &callbacks, fmi2False, fmi2False)
+}
+
+fn c_str(ptr: fmi2String) -> String {
+    assert!(!ptr.is_null());
+    unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_string()
+}
+
+fn get_real(c: fmi2Component, vr: fmi2ValueReference) -> f64 {
+    let mut value = 0.0;
+    assert_eq!(fmi2GetReal(c, &vr, 1, &mut value), fmi2Status::fmi2OK);
+
+    value
+}
+
+#[test]
+fn it_generates_model_description_xml() {
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let md = Module::new(add_wasm_path(), &engine).expect("Could not load dtasm module")
+        .model_description().expect("Could not read model description");
+
+    assert_eq!(model_identifier(&md), "Add");
+    assert!(unsupported_variables(&md).is_empty());
+
+    let xml = model_description_xml(&md, "Add", "{guid}");
+    assert!(xml.contains("<CoSimulation modelIdentifier=\"Add\""));
+    assert!(xml.contains("canGetAndSetFMUstate=\"true\""));
+    assert!(xml.contains("<DefaultExperiment startTime=\"0\" stopTime=\"10\" stepSize=\"0.01\"/>"));
+    assert!(xml.contains("name=\"real_in1\" valueReference=\"1\" description=\"\" causality=\"input\" variability=\"continuous\""));
+    assert!(xml.contains("<Real start=\"1.5\"/>"));
+    assert!(xml.contains("name=\"int_out\" valueReference=\"6\" description=\"\" causality=\"output\" variability=\"discrete\""));
+    assert!(xml.contains("<String start=\"hello\"/>"));
+    assert_eq!(xml.matches("<Unknown index=").count(), 8);
+}
+
+#[test]
+fn it_rejects_wrong_guid() {
+    let (_fmu_dir, dir, _) = add_resources();
+    assert!(instantiate(&dir, "{00000000-0000-0000-0000-000000000000}").is_null());
+}
+
+#[test]
+fn it_simulates_the_fmu() {
+    let (_fmu_dir, dir, guid) = add_resources();
+    let c = instantiate(&dir, &guid);
+    assert!(!c.is_null());
+
+    assert_eq!(c_str(fmi2GetVersion()), "2.0");
+    assert_eq!(fmi2SetupExperiment(c, fmi2False, 0.0, 0.0, fmi2True, 1.0), fmi2Status::fmi2OK);
+    assert_eq!(fmi2EnterInitializationMode(c), fmi2Status::fmi2OK);
+    assert_eq!(get_real(c, 1), 1.5);
+
+    let vrs = [1, 2];
+    let values = [2.0, 3.5];
+    assert_eq!(fmi2SetReal(c, vrs.as_ptr(), 2, values.as_ptr()), fmi2Status::fmi2OK);
+    assert_eq!(fmi2ExitInitializationMode(c), fmi2Status::fmi2OK);
+
+    assert_eq!(fmi2DoStep(c, 0.0, 0.1, fmi2True), fmi2Status::fmi2OK);
+    assert_eq!(get_real(c, 3), 5.5);
+
+    let str_vrs = [10, 11];
+    let (hello, world) = (CString::new("hello").unwrap(), CString::new(" fmi").unwrap());
+    let str_values = [hello.as_ptr(), world.as_ptr()];
+    assert_eq!(fmi2SetString(c, str_vrs.as_ptr(), 2, str_values.as_ptr()), fmi2Status::fmi2OK);
+    assert_eq!(fmi2DoStep(c, 0.1, 0.1, fmi2True), fmi2Status::fmi2OK);
+
+    let str_out: fmi2ValueReference = 12;
+    let mut str_value: fmi2String = ptr::null();
+    assert_eq!(fmi2GetString(c, &str_out, 1, &mut str_value), fmi2Status::fmi2OK);
+    assert_eq!(c_str(str_value), "hello fmi");
+
+    // value references of the wrong type are rejected
+    assert_eq!(fmi2GetReal(c, &str_out, 1, &mut 0.0), fmi2Status::fmi2Error);
+
+    assert_eq!(fmi2Terminate(c), fmi2Status::fmi2OK);
+    fmi2FreeInstance(c);
+}
+
+#[test]
+fn it_restores_fmu_states() {
+    let (_fmu_dir, dir, guid) = add_resources();
+    let c = instantiate(&dir, &guid);
+    assert!(!c.is_null());
+    assert_eq!(fmi2EnterInitializationMode(c), fmi2Status::fmi2OK);
+    assert_eq!(fmi2ExitInitializationMode(c), fmi2Status::fmi2OK);
+
+    let vr = 1;
+    assert_eq!(fmi2SetReal(c, &vr, 1, &10.0), fmi2Status::fmi2OK);
+    assert_eq!(fmi2DoStep(c, 0.0, 0.1, fmi2False), fmi2Status::fmi2OK);
+    let out = get_real(c, 3);
+
+    let mut state: fmi2FMUstate = ptr::null_mut();
+    assert_eq!(fmi2GetFMUstate(c, &mut state), fmi2Status::fmi2OK);
+
+    let mut size = 0;
+    assert_eq!(fmi2SerializedFMUstateSize(c, state, &mut size), fmi2Status::fmi2OK);
+    let mut serialized = vec![0 as fmi2Byte; size];
+    assert_eq!(fmi2SerializeFMUstate(c, state, serialized.as_mut_ptr(), size), fmi2Status::fmi2OK);
+
+    assert_eq!(fmi2SetReal(c, &vr, 1, &20.0), fmi2Status::fmi2OK);
+    assert_eq!(fmi2DoStep(c, 0.1, 0.1, fmi2False), fmi2Status::fmi2OK);
+    assert_ne!(get_real(c, 3), out);
+
+    assert_eq!(fmi2SetFMUstate(c, state), fmi2Status::fmi2OK);
+    assert_eq!(get_real(c, 3), out);
+    assert_eq!(fmi2FreeFMUstate(c, &mut state), fmi2Status::fmi2OK);
+    assert!(state.is_null());
+
+    assert_eq!(fmi2SetReal(c, &vr, 1, &20.0), fmi2Status::fmi2OK);
+    assert_eq!(fmi2DoStep(c, 0.1, 0.1, fmi2False), fmi2Status::fmi2OK);
+
+    let mut restored: fmi2FMUstate = ptr::null_mut();
+    assert_eq!(fmi2DeSerializeFMUstate(c, serialized.as_ptr(), size, &mut restored), fmi2Status::fmi2OK);
+    assert_eq!(fmi2SetFMUstate(c, restored), fmi2Status::fmi2OK);
+    assert_eq!(get_real(c, 3), out);
+    assert_eq!(fmi2FreeFMUstate(c, &mut restored), fmi2Status::fmi2OK);
+
+    fmi2FreeInstance(c);
+}
+
+#[test]
+fn it_rejects_null_pointers() {
+    let (_fmu_dir, dir, guid) = add_resources();
+    let c = instantiate(&dir, &guid);
+    assert!(!c.is_null());
+    assert_eq!(fmi2EnterInitializationMode(c), fmi2Status::fmi2OK);
+
+    let vrs = [1, 2];
+    assert_eq!(fmi2SetReal(c, vrs.as_ptr(), 2, ptr::null()), fmi2Status::fmi2Error);
+    assert_eq!(fmi2GetReal(c, vrs.as_ptr(), 2, ptr::null_mut()), fmi2Status::fmi2Error);
+    assert_eq!(fmi2SetReal(c, ptr::null(), 0, ptr::null()), fmi2Status::fmi2OK);
+    assert_eq!(fmi2GetRealStatus(c, fmi2StatusKind::fmi2LastSuccessfulTime, ptr::null_mut()), fmi2Status::fmi2Error);
+
+    let mut state: fmi2FMUstate = ptr::null_mut();
+    assert_eq!(fmi2GetFMUstate(c, &mut state), fmi2Status::fmi2OK);
+    assert_eq!(fmi2SerializedFMUstateSize(c, state, ptr::null_mut()), fmi2Status::fmi2Error);
+    assert_eq!(fmi2FreeFMUstate(c, &mut state), fmi2Status::fmi2OK);
+
+    fmi2FreeInstance(c);
+}
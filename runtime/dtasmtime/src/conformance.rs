@@ -0,0 +1,413 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Conformance checks that exercise a dtasm module through the runtime and
+//! verify that it follows the dtasm ABI. Every check runs on a fresh instance
+//! of the module; the outcomes are collected in a `ConformanceReport`.
+
+use std::fmt;
+
+use dtasm_abi::dtasm_generated::dtasm_model_description as DTMD;
+
+use crate::errors::DtasmtimeError;
+use crate::runtime::{Instance, Module};
+use dtasm_base::model_description as MD;
+use dtasm_base::types::{DtasmVarValues, LogLevel, Status};
+use dtasm_base::validation::{validate, Severity};
+
+type CheckFn = fn(&mut Instance) -> Result<(), DtasmtimeError>;
+
+/// Number of bytes behind each output buffer that must not be touched by the module
+const GUARD_SIZE: i32 = 64;
+const GUARD_BYTE: u8 = 0xa5;
+const N_STEPS: u32 = 100;
+const N_ALLOC_CYCLES: u32 = 1000;
+const ALLOC_SIZES: [i32; 6] = [1, 7, 64, 1000, 4096, 65536];
+const DEFAULT_TIMESTEP: f64 = 0.02;
+
+static CHECKS: [(&str, &str, CheckFn); 8] = [
+    ("md_retry", "getModelDescription follows the retry protocol for small buffers and is idempotent",
+        check_model_description_retry),
+    ("md_valid", "the model description passes semantic validation without errors",
+        check_model_description_valid),
+    ("init_defaults", "init succeeds with the default values of the model description",
+        check_init_with_defaults),
+    ("init_empty", "init succeeds without any initial values",
+        check_init_without_defaults),
+    ("get_values", "getValues returns exactly the requested value for every non-input variable",
+        check_get_values),
+    ("value_types", "getValues responses carry each value with the type of its variable",
+        check_value_types),
+    ("alloc_balance", "alloc returns disjoint blocks and memory does not grow over alloc/dealloc and step cycles",
+        check_alloc_balance),
+    ("monotonic_time", "time advances monotonically by at most the requested step after doStep",
+        check_monotonic_time),
+];
+
+/// Outcome of a single conformance check
+#[derive(Debug,Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub error: Option<String>
+}
+
+impl CheckResult {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Outcomes of all conformance checks run against a module
+#[derive(Debug,Clone)]
+pub struct ConformanceReport {
+    pub results: Vec<CheckResult>
+}
+
+impl ConformanceReport {
+    /// Whether all checks passed
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.passed())
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for result in &self.results {
+            match &result.error {
+                None => writeln!(f, "[PASS] {:<15} {}", result.name, result.description)?,
+                Some(err) => {
+                    writeln!(f, "[FAIL] {:<15} {}", result.name, result.description)?;
+                    writeln!(f, "       {}", err)?;
+                }
+            }
+        }
+
+        let n_passed = self.results.iter().filter(|r| r.passed()).count();
+        write!(f, "{} of {} checks passed", n_passed, self.results.len())
+    }
+}
+
+/// Run all conformance checks against a module; each check uses a fresh instance
+///
+/// * `module` - the dtasm module to check
+pub fn check_module(module: &mut Module) -> ConformanceReport {
+    let results = CHECKS.iter()
+        .map(|(name, description, check)| {
+            let res = module.instantiate().and_then(|mut inst| check(&mut inst));
+
+            CheckResult {
+                name: *name,
+                description: *description,
+                error: res.err().map(|e| e.to_string())
+            }
+        })
+        .collect();
+
+    ConformanceReport { results }
+}
+
+fn violation(msg: String) -> DtasmtimeError {
+    DtasmtimeError::ConformanceError(msg)
+}
+
+/// Call `getModelDescription` with an output buffer of the given size and return
+/// the reported size together with the bytes written, failing if the module
+/// writes beyond the buffer
+fn get_model_description_raw(inst: &mut Instance, size: i32) -> Result<(i32, Vec<u8>), DtasmtimeError> {
+    let ptr = inst.alloc(size + GUARD_SIZE)?;
+    inst.write_memory(ptr, &vec![GUARD_BYTE; (size + GUARD_SIZE) as usize])?;
+
+    let size_out = inst.call_get_model_description(ptr, size)?;
+    let buffer = inst.read_memory(ptr, (size + GUARD_SIZE) as usize)?;
+    inst.dealloc(ptr)?;
+
+    if buffer[size as usize..].iter().any(|b| *b != GUARD_BYTE) {
+        return Err(violation(format!("getModelDescription wrote beyond the end of a buffer of size {}", size)));
+    }
+
+    let len = size_out.max(0).min(size) as usize;
+    Ok((size_out, buffer[..len].to_vec()))
+}
+
+fn check_model_description_retry(inst: &mut Instance) -> Result<(), DtasmtimeError> {
+    let (required, _) = get_model_description_raw(inst, 0)?;
+    if required <= 0 {
+        return Err(violation(format!("getModelDescription reported invalid size {} for an empty buffer", required)));
+    }
+
+    for size in [1, 16, required - 1] {
+        if size <= 0 || size >= required {
+            continue;
+        }
+
+        for _ in 0..2 {
+            let (size_out, _) = get_model_description_raw(inst, size)?;
+            if size_out != required {
+                return Err(violation(format!(
+                    "getModelDescription reported size {} for a buffer of size {}, expected {}",
+                    size_out, size, required)));
+            }
+        }
+    }
+
+    let (size_out, bytes) = get_model_description_raw(inst, required)?;
+    if size_out != required {
+        return Err(violation(format!(
+            "getModelDescription reported size {} for a buffer of the requested size {}", size_out, required)));
+    }
+    DTMD::root_as_model_description(&bytes)
+        .map_err(|e| violation(format!("getModelDescription returned an invalid buffer: {}", e)))?;
+
+    let (size_again, bytes_again) = get_model_description_raw(inst, 2 * required)?;
+    if size_again != required || bytes_again != bytes {
+        return Err(violation("getModelDescription returned different content on repeated calls".to_string()));
+    }
+
+    Ok(())
+}
+
+fn check_model_description_valid(inst: &mut Instance) -> Result<(), DtasmtimeError> {
+    let md = inst.get_model_description()?;
+
+    let errors: Vec<String> = validate(&md).iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| d.to_string())
+        .collect();
+    if !errors.is_empty() {
+        return Err(violation(errors.join("; ")));
+    }
+
+    Ok(())
+}
+
+fn default_values(md: &MD::ModelDescription, causalities: &[MD::CausalityType]) -> DtasmVarValues {
+    let mut values = DtasmVarValues::new();
+
+    for variable in md.variables.iter().filter(|v| causalities.contains(&v.causality)) {
+        if let Some(default) = &variable.default {
+            values.insert_default(variable, default);
+        }
+    }
+
+    values
+}
+
+fn check_status(call: &str, status: Status, message: Option<String>) -> Result<(), DtasmtimeError> {
+    match (status, message) {
+        (Status::OK, _) | (Status::Warning, _) => Ok(()),
+        (status, Some(message)) => Err(violation(format!("{} returned status {:?}: {}", call, status, message))),
+        (status, None) => Err(violation(format!("{} returned status {:?}", call, status)))
+    }
+}
+
+fn initialize(inst: &mut Instance, with_defaults: bool) -> Result<MD::ModelDescription, DtasmtimeError> {
+    let md = inst.get_model_description()?;
+
+    let init_vals = match with_defaults {
+        true => default_values(&md, &[MD::CausalityType::Parameter, MD::CausalityType::Local, MD::CausalityType::Input]),
+        false => DtasmVarValues::new()
+    };
+    let (tmin, tmax) = match &md.experiment {
+        Some(exp) => (exp.start_time_default, Some(exp.end_time_default)),
+        None => (0.0, None)
+    };
+
+    let res = inst.initialize(&init_vals, tmin, tmax, None, LogLevel::Warn, true)?;
+    check_status("init", res.status, res.message)?;
+
+    Ok(md)
+}
+
+fn check_init_with_defaults(inst: &mut Instance) -> Result<(), DtasmtimeError> {
+    initialize(inst, true).map(|_| ())
+}
+
+fn check_init_without_defaults(inst: &mut Instance) -> Result<(), DtasmtimeError> {
+    initialize(inst, false).map(|_| ())
+}
+
+fn n_values(values: &DtasmVarValues) -> usize {
+    values.real_values.len() + values.int_values.len() + values.bool_values.len() + values.string_values.len()
+        + values.real_arrays.len() + values.int_arrays.len() + values.bool_arrays.len() + values.string_arrays.len()
+        + values.binary_values.len() + values.int64_values.len() + values.float32_values.len()
+        + values.int64_arrays.len() + values.float32_arrays.len()
+}
+
+fn has_typed_value(variable: &MD::ModelVariable, values: &DtasmVarValues) -> bool {
+    match (variable.value_type, variable.is_array()) {
+        (MD::VariableType::DtasmReal, false) => values.real_values.contains_key(&variable.id),
+        (MD::VariableType::DtasmInt, false) => values.int_values.contains_key(&variable.id),
+        (MD::VariableType::DtasmBool, false) => values.bool_values.contains_key(&variable.id),
+        (MD::VariableType::DtasmString, false) => values.string_values.contains_key(&variable.id),
+        (MD::VariableType::DtasmReal, true) => values.real_arrays.contains_key(&variable.id),
+        (MD::VariableType::DtasmInt, true) => values.int_arrays.contains_key(&variable.id),
+        (MD::VariableType::DtasmBool, true) => values.bool_arrays.contains_key(&variable.id),
+        (MD::VariableType::DtasmString, true) => values.string_arrays.contains_key(&variable.id),
+        (MD::VariableType::DtasmBinary, _) => values.binary_values.contains_key(&variable.id),
+        (MD::VariableType::DtasmInt64, false) => values.int64_values.contains_key(&variable.id),
+        (MD::VariableType::DtasmFloat32, false) => values.float32_values.contains_key(&variable.id),
+        (MD::VariableType::DtasmInt64, true) => values.int64_arrays.contains_key(&variable.id),
+        (MD::VariableType::DtasmFloat32, true) => values.float32_arrays.contains_key(&variable.id)
+    }
+}
+
+fn non_input_variables(md: &MD::ModelDescription) -> Vec<&MD::ModelVariable> {
+    md.variables.iter()
+        .filter(|v| v.causality != MD::CausalityType::Input)
+        .collect()
+}
+
+fn check_get_values(inst: &mut Instance) -> Result<(), DtasmtimeError> {
+    let md = initialize(inst, true)?;
+
+    for variable in non_input_variables(&md) {
+        let res = inst.get_values(&vec![variable.id])?;
+        check_status("getValues", res.status, res.message)?;
+
+        if !has_typed_value(variable, &res.values) {
+            return Err(violation(format!("getValues returned no value for variable {} (id {})",
+                variable.name, variable.id)));
+        }
+        if n_values(&res.values) != 1 {
+            return Err(violation(format!("getValues returned {} values when requesting variable {} (id {})",
+                n_values(&res.values), variable.name, variable.id)));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_value_types(inst: &mut Instance) -> Result<(), DtasmtimeError> {
+    let md = initialize(inst, true)?;
+    let variables = non_input_variables(&md);
+    let ids: Vec<i32> = variables.iter().map(|v| v.id).collect();
+
+    let (t, dt) = time_steps(&md);
+
+    for step in 0..2 {
+        // values of the wrong type or unknown ids are rejected when decoding the response
+        let res = inst.get_values(&ids)?;
+
+        for variable in &variables {
+            if !has_typed_value(variable, &res.values) {
+                return Err(violation(format!("getValues returned no {:?} value for variable {} (id {})",
+                    variable.value_type, variable.name, variable.id)));
+            }
+        }
+        if n_values(&res.values) != ids.len() {
+            return Err(violation(format!("getValues returned {} values for {} requested variables",
+                n_values(&res.values), ids.len())));
+        }
+
+        if step == 0 {
+            inst.set_values(&default_values(&md, &[MD::CausalityType::Input]))?;
+            let res = inst.do_step(t, dt)?;
+            check_status("doStep", res.status, res.message)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_alloc_balance(inst: &mut Instance) -> Result<(), DtasmtimeError> {
+    let mut blocks: Vec<(i32, Vec<u8>)> = Vec::new();
+    for (i, size) in ALLOC_SIZES.iter().enumerate() {
+        let ptr = inst.alloc(*size)?;
+        if ptr == 0 {
+            return Err(violation(format!("alloc({}) returned a null pointer", size)));
+        }
+
+        let pattern = vec![i as u8 + 1; *size as usize];
+        inst.write_memory(ptr, &pattern)?;
+        blocks.push((ptr, pattern));
+    }
+    for (ptr, pattern) in &blocks {
+        if inst.read_memory(*ptr, pattern.len())? != *pattern {
+            return Err(violation(format!("Block allocated at {} overlaps with another allocation", ptr)));
+        }
+    }
+    for (ptr, _) in &blocks {
+        inst.dealloc(*ptr)?;
+    }
+
+    let pages = inst.memory_pages();
+    for _ in 0..N_ALLOC_CYCLES {
+        for size in ALLOC_SIZES {
+            let ptr = inst.alloc(size)?;
+            inst.dealloc(ptr)?;
+        }
+    }
+    if inst.memory_pages() > pages {
+        return Err(violation(format!("Memory grew from {} to {} pages over {} alloc/dealloc cycles",
+            pages, inst.memory_pages(), N_ALLOC_CYCLES)));
+    }
+
+    // the first step may allocate lasting module state, so measure from the second step on
+    let md = initialize(inst, true)?;
+    let inputs = default_values(&md, &[MD::CausalityType::Input]);
+    let ids: Vec<i32> = non_input_variables(&md).iter().map(|v| v.id).collect();
+    let (mut t, dt) = time_steps(&md);
+
+    let mut pages = 0;
+    for step in 0..N_STEPS {
+        if step == 1 {
+            pages = inst.memory_pages();
+        }
+
+        inst.set_values(&inputs)?;
+        t = inst.do_step(t, dt)?.updated_time;
+        inst.get_values(&ids)?;
+    }
+    if inst.memory_pages() > pages {
+        return Err(violation(format!("Memory grew from {} to {} pages over {} steps",
+            pages, inst.memory_pages(), N_STEPS)));
+    }
+
+    Ok(())
+}
+
+fn time_steps(md: &MD::ModelDescription) -> (f64, f64) {
+    match &md.experiment {
+        Some(exp) if exp.time_step_default > 0.0 => (exp.start_time_default, exp.time_step_default),
+        Some(exp) => (exp.start_time_default, DEFAULT_TIMESTEP),
+        None => (0.0, DEFAULT_TIMESTEP)
+    }
+}
+
+fn check_monotonic_time(inst: &mut Instance) -> Result<(), DtasmtimeError> {
+    let md = initialize(inst, true)?;
+    let inputs = default_values(&md, &[MD::CausalityType::Input]);
+    let (mut t, dt) = time_steps(&md);
+
+    for _ in 0..N_STEPS {
+        inst.set_values(&inputs)?;
+        let res = inst.do_step(t, dt)?;
+        check_status("doStep", res.status, res.message)?;
+
+        if res.updated_time <= t {
+            return Err(violation(format!("Time did not advance in doStep from t = {} (updated time {})",
+                t, res.updated_time)));
+        }
+        if res.updated_time > t + dt + 1e-9 * dt.max(t.abs()) {
+            return Err(violation(format!("doStep from t = {} with step {} advanced to {}",
+                t, dt, res.updated_time)));
+        }
+        if let Some(t_event) = res.next_time_event {
+            if t_event < res.updated_time {
+                return Err(violation(format!("doStep to t = {} announced a time event in the past at {}",
+                    res.updated_time, t_event)));
+            }
+        }
+
+        let current_time = inst.get_values(&Vec::new())?.current_time;
+        if current_time != res.updated_time {
+            return Err(violation(format!("getValues reported time {} after doStep returned {}",
+                current_time, res.updated_time)));
+        }
+
+        t = res.updated_time;
+    }
+
+    Ok(())
+}
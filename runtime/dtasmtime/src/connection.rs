@@ -0,0 +1,149 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Connections from outputs of one dtasm instance to inputs of another. A
+//! connection checks that both variables are compatible (type, dimensions and
+//! physical dimension of their units) and converts values between the units of
+//! the variables when transferring them.
+
+use std::collections::HashMap;
+
+use crate::errors::DtasmtimeError;
+use crate::errors::DtasmtimeError::DtasmError as DTERR;
+use dtasm_base::errors::DtasmError;
+use dtasm_base::model_description as MD;
+use dtasm_base::types::DtasmVarValues;
+use dtasm_base::units::{UnitConversion, UnitRegistry};
+
+/// Connection of an output variable to an input variable
+#[derive(Debug,Clone)]
+pub struct Connection {
+    from_id: i32,
+    to_id: i32,
+    value_type: MD::VariableType,
+    is_array: bool,
+    conversion: UnitConversion
+}
+
+impl Connection {
+    /// Create a connection after checking the compatibility of both variables;
+    /// variables without unit are connected without unit check or conversion
+    ///
+    /// * `from_md` - model description of the module providing the value
+    /// * `from_id` - id of the output variable
+    /// * `to_md` - model description of the module receiving the value
+    /// * `to_id` - id of the input variable
+    pub fn new(from_md: &MD::ModelDescription, from_id: i32, to_md: &MD::ModelDescription, to_id: i32) -> Result<Connection, DtasmtimeError> {
+        let from_var = find_variable(from_md, from_id)?;
+        let to_var = find_variable(to_md, to_id)?;
+
+        if from_var.causality != MD::CausalityType::Output {
+            return Err(DTERR(DtasmError::VariableCausalityMismatch(from_var.causality, from_id)));
+        }
+        if to_var.causality != MD::CausalityType::Input {
+            return Err(DTERR(DtasmError::VariableCausalityMismatch(to_var.causality, to_id)));
+        }
+        if from_var.value_type != to_var.value_type {
+            return Err(DTERR(DtasmError::VariableTypeMismatch(from_var.value_type, to_id)));
+        }
+        if from_var.dimensions != to_var.dimensions {
+            return Err(DTERR(DtasmError::VariableDimensionMismatch(from_var.array_len(), to_id)));
+        }
+
+        let mut conversion = UnitConversion::identity();
+        if !from_var.unit.is_empty() && !to_var.unit.is_empty() {
+            let from_unit = UnitRegistry::with_model_description(from_md).resolve(&from_var.unit)?;
+            let to_unit = UnitRegistry::with_model_description(to_md).resolve(&to_var.unit)?;
+
+            conversion = from_unit.conversion_to(&to_unit)
+                .ok_or_else(|| DtasmError::IncompatibleUnits(from_var.unit.clone(), to_var.unit.clone()))?;
+        }
+
+        if conversion.is_identity() {
+            conversion = UnitConversion::identity();
+        }
+        else if !is_floating_point(from_var.value_type) {
+            return Err(DTERR(DtasmError::IncompatibleUnits(from_var.unit.clone(), to_var.unit.clone())));
+        }
+
+        Ok(Connection {
+            from_id,
+            to_id,
+            value_type: from_var.value_type,
+            is_array: from_var.is_array(),
+            conversion
+        })
+    }
+
+    pub fn from_id(&self) -> i32 {
+        self.from_id
+    }
+
+    pub fn to_id(&self) -> i32 {
+        self.to_id
+    }
+
+    /// Conversion applied to the values transferred along the connection
+    pub fn conversion(&self) -> UnitConversion {
+        self.conversion
+    }
+
+    /// Copy the value of the output variable (if contained in `source`) into
+    /// `target` as value of the input variable, converted to its unit
+    ///
+    /// * `source` - values of the providing instance, e.g. from `Instance::get_values`
+    /// * `target` - values to be set on the receiving instance
+    pub fn transfer(&self, source: &DtasmVarValues, target: &mut DtasmVarValues) {
+        let conv = self.conversion;
+        let (from, to) = (self.from_id, self.to_id);
+
+        match (self.value_type, self.is_array) {
+            (MD::VariableType::DtasmReal, false) => copy_value(&source.real_values, from, &mut target.real_values, to, |v| conv.apply(*v)),
+            (MD::VariableType::DtasmInt, false) => copy_value(&source.int_values, from, &mut target.int_values, to, |v| *v),
+            (MD::VariableType::DtasmBool, false) => copy_value(&source.bool_values, from, &mut target.bool_values, to, |v| *v),
+            (MD::VariableType::DtasmString, false) => copy_value(&source.string_values, from, &mut target.string_values, to, |v| v.clone()),
+            (MD::VariableType::DtasmReal, true) => copy_value(&source.real_arrays, from, &mut target.real_arrays, to,
+                |v| v.iter().map(|x| conv.apply(*x)).collect()),
+            (MD::VariableType::DtasmInt, true) => copy_value(&source.int_arrays, from, &mut target.int_arrays, to, |v| v.clone()),
+            (MD::VariableType::DtasmBool, true) => copy_value(&source.bool_arrays, from, &mut target.bool_arrays, to, |v| v.clone()),
+            (MD::VariableType::DtasmString, true) => copy_value(&source.string_arrays, from, &mut target.string_arrays, to, |v| v.clone()),
+            (MD::VariableType::DtasmBinary, _) => copy_value(&source.binary_values, from, &mut target.binary_values, to, |v| v.clone()),
+            (MD::VariableType::DtasmInt64, false) => copy_value(&source.int64_values, from, &mut target.int64_values, to, |v| *v),
+            (MD::VariableType::DtasmFloat32, false) => copy_value(&source.float32_values, from, &mut target.float32_values, to,
+                |v| conv.apply(*v as f64) as f32),
+            (MD::VariableType::DtasmInt64, true) => copy_value(&source.int64_arrays, from, &mut target.int64_arrays, to, |v| v.clone()),
+            (MD::VariableType::DtasmFloat32, true) => copy_value(&source.float32_arrays, from, &mut target.float32_arrays, to,
+                |v| v.iter().map(|x| conv.apply(*x as f64) as f32).collect())
+        }
+    }
+}
+
+/// Transfer values along all given connections; the result can be passed to
+/// `Instance::set_values` of the receiving instance
+///
+/// * `connections` - connections from the providing to the receiving instance
+/// * `source` - values of the providing instance
+pub fn transfer_values(connections: &[Connection], source: &DtasmVarValues) -> DtasmVarValues {
+    let mut target = DtasmVarValues::new();
+    for connection in connections {
+        connection.transfer(source, &mut target);
+    }
+
+    target
+}
+
+fn find_variable(md: &MD::ModelDescription, id: i32) -> Result<&MD::ModelVariable, DtasmtimeError> {
+    md.variables.iter()
+        .find(|v| v.id == id)
+        .ok_or(DTERR(DtasmError::UnknownVariableId(id)))
+}
+
+fn is_floating_point(value_type: MD::VariableType) -> bool {
+    value_type == MD::VariableType::DtasmReal || value_type == MD::VariableType::DtasmFloat32
+}
+
+fn copy_value<T>(source: &HashMap<i32, T>, from_id: i32, target: &mut HashMap<i32, T>, to_id: i32, convert: impl Fn(&T) -> T) {
+    if let Some(value) = source.get(&from_id) {
+        target.insert(to_id, convert(value));
+    }
+}
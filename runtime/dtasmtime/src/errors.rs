@@ -13,4 +13,14 @@ pub enum DtasmtimeError {
     DtasmError(#[from] errors::DtasmError),
     #[error(transparent)]
     IoError(#[from] std::io::Error),
+    #[error("Invalid result file: `{0}`")]
+    ResultFormatError(String),
+    #[error("Conformance violation: {0}")]
+    ConformanceError(String),
+    #[cfg(feature = "columnar")]
+    #[error(transparent)]
+    ArrowError(#[from] arrow::error::ArrowError),
+    #[cfg(feature = "columnar")]
+    #[error(transparent)]
+    ParquetError(#[from] parquet::errors::ParquetError),
 }
@@ -2,6 +2,14 @@
 // SPDX-License-Identifier: MIT
 
 pub mod runtime;
-mod errors;
+pub mod results;
+pub mod conformance;
+pub mod connection;
+pub mod stepping;
+pub mod errors;
+pub use dtasm_base::abi;
+pub use dtasm_base::bounds;
 pub use dtasm_base::model_description;
-pub use dtasm_base::types;
\ No newline at end of file
+pub use dtasm_base::types;
+pub use dtasm_base::units;
+pub use dtasm_base::validation;
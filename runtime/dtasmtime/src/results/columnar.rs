@@ -0,0 +1,330 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Columnar recording of simulation results into Arrow IPC or Parquet files.
+//! Values are collected in typed Arrow columns and streamed to the file in
+//! record batches of configurable size. The model description is stored as
+//! JSON in the schema metadata (key `dtasm.model_description`); each field
+//! carries id, unit, causality and description of its variable. Array
+//! variables are recorded as fixed size lists of their elements in row-major
+//! order, their dimensions are given in the field metadata.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow::array::{ArrayBuilder, ArrayRef, BinaryBuilder, BooleanBuilder, FixedSizeListBuilder, Float32Builder, Float64Builder, 
+    Int32Builder, Int64Builder, StringBuilder};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+
+use crate::errors::DtasmtimeError;
+use dtasm_base::model_description as MD;
+use dtasm_base::types::DtasmVarValues;
+
+const TIME_COLUMN: &str = "time";
+
+/// Schema metadata key of the JSON model description
+pub const MODEL_DESCRIPTION_KEY: &str = "dtasm.model_description";
+
+/// File format written by an `ArrowRecorder`
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum ColumnarFormat {
+    ArrowIpc,
+    Parquet
+}
+
+enum ColumnBuilder {
+    Real(Float64Builder),
+    Int(Int32Builder),
+    Bool(BooleanBuilder),
+    String(StringBuilder),
+    Binary(BinaryBuilder),
+    Int64(Int64Builder),
+    Float32(Float32Builder),
+    RealArray(FixedSizeListBuilder<Float64Builder>),
+    IntArray(FixedSizeListBuilder<Int32Builder>),
+    BoolArray(FixedSizeListBuilder<BooleanBuilder>),
+    StringArray(FixedSizeListBuilder<StringBuilder>),
+    Int64Array(FixedSizeListBuilder<Int64Builder>),
+    Float32Array(FixedSizeListBuilder<Float32Builder>)
+}
+
+enum Sink {
+    ArrowIpc(FileWriter<File>),
+    Parquet(ArrowWriter<File>)
+}
+
+/// Records values of all time-varying (input, output and local) variables of
+/// a dtasm instance into typed columns
+pub struct ArrowRecorder {
+    schema: SchemaRef,
+    var_ids: Vec<i32>,
+    time: Float64Builder,
+    columns: Vec<ColumnBuilder>,
+    batch_size: usize,
+    n_rows: usize,
+    sink: Sink
+}
+
+impl ArrowRecorder {
+    /// Create the output file and write the schema derived from the model
+    /// description
+    ///
+    /// * `filepath` - path of the file to create
+    /// * `format` - Arrow IPC file or Parquet
+    /// * `md` - model description of the simulated module
+    /// * `batch_size` - number of time points collected per record batch
+    pub fn create(filepath: PathBuf, format: ColumnarFormat, md: &MD::ModelDescription,
+        batch_size: usize) -> Result<ArrowRecorder, DtasmtimeError> {
+
+        let mut model_vars: Vec<&MD::ModelVariable> = md.variables.iter()
+            .filter(|v| v.causality != MD::CausalityType::Parameter)
+            .collect();
+        model_vars.sort_by(|a,b| a.id.cmp(&b.id));
+
+        let time_unit = match &md.experiment {
+            Some(exp) => exp.time_unit.clone(),
+            None => String::new()
+        };
+        let mut time_meta = BTreeMap::new();
+        time_meta.insert("dtasm.unit".to_string(), time_unit);
+
+        let mut fields = vec![Field::new(TIME_COLUMN, DataType::Float64, false)
+            .with_metadata(Some(time_meta))];
+        let mut var_ids = Vec::new();
+        let mut columns = Vec::new();
+
+        for model_var in model_vars {
+            let mut data_type = match model_var.value_type {
+                MD::VariableType::DtasmReal => DataType::Float64,
+                MD::VariableType::DtasmInt => DataType::Int32,
+                MD::VariableType::DtasmBool => DataType::Boolean,
+                MD::VariableType::DtasmString => DataType::Utf8,
+                MD::VariableType::DtasmBinary => DataType::Binary,
+                MD::VariableType::DtasmInt64 => DataType::Int64,
+                MD::VariableType::DtasmFloat32 => DataType::Float32
+            };
+            if model_var.is_array() {
+                // matches the data type built by `FixedSizeListBuilder`
+                data_type = DataType::FixedSizeList(Box::new(Field::new("item", data_type, true)), 
+                    model_var.array_len() as i32);
+            }
+
+            fields.push(Field::new(&model_var.name, data_type, true)
+                .with_metadata(Some(field_metadata(model_var))));
+            var_ids.push(model_var.id);
+            columns.push(ColumnBuilder::new(model_var, batch_size)?);
+        }
+
+        let schema = Arc::new(Schema::new_with_metadata(fields, schema_metadata(md)?));
+
+        let file = File::create(filepath)?;
+        let sink = match format {
+            ColumnarFormat::ArrowIpc => Sink::ArrowIpc(FileWriter::try_new(file, &schema)?),
+            ColumnarFormat::Parquet => Sink::Parquet(ArrowWriter::try_new(file, schema.clone(), None)?)
+        };
+
+        Ok(ArrowRecorder {
+            schema,
+            var_ids,
+            time: Float64Builder::with_capacity(batch_size),
+            columns,
+            batch_size: batch_size.max(1),
+            n_rows: 0,
+            sink
+        })
+    }
+
+    /// Append the values at time `t`; variables without a value in
+    /// `var_values` are recorded as null, array values of the wrong length are 
+    /// rejected (and nothing is recorded). A record batch is written once
+    /// `batch_size` time points have been collected.
+    pub fn record(&mut self, var_values: &DtasmVarValues, t: f64) -> Result<(), DtasmtimeError> {
+        for (var_id, column) in self.var_ids.iter().zip(self.columns.iter()) {
+            column.check_len(*var_id, var_values)?;
+        }
+
+        self.time.append_value(t);
+
+        for (var_id, column) in self.var_ids.iter().zip(self.columns.iter_mut()) {
+            column.append(*var_id, var_values);
+        }
+
+        self.n_rows += 1;
+        if self.n_rows >= self.batch_size {
+            self.write_batch()?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the remaining values and close the file
+    pub fn finish(mut self) -> Result<(), DtasmtimeError> {
+        if self.n_rows > 0 {
+            self.write_batch()?;
+        }
+
+        match self.sink {
+            Sink::ArrowIpc(mut writer) => { writer.finish()?; },
+            Sink::Parquet(writer) => { writer.close()?; }
+        };
+
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> Result<(), DtasmtimeError> {
+        let mut arrays: Vec<ArrayRef> = vec![Arc::new(self.time.finish())];
+        for column in self.columns.iter_mut() {
+            arrays.push(column.finish());
+        }
+
+        let batch = RecordBatch::try_new(self.schema.clone(), arrays)?;
+        match &mut self.sink {
+            Sink::ArrowIpc(writer) => writer.write(&batch)?,
+            Sink::Parquet(writer) => writer.write(&batch)?
+        };
+
+        self.n_rows = 0;
+
+        Ok(())
+    }
+}
+
+impl ColumnBuilder {
+    fn new(model_var: &MD::ModelVariable, capacity: usize) -> Result<ColumnBuilder, DtasmtimeError> {
+        if !model_var.is_array() {
+            return Ok(match model_var.value_type {
+                MD::VariableType::DtasmReal => ColumnBuilder::Real(Float64Builder::with_capacity(capacity)),
+                MD::VariableType::DtasmInt => ColumnBuilder::Int(Int32Builder::with_capacity(capacity)),
+                MD::VariableType::DtasmBool => ColumnBuilder::Bool(BooleanBuilder::with_capacity(capacity)),
+                MD::VariableType::DtasmString => ColumnBuilder::String(StringBuilder::with_capacity(capacity, 16 * capacity)),
+                MD::VariableType::DtasmBinary => ColumnBuilder::Binary(BinaryBuilder::with_capacity(capacity, 16 * capacity)),
+                MD::VariableType::DtasmInt64 => ColumnBuilder::Int64(Int64Builder::with_capacity(capacity)),
+                MD::VariableType::DtasmFloat32 => ColumnBuilder::Float32(Float32Builder::with_capacity(capacity))
+            });
+        }
+
+        let len = model_var.array_len() as i32;
+        let n = capacity * model_var.array_len();
+        Ok(match model_var.value_type {
+            MD::VariableType::DtasmReal => ColumnBuilder::RealArray(FixedSizeListBuilder::new(Float64Builder::with_capacity(n), len)),
+            MD::VariableType::DtasmInt => ColumnBuilder::IntArray(FixedSizeListBuilder::new(Int32Builder::with_capacity(n), len)),
+            MD::VariableType::DtasmBool => ColumnBuilder::BoolArray(FixedSizeListBuilder::new(BooleanBuilder::with_capacity(n), len)),
+            MD::VariableType::DtasmString => ColumnBuilder::StringArray(FixedSizeListBuilder::new(StringBuilder::with_capacity(n, 16 * n), len)),
+            MD::VariableType::DtasmInt64 => ColumnBuilder::Int64Array(FixedSizeListBuilder::new(Int64Builder::with_capacity(n), len)),
+            MD::VariableType::DtasmFloat32 => ColumnBuilder::Float32Array(FixedSizeListBuilder::new(Float32Builder::with_capacity(n), len)),
+            MD::VariableType::DtasmBinary => return Err(DtasmtimeError::ResultFormatError(
+                format!("Binary variable `{}` cannot be an array", model_var.name)))
+        })
+    }
+
+    /// Check that an array value given for the column has the declared number 
+    /// of elements
+    fn check_len(&self, id: i32, var_values: &DtasmVarValues) -> Result<(), DtasmtimeError> {
+        let (expected, actual) = match self {
+            ColumnBuilder::RealArray(b) => (b.value_length(), var_values.real_arrays.get(&id).map(|v| v.len())),
+            ColumnBuilder::IntArray(b) => (b.value_length(), var_values.int_arrays.get(&id).map(|v| v.len())),
+            ColumnBuilder::BoolArray(b) => (b.value_length(), var_values.bool_arrays.get(&id).map(|v| v.len())),
+            ColumnBuilder::StringArray(b) => (b.value_length(), var_values.string_arrays.get(&id).map(|v| v.len())),
+            ColumnBuilder::Int64Array(b) => (b.value_length(), var_values.int64_arrays.get(&id).map(|v| v.len())),
+            ColumnBuilder::Float32Array(b) => (b.value_length(), var_values.float32_arrays.get(&id).map(|v| v.len())),
+            _ => return Ok(())
+        };
+
+        match actual {
+            Some(len) if len != expected as usize => Err(DtasmtimeError::ResultFormatError(
+                format!("Array variable {} has {} elements instead of {}", id, len, expected))),
+            _ => Ok(())
+        }
+    }
+
+    fn append(&mut self, id: i32, var_values: &DtasmVarValues) {
+        match self {
+            ColumnBuilder::Real(b) => b.append_option(var_values.real_values.get(&id).copied()),
+            ColumnBuilder::Int(b) => b.append_option(var_values.int_values.get(&id).copied()),
+            ColumnBuilder::Bool(b) => b.append_option(var_values.bool_values.get(&id).copied()),
+            ColumnBuilder::String(b) => b.append_option(var_values.string_values.get(&id)),
+            ColumnBuilder::Binary(b) => b.append_option(var_values.binary_values.get(&id)),
+            ColumnBuilder::Int64(b) => b.append_option(var_values.int64_values.get(&id).copied()),
+            ColumnBuilder::Float32(b) => b.append_option(var_values.float32_values.get(&id).copied()),
+            ColumnBuilder::RealArray(b) => append_list(b, var_values.real_arrays.get(&id), |v, x| v.append_value(*x), |v| v.append_null()),
+            ColumnBuilder::IntArray(b) => append_list(b, var_values.int_arrays.get(&id), |v, x| v.append_value(*x), |v| v.append_null()),
+            ColumnBuilder::BoolArray(b) => append_list(b, var_values.bool_arrays.get(&id), |v, x| v.append_value(*x), |v| v.append_null()),
+            ColumnBuilder::StringArray(b) => append_list(b, var_values.string_arrays.get(&id), |v, x| v.append_value(x), |v| v.append_null()),
+            ColumnBuilder::Int64Array(b) => append_list(b, var_values.int64_arrays.get(&id), |v, x| v.append_value(*x), |v| v.append_null()),
+            ColumnBuilder::Float32Array(b) => append_list(b, var_values.float32_arrays.get(&id), |v, x| v.append_value(*x), |v| v.append_null())
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Real(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int(b) => Arc::new(b.finish()),
+            ColumnBuilder::Bool(b) => Arc::new(b.finish()),
+            ColumnBuilder::String(b) => Arc::new(b.finish()),
+            ColumnBuilder::Binary(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int64(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float32(b) => Arc::new(b.finish()),
+            ColumnBuilder::RealArray(b) => Arc::new(b.finish()),
+            ColumnBuilder::IntArray(b) => Arc::new(b.finish()),
+            ColumnBuilder::BoolArray(b) => Arc::new(b.finish()),
+            ColumnBuilder::StringArray(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int64Array(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float32Array(b) => Arc::new(b.finish())
+        }
+    }
+}
+
+/// Append an array value as list entry; a missing value is appended as null 
+/// list, which still occupies `value_length` (null) elements
+fn append_list<B: ArrayBuilder, T>(builder: &mut FixedSizeListBuilder<B>, values: Option<&Vec<T>>,
+    append_value: impl Fn(&mut B, &T), append_null: impl Fn(&mut B)) {
+    match values {
+        Some(values) => {
+            values.iter().for_each(|x| append_value(builder.values(), x));
+            builder.append(true);
+        },
+        None => {
+            for _ in 0..builder.value_length() {
+                append_null(builder.values());
+            }
+            builder.append(false);
+        }
+    }
+}
+
+fn field_metadata(model_var: &MD::ModelVariable) -> BTreeMap<String, String> {
+    let mut meta = BTreeMap::new();
+    meta.insert("dtasm.id".to_string(), model_var.id.to_string());
+    meta.insert("dtasm.unit".to_string(), model_var.unit.clone());
+    meta.insert("dtasm.causality".to_string(), format!("{:?}", model_var.causality));
+    meta.insert("dtasm.description".to_string(), model_var.description.clone());
+    if model_var.is_array() {
+        let dimensions: Vec<String> = model_var.dimensions.iter().map(|d| d.to_string()).collect();
+        meta.insert("dtasm.dimensions".to_string(), dimensions.join(","));
+    }
+
+    meta
+}
+
+fn schema_metadata(md: &MD::ModelDescription) -> Result<HashMap<String, String>, DtasmtimeError> {
+    let mut meta = HashMap::new();
+    meta.insert(MODEL_DESCRIPTION_KEY.to_string(), md.to_json()?);
+
+    Ok(meta)
+}
+
+/// Model description stored in the schema of a file written by an `ArrowRecorder`
+///
+/// * `schema` - schema as read from an Arrow IPC or Parquet file
+pub fn read_model_description(schema: &Schema) -> Result<MD::ModelDescription, DtasmtimeError> {
+    let json = schema.metadata().get(MODEL_DESCRIPTION_KEY)
+        .ok_or_else(|| DtasmtimeError::ResultFormatError(
+            format!("Schema metadata `{}` missing", MODEL_DESCRIPTION_KEY)))?;
+
+    Ok(MD::ModelDescription::from_json(json)?)
+}
@@ -0,0 +1,467 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Simulation results in the MATLAB v4 based result format used by Modelica
+//! tools (Dymola, OpenModelica). A result file consists of the matrices
+//! `Aclass`, `name`, `description`, `dataInfo`, `data_1` (parameters, stored
+//! at start and end time) and `data_2` (time-varying variables, one column
+//! per time point). Matrices are written in the transposed (`binTrans`)
+//! layout, which allows to append time points while the simulation runs.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use crate::errors::DtasmtimeError;
+use DtasmtimeError::DtasmError as DTERR;
+use DtasmtimeError::ResultFormatError;
+use dtasm_base::errors::DtasmError;
+use dtasm_base::model_description as MD;
+use dtasm_base::types::DtasmVarValues;
+
+/// MOPT type codes (little endian IEEE, full matrix)
+const MAT_DOUBLE: i32 = 0;
+const MAT_INT32: i32 = 20;
+const MAT_TEXT: i32 = 51;
+
+const MAT_HEADER_SIZE: u64 = 20;
+const DATA_1: &str = "data_1";
+const DATA_2: &str = "data_2";
+
+/// Writes simulation results of a dtasm instance to a Modelica compatible
+/// `.mat` file. Parameters go to `data_1`, time-varying (input, output and
+/// local) variables to `data_2`. Array variables are stored as one variable
+/// per element, named like `x[1]`, `x[2,3]` (indices starting at 1). String
+/// and binary variables cannot be represented in this format and are omitted.
+pub struct MatResultWriter {
+    file: BufWriter<File>,
+    data_2_vars: Vec<ElementRef>,
+    data_1_end_time_pos: u64,
+    data_2_ncols_pos: u64,
+    n_points: i32,
+    last_time: f64,
+}
+
+impl MatResultWriter {
+    /// Create the result file and write all matrices except the time points
+    /// of `data_2`, which are appended by calls to `write_record`.
+    ///
+    /// * `filepath` - path of the `.mat` file to create
+    /// * `md` - model description of the simulated module
+    /// * `parameters` - values of the parameters; variables missing here fall
+    ///    back to the default given in the model description
+    /// * `start_time` - start time of the simulation
+    pub fn create(filepath: PathBuf, md: &MD::ModelDescription, parameters: &DtasmVarValues,
+        start_time: f64) -> Result<MatResultWriter, DtasmtimeError> {
+
+        let mut variables: Vec<&MD::ModelVariable> = md.variables.iter()
+            .filter(|v| v.value_type != MD::VariableType::DtasmString && v.value_type != MD::VariableType::DtasmBinary)
+            .collect();
+        variables.sort_by(|a,b| a.id.cmp(&b.id));
+
+        let time_unit = match &md.experiment {
+            Some(exp) if !exp.time_unit.is_empty() => exp.time_unit.clone(),
+            _ => "s".to_string()
+        };
+
+        let mut names: Vec<String> = vec!["time".to_string()];
+        let mut descriptions: Vec<String> = vec![format!("Simulation time [{}]", time_unit)];
+        let mut data_info: Vec<i32> = vec![0, 1, 0, -1];
+        let mut param_values: Vec<f64> = vec![start_time];
+        let mut data_2_vars: Vec<ElementRef> = Vec::new();
+
+        for variable in &variables {
+            for (element, name) in element_names(variable).into_iter().enumerate() {
+                let elem = ElementRef { 
+                    id: variable.id, 
+                    value_type: variable.value_type, 
+                    index: if variable.is_array() { Some(element) } else { None } 
+                };
+                names.push(name);
+                descriptions.push(describe(variable));
+
+                if variable.causality == MD::CausalityType::Parameter {
+                    param_values.push(parameter_value(variable, &elem, parameters));
+                    data_info.extend_from_slice(&[1, param_values.len() as i32, 0, 0]);
+                }
+                else {
+                    data_2_vars.push(elem);
+                    data_info.extend_from_slice(&[2, data_2_vars.len() as i32 + 1, 0, -1]);
+                }
+            }
+        }
+
+        let mut file = BufWriter::new(File::create(filepath)?);
+
+        write_text_rows(&mut file, "Aclass", &["Atrajectory", "1.1", "", "binTrans"])?;
+        write_text_columns(&mut file, "name", &names)?;
+        write_text_columns(&mut file, "description", &descriptions)?;
+
+        write_header(&mut file, "dataInfo", MAT_INT32, 4, names.len() as i32)?;
+        for val in &data_info {
+            file.write_all(&val.to_le_bytes())?;
+        }
+
+        // data_1 holds parameter values at start and end time; the end time is
+        // patched when the writer is finished
+        let n_rows_1 = param_values.len() as i32;
+        write_header(&mut file, DATA_1, MAT_DOUBLE, n_rows_1, 2)?;
+        let data_1_pos = file.stream_position()?;
+        for val in &param_values {
+            file.write_all(&val.to_le_bytes())?;
+        }
+        for val in &param_values {
+            file.write_all(&val.to_le_bytes())?;
+        }
+        let data_1_end_time_pos = data_1_pos + 8 * n_rows_1 as u64;
+
+        // data_2 starts with zero columns, the column count is patched as well
+        let data_2_pos = file.stream_position()?;
+        write_header(&mut file, DATA_2, MAT_DOUBLE, data_2_vars.len() as i32 + 1, 0)?;
+
+        Ok(MatResultWriter {
+            file,
+            data_2_vars,
+            data_1_end_time_pos,
+            data_2_ncols_pos: data_2_pos + 8,
+            n_points: 0,
+            last_time: start_time
+        })
+    }
+
+    /// Append the values of all time-varying variables at time `t`
+    pub fn write_record(&mut self, var_values: &DtasmVarValues, t: f64) -> Result<(), DtasmtimeError> {
+        self.file.write_all(&t.to_le_bytes())?;
+
+        for elem in &self.data_2_vars {
+            let val = element_value(elem, var_values)
+                .ok_or(DTERR(DtasmError::UnknownVariableId(elem.id)))?;
+
+            self.file.write_all(&val.to_le_bytes())?;
+        }
+
+        self.n_points += 1;
+        self.last_time = t;
+
+        Ok(())
+    }
+
+    /// Complete the result file by writing the number of recorded time points
+    /// and the end time
+    pub fn finish(mut self) -> Result<(), DtasmtimeError> {
+        self.file.seek(SeekFrom::Start(self.data_2_ncols_pos))?;
+        self.file.write_all(&self.n_points.to_le_bytes())?;
+
+        self.file.seek(SeekFrom::Start(self.data_1_end_time_pos))?;
+        self.file.write_all(&self.last_time.to_le_bytes())?;
+
+        self.file.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Contents of a Modelica result file
+///
+/// * `names` - variable names, starting with `time`
+/// * `descriptions` - variable descriptions (including the unit in brackets)
+/// * `data_info` - for each variable: data matrix (0 for time), signed column
+///    in the data matrix, interpolation and extrapolation flags
+/// * `data_1` - rows of `data_1`, one row per stored time point
+/// * `data_2` - rows of `data_2`, one row per stored time point
+#[derive(Debug,Clone)]
+pub struct MatResults {
+    pub names: Vec<String>,
+    pub descriptions: Vec<String>,
+    pub data_info: Vec<[i32; 4]>,
+    pub data_1: Vec<Vec<f64>>,
+    pub data_2: Vec<Vec<f64>>,
+}
+
+impl MatResults {
+    /// Time points at which time-varying variables were recorded
+    pub fn time(&self) -> Vec<f64> {
+        self.data_2.iter().map(|row| row[0]).collect()
+    }
+
+    /// Values of the variable `name` at all stored time points of its data
+    /// matrix (i.e. start and end time for parameters)
+    pub fn trajectory(&self, name: &str) -> Option<Vec<f64>> {
+        let idx = self.names.iter().position(|n| n == name)?;
+        let info = self.data_info.get(idx)?;
+
+        let data = match info[0] {
+            0 | 2 => &self.data_2,
+            1 => &self.data_1,
+            _ => return None
+        };
+        let col = (info[1].abs() - 1) as usize;
+        let sign = if info[1] < 0 { -1.0 } else { 1.0 };
+
+        data.iter().map(|row| row.get(col).map(|v| sign * v)).collect()
+    }
+}
+
+/// Read a Modelica result file as written by `MatResultWriter` (or by
+/// Modelica tools, in transposed or normal layout)
+pub fn read_mat_results(filepath: PathBuf) -> Result<MatResults, DtasmtimeError> {
+    let file = File::open(filepath)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut matrices: HashMap<String, Matrix> = HashMap::new();
+
+    while let Some(matrix) = read_matrix(&mut reader, file_len)? {
+        matrices.insert(matrix.name.clone(), matrix);
+    }
+
+    let get = |name: &str| matrices.get(name)
+        .ok_or(ResultFormatError(format!("Matrix `{}` missing", name)));
+
+    let aclass = get("Aclass")?.text_rows();
+    let transposed = match aclass.get(3).map(|s| s.as_str()) {
+        Some("binTrans") => true,
+        Some("binNormal") => false,
+        _ => return Err(ResultFormatError("Unsupported Aclass in result file".to_string()))
+    };
+
+    let names = get("name")?.texts(transposed);
+    let descriptions = get("description")?.texts(transposed);
+    let data_info = get("dataInfo")?.rows(transposed).iter()
+        .map(|col| {
+            let mut info = [0; 4];
+            for (i, v) in col.iter().take(4).enumerate() { info[i] = *v as i32; }
+            info
+        })
+        .collect();
+
+    Ok(MatResults {
+        names,
+        descriptions,
+        data_info,
+        data_1: get(DATA_1)?.rows(transposed),
+        data_2: get(DATA_2)?.rows(transposed)
+    })
+}
+
+/// A stored variable: a scalar variable or a single element of an array 
+/// variable (at `index` of its flattened values)
+struct ElementRef {
+    id: i32,
+    value_type: MD::VariableType,
+    index: Option<usize>,
+}
+
+/// Names of the stored elements of a variable, in row-major order for arrays
+fn element_names(variable: &MD::ModelVariable) -> Vec<String> {
+    if !variable.is_array() {
+        return vec![variable.name.clone()];
+    }
+
+    (0..variable.array_len())
+        .map(|mut flat| {
+            let mut indices: Vec<String> = variable.dimensions.iter().rev()
+                .map(|dim| {
+                    let dim = (*dim).max(1) as usize;
+                    let idx = flat % dim + 1;
+                    flat /= dim;
+                    idx.to_string()
+                })
+                .collect();
+            indices.reverse();
+            format!("{}[{}]", variable.name, indices.join(","))
+        })
+        .collect()
+}
+
+/// Value of a stored element as double, `None` if it is missing in `values`
+fn element_value(elem: &ElementRef, values: &DtasmVarValues) -> Option<f64> {
+    let (id, idx) = (&elem.id, elem.index);
+    let bool_val = |v: &bool| if *v { 1.0 } else { 0.0 };
+
+    match (elem.value_type, idx) {
+        (MD::VariableType::DtasmReal, None) => values.real_values.get(id).copied(),
+        (MD::VariableType::DtasmInt, None) => values.int_values.get(id).map(|v| *v as f64),
+        (MD::VariableType::DtasmBool, None) => values.bool_values.get(id).map(bool_val),
+        (MD::VariableType::DtasmInt64, None) => values.int64_values.get(id).map(|v| *v as f64),
+        (MD::VariableType::DtasmFloat32, None) => values.float32_values.get(id).map(|v| *v as f64),
+        (MD::VariableType::DtasmReal, Some(i)) => values.real_arrays.get(id)?.get(i).copied(),
+        (MD::VariableType::DtasmInt, Some(i)) => values.int_arrays.get(id)?.get(i).map(|v| *v as f64),
+        (MD::VariableType::DtasmBool, Some(i)) => values.bool_arrays.get(id)?.get(i).map(bool_val),
+        (MD::VariableType::DtasmInt64, Some(i)) => values.int64_arrays.get(id)?.get(i).map(|v| *v as f64),
+        (MD::VariableType::DtasmFloat32, Some(i)) => values.float32_arrays.get(id)?.get(i).map(|v| *v as f64),
+        (MD::VariableType::DtasmString, _) | (MD::VariableType::DtasmBinary, _) => None
+    }
+}
+
+fn describe(variable: &MD::ModelVariable) -> String {
+    if variable.unit.is_empty() {
+        variable.description.clone()
+    }
+    else if variable.description.is_empty() {
+        format!("[{}]", variable.unit)
+    }
+    else {
+        format!("{} [{}]", variable.description, variable.unit)
+    }
+}
+
+/// Value of a parameter element; array elements missing in `parameters` fall 
+/// back to the default of the variable, like scalars
+fn parameter_value(variable: &MD::ModelVariable, elem: &ElementRef, parameters: &DtasmVarValues) -> f64 {
+    let default = variable.default.as_ref().and_then(|d| match variable.value_type {
+        MD::VariableType::DtasmReal => Some(d.real_val),
+        MD::VariableType::DtasmInt => Some(d.int_val as f64),
+        MD::VariableType::DtasmBool => Some(if d.bool_val { 1.0 } else { 0.0 }),
+        MD::VariableType::DtasmInt64 => Some(d.int64_val as f64),
+        MD::VariableType::DtasmFloat32 => Some(d.float32_val as f64),
+        MD::VariableType::DtasmString | MD::VariableType::DtasmBinary => None
+    });
+
+    element_value(elem, parameters)
+        .or(default)
+        .unwrap_or(f64::NAN)
+}
+
+fn write_header<W: Write>(w: &mut W, name: &str, mat_type: i32, mrows: i32, ncols: i32) -> std::io::Result<()> {
+    let namlen = name.len() as i32 + 1;
+    for val in &[mat_type, mrows, ncols, 0, namlen] {
+        w.write_all(&val.to_le_bytes())?;
+    }
+    w.write_all(name.as_bytes())?;
+    w.write_all(&[0u8])
+}
+
+/// Write strings as columns of a text matrix (one string per column)
+fn write_text_columns<W: Write>(w: &mut W, name: &str, strings: &[String]) -> std::io::Result<()> {
+    let max_len = strings.iter().map(|s| s.len()).max().unwrap_or(0).max(1);
+
+    write_header(w, name, MAT_TEXT, max_len as i32, strings.len() as i32)?;
+    for s in strings {
+        w.write_all(s.as_bytes())?;
+        w.write_all(&vec![b' '; max_len - s.len()])?;
+    }
+
+    Ok(())
+}
+
+/// Write strings as rows of a text matrix (one string per row)
+fn write_text_rows<W: Write>(w: &mut W, name: &str, strings: &[&str]) -> std::io::Result<()> {
+    let max_len = strings.iter().map(|s| s.len()).max().unwrap_or(0).max(1);
+
+    write_header(w, name, MAT_TEXT, strings.len() as i32, max_len as i32)?;
+    for col in 0..max_len {
+        for s in strings {
+            w.write_all(&[*s.as_bytes().get(col).unwrap_or(&b' ')])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A single MAT v4 matrix, data stored in column-major order
+struct Matrix {
+    name: String,
+    mrows: usize,
+    ncols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    fn column(&self, col: usize) -> Vec<f64> {
+        self.data[col*self.mrows..(col+1)*self.mrows].to_vec()
+    }
+
+    fn row(&self, row: usize) -> Vec<f64> {
+        (0..self.ncols).map(|col| self.data[col*self.mrows + row]).collect()
+    }
+
+    /// Columns of the matrix if `by_column` is set, rows otherwise
+    fn rows(&self, by_column: bool) -> Vec<Vec<f64>> {
+        if by_column {
+            (0..self.ncols).map(|col| self.column(col)).collect()
+        }
+        else {
+            (0..self.mrows).map(|row| self.row(row)).collect()
+        }
+    }
+
+    fn text_rows(&self) -> Vec<String> {
+        self.rows(false).iter().map(|r| to_text(r)).collect()
+    }
+
+    fn texts(&self, by_column: bool) -> Vec<String> {
+        self.rows(by_column).iter().map(|r| to_text(r)).collect()
+    }
+}
+
+fn to_text(chars: &[f64]) -> String {
+    let bytes: Vec<u8> = chars.iter().map(|c| *c as u8).collect();
+    String::from_utf8_lossy(&bytes)
+        .trim_end_matches(|c| c == ' ' || c == '\0')
+        .to_string()
+}
+
+fn read_i32<R: Read>(r: &mut R) -> std::io::Result<i32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(i32::from_le_bytes(buf))
+}
+
+/// Read the next matrix from `r`, `None` at the end of the file. Sizes given
+/// in the matrix header are checked against the `file_len` before allocating.
+fn read_matrix<R: Read + Seek>(r: &mut R, file_len: u64) -> Result<Option<Matrix>, DtasmtimeError> {
+    let mut header = [0u8; MAT_HEADER_SIZE as usize];
+    let mut n_read = 0;
+    while n_read < header.len() {
+        let n = r.read(&mut header[n_read..])?;
+        if n == 0 { break; }
+        n_read += n;
+    }
+    if n_read == 0 { return Ok(None); }
+    if n_read < header.len() {
+        return Err(ResultFormatError("Truncated matrix header".to_string()));
+    }
+
+    let mut fields = [0i32; 5];
+    for (i, field) in fields.iter_mut().enumerate() {
+        *field = read_i32(&mut &header[4*i..4*i+4])?;
+    }
+    let [mat_type, mrows, ncols, imagf, namlen] = fields;
+
+    if mat_type < 0 || mat_type >= 1000 || mrows < 0 || ncols < 0 || namlen < 1 {
+        return Err(ResultFormatError(format!("Unsupported matrix type {}", mat_type)));
+    }
+    if imagf != 0 {
+        return Err(ResultFormatError("Complex matrices are not supported".to_string()));
+    }
+
+    if namlen as u64 > file_len.saturating_sub(r.stream_position()?) {
+        return Err(ResultFormatError("Matrix name exceeds the result file".to_string()));
+    }
+    let mut name_bytes = vec![0u8; namlen as usize];
+    r.read_exact(&mut name_bytes)?;
+    let name = String::from_utf8_lossy(&name_bytes).trim_end_matches('\0').to_string();
+
+    let (elem_size, convert): (usize, fn(&[u8]) -> f64) = match (mat_type / 10) % 10 {
+        0 => (8, |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])),
+        1 => (4, |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64),
+        2 => (4, |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64),
+        3 => (2, |b| i16::from_le_bytes([b[0], b[1]]) as f64),
+        4 => (2, |b| u16::from_le_bytes([b[0], b[1]]) as f64),
+        5 => (1, |b| b[0] as f64),
+        p => return Err(ResultFormatError(format!("Unsupported precision {} in matrix `{}`", p, name)))
+    };
+
+    let (mrows, ncols) = (mrows as usize, ncols as usize);
+    let remaining = file_len.saturating_sub(r.stream_position()?);
+    let size = mrows.checked_mul(ncols)
+        .and_then(|n| n.checked_mul(elem_size))
+        .filter(|size| *size as u64 <= remaining)
+        .ok_or_else(|| ResultFormatError(format!("Size of matrix `{}` exceeds the result file", name)))?;
+    let mut bytes = vec![0u8; size];
+    r.read_exact(&mut bytes)?;
+    let data = bytes.chunks_exact(elem_size).map(convert).collect();
+
+    Ok(Some(Matrix { name, mrows, ncols, data }))
+}
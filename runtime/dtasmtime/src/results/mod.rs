@@ -0,0 +1,8 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Writers (and readers) for persisting simulation results of a dtasm run.
+
+pub mod mat;
+#[cfg(feature = "columnar")]
+pub mod columnar;
@@ -1,7 +1,6 @@
 // Copyright 2021 Siemens AG
 // SPDX-License-Identifier: MIT
 
-use std::convert::identity; 
 use std::error::Error;
 use std::path::PathBuf;
 use std::io::{Read, Write};
@@ -18,9 +17,13 @@ use dtasm_abi::dtasm_generated::dtasm_model_description as DTMD;
 use crate::errors::DtasmtimeError;
 use DtasmtimeError::DtasmError as DTERR; 
 use dtasm_base::model_conversion::convert_model_description;
+use dtasm_base::value_conversion::{convert_var_values, create_var_values};
 use dtasm_base::model_description as MD;
-use dtasm_base::types::{DtasmVarType,DtasmVarValues,LogLevel,Status,GetValuesResponse,DoStepResponse};
+use dtasm_base::types::{DtasmVarType,DtasmVarValues,LogLevel,StatusResponse,GetValuesResponse,DoStepResponse};
 use dtasm_base::errors::DtasmError;
+use dtasm_base::validation::{validate, validate_strict, has_errors, Diagnostic};
+use dtasm_base::bounds::{apply_bounds_policy, BoundsPolicy, BoundsViolation};
+use dtasm_base::abi::{check_abi_feature, check_abi_version, AbiFeature, AbiVersion, ABI_VERSION_SECTION};
 
 type In1Out1T = WT::TypedFunc<i32,i32>;
 type In1Out0T = WT::TypedFunc<i32,()>;
@@ -42,35 +45,88 @@ static DTASM_EXPORTS: [&str; 8] = [
     "setValues",
     "doStep"];
 
+/// Log message reported by a module through the optional `dtasm.log` import
+///
+/// * `instance` - name of the reporting instance (file name of the module unless set
+///   with `Instance::set_name`)
+/// * `level` - log level of the message
+/// * `message` - the message
+#[derive(Debug,Clone)]
+pub struct LogRecord<'a> {
+    pub instance: &'a str,
+    pub level: LogLevel,
+    pub message: &'a str
+}
+
+/// Receiver of the log messages of an instance
+pub type LogCallback = Box<dyn FnMut(&LogRecord) + Send>;
+
+/// Host state of an instance in its wasmtime store
+struct HostState {
+    wasi: WTW::WasiCtx,
+    instance_name: String,
+    log_callback: Option<LogCallback>
+}
+
+/// Strictness of the semantic validation of model descriptions when they are loaded
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum ValidationLevel {
+    /// Do not validate model descriptions
+    Off,
+    /// Collect diagnostics (see `Instance::model_description_diagnostics`), 
+    /// but accept the model description
+    Warn,
+    /// Reject model descriptions with errors, including inputs without default value
+    Strict
+}
+
 /// Engine for executing modules
 pub struct Engine {
     wt_engine: WT::Engine, 
-    wt_linker: WT::Linker<WTW::WasiCtx>,
+    wt_linker: WT::Linker<HostState>,
+    validation_level: ValidationLevel
 }
 
 impl Engine {
     pub fn new() -> Result<Engine, Box<dyn Error>> {
+        Engine::with_validation(ValidationLevel::Warn)
+    }
+
+    /// Create an engine that validates model descriptions with the given strictness
+    pub fn with_validation(validation_level: ValidationLevel) -> Result<Engine, Box<dyn Error>> {
         let engine = WT::Engine::default();
         let mut linker = WT::Linker::new(&engine);
-        WTW::add_to_linker(&mut linker, |s| s)?;
+        WTW::add_to_linker(&mut linker, |s: &mut HostState| &mut s.wasi)?;
+        linker.func_wrap("dtasm", "log", log_message)?;
 
         Ok(Engine {
             wt_engine: engine,
-            wt_linker: linker
+            wt_linker: linker,
+            validation_level
         })
     }
 }
 
+/// Name of the custom section holding the model description FlatBuffer of a module
+pub const MODEL_DESCRIPTION_SECTION: &str = "dtasm.model_description";
+
 /// Represents a dtasm module in memory
 pub struct Module<'a> {
     wt_module: WT::Module,
-    dtasm_engine: &'a Engine
+    dtasm_engine: &'a Engine,
+    name: String,
+    md_section: Option<Vec<u8>>,
+    abi_version: AbiVersion
 }
 
 impl Module<'_> {
-    /// Loads a module from bytestream; note that the module needs to be tied to an engine at this point
+    /// Loads a module from bytestream; note that the module needs to be tied to an engine at this point.
+    /// Fails if the module was built for a dtasm ABI version not supported by the runtime, or 
+    /// imports `dtasm.log` without stating an ABI version that provides it.
     pub fn new(file: PathBuf, engine: &Engine) -> Result<Module, DtasmtimeError> {
-        let module = WT::Module::from_file(&engine.wt_engine, file)?;
+        let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let bytes = std::fs::read(file)?;
+        let module = WT::Module::new(&engine.wt_engine, &bytes)?;
 
         for name in DTASM_EXPORTS.iter() {
             if module.get_export(name).is_none() {
@@ -80,18 +136,58 @@ impl Module<'_> {
 
         // TODO: ensure that exports have expected signature
 
+        let abi_section = custom_section(&bytes, ABI_VERSION_SECTION)?;
+        let abi_version = check_abi_version(abi_section.as_deref())?;
+
+        let imports_log = module.imports().any(|import| import.module() == "dtasm" && import.name() == Some("log"));
+        if imports_log {
+            check_abi_feature(abi_version, AbiFeature::LogImport)?;
+        }
+
+        let md_section = custom_section(&bytes, MODEL_DESCRIPTION_SECTION)?;
+
         Ok(Module {
             wt_module: module, 
-            dtasm_engine: engine
+            dtasm_engine: engine,
+            name,
+            md_section,
+            abi_version
         })
     }
 
+    /// dtasm ABI version the module was built for (1.0 for modules that do not
+    /// state their version)
+    pub fn abi_version(&self) -> AbiVersion {
+        self.abi_version
+    }
+
+    /// Retrieve the model description of this module; it is read from the 
+    /// `dtasm.model_description` custom section if the module has one, otherwise 
+    /// the module is instantiated to call the `getModelDescription` export. Note 
+    /// that a model description read from the custom section is not validated.
+    pub fn model_description(&mut self) -> Result<MD::ModelDescription, DtasmtimeError> {
+        match &self.md_section {
+            Some(bytes) => {
+                let model_desc_fb = DTMD::root_as_model_description(bytes)
+                    .map_err(|e| DTERR(DtasmError::InvalidModelDescription(e.to_string())))?;
+
+                Ok(convert_model_description(&model_desc_fb))
+            },
+            None => self.instantiate()?.get_model_description()
+        }
+    }
+
     /// Create an instance of the module
     pub fn instantiate(&mut self) -> Result<Instance, DtasmtimeError> {
         let wasi = WTW::WasiCtxBuilder::new()
             .inherit_stdio()
             .build();
-        let mut store = WT::Store::new(&self.dtasm_engine.wt_engine, wasi);
+        let host_state = HostState {
+            wasi,
+            instance_name: self.name.clone(),
+            log_callback: None
+        };
+        let mut store = WT::Store::new(&self.dtasm_engine.wt_engine, host_state);
         let wt_instance = self.dtasm_engine.wt_linker.instantiate(&mut store, &self.wt_module)?;
 
         let reactor_init = wt_instance
@@ -127,9 +223,27 @@ impl Module<'_> {
             .get_func(&mut store, "doStep")
             .ok_or(DTERR(DtasmError::MissingDtasmExport("doStep".to_string())))?
             .typed::<(i32,i32,i32,i32),i32,_>(&store)?;
+        // optional, only exported by modules that can reset steps
+        let reset_step = match wt_instance.get_func(&mut store, "resetStep") {
+            Some(f) => Some(f.typed::<(i32,i32,i32,i32),i32,_>(&store)?),
+            None => None
+        };
+        // mutable globals are part of the state captured by snapshots; only 
+        // exported globals are accessible to the runtime
+        let globals: Vec<WT::Global> = wt_instance.exports(&mut store)
+            .filter_map(|export| export.into_global())
+            .collect();
+        let globals = globals.into_iter()
+            .filter(|global| {
+                let ty = global.ty(&store);
+                ty.mutability() == WT::Mutability::Var 
+                    && matches!(ty.content(), WT::ValType::I32 | WT::ValType::I64 | WT::ValType::F32 | WT::ValType::F64)
+            })
+            .collect();
 
         Ok(Instance {
             memory, 
+            globals,
             store: store,
             reactor_init_fn: reactor_init,
             alloc_fn: alloc, 
@@ -139,17 +253,88 @@ impl Module<'_> {
             get_values_fn: get_values,
             set_values_fn: set_values,
             do_step_fn: do_step,
+            reset_step_fn: reset_step,
             var_types: HashMap::new(),
             md: None, 
+            validation_level: self.dtasm_engine.validation_level,
+            diagnostics: Vec::new(),
+            bounds_policy: BoundsPolicy::default(),
+            bounds_violations: Vec::new(),
+            abi_version: self.abi_version,
             builder: FB::FlatBufferBuilder::with_capacity(FB_BUILDER_SIZE)
         })
     }
 }
 
+/// State of an instance as taken by `Instance::snapshot`: the values of the exported 
+/// mutable globals (e.g. `__stack_pointer`, if exported) and the linear memory
+///
+/// Serialized as number of globals (`u32`), the raw bits of each global (`u64`), all 
+/// little endian, followed by the contents of the memory.
+#[derive(Debug,Clone)]
+pub struct Snapshot {
+    bytes: Vec<u8>
+}
+
+impl Snapshot {
+    /// Snapshot from bytes previously obtained by `as_bytes`; checked when the 
+    /// snapshot is restored
+    pub fn from_bytes(bytes: Vec<u8>) -> Snapshot {
+        Snapshot { bytes }
+    }
+
+    /// Serialized snapshot
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn new(globals: &[u64], memory: &[u8]) -> Snapshot {
+        let mut bytes = Vec::with_capacity(4 + 8 * globals.len() + memory.len());
+        bytes.extend_from_slice(&(globals.len() as u32).to_le_bytes());
+        for global in globals {
+            bytes.extend_from_slice(&global.to_le_bytes());
+        }
+        bytes.extend_from_slice(memory);
+
+        Snapshot { bytes }
+    }
+
+    /// Raw bits of the globals and contents of the memory
+    fn parts(&self) -> Result<(Vec<u64>, &[u8]), DtasmtimeError> {
+        let invalid = || DTERR(DtasmError::DtasmInternalError("Invalid snapshot: truncated globals".to_string()));
+
+        let n_globals = self.bytes.get(..4).ok_or_else(invalid)?;
+        let n_globals = u32::from_le_bytes([n_globals[0], n_globals[1], n_globals[2], n_globals[3]]) as usize;
+        let memory_start = n_globals.checked_mul(8).and_then(|len| len.checked_add(4))
+            .filter(|start| *start <= self.bytes.len())
+            .ok_or_else(invalid)?;
+
+        let globals = self.bytes[4..memory_start].chunks_exact(8)
+            .map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+            .collect();
+
+        Ok((globals, &self.bytes[memory_start..]))
+    }
+}
+
+/// Contents of the first custom section with the given name, if any
+fn custom_section(bytes: &[u8], section_name: &str) -> Result<Option<Vec<u8>>, DtasmtimeError> {
+    for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+        if let wasmparser::Payload::CustomSection { name, data, .. } = payload.map_err(anyhow::Error::from)? {
+            if name == section_name {
+                return Ok(Some(data.to_vec()));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
 /// Represents an instance of a loaded dtasm module
 pub struct Instance {
-    memory: WT::Memory, 
-    store: WT::Store<WTW::WasiCtx>,
+    memory: WT::Memory,
+    globals: Vec<WT::Global>, 
+    store: WT::Store<HostState>,
     reactor_init_fn: Option<WT::Func>,
     alloc_fn: In1Out1T, 
     dealloc_fn: In1Out0T, 
@@ -158,8 +343,14 @@ pub struct Instance {
     get_values_fn: In4Out1T,
     do_step_fn: In4Out1T,
     set_values_fn: In4Out1T,
+    reset_step_fn: Option<In4Out1T>,
     var_types: HashMap<i32, DtasmVarType>,
     md: Option<MD::ModelDescription>, 
+    validation_level: ValidationLevel,
+    diagnostics: Vec<Diagnostic>,
+    bounds_policy: BoundsPolicy,
+    bounds_violations: Vec<BoundsViolation>,
+    abi_version: AbiVersion,
     builder: FB::FlatBufferBuilder<'static>
 }
 
@@ -188,18 +379,80 @@ impl Instance {
             size_out = self.get_md_fn.call(&mut self.store, (mem, size))?;
         }
 
+        // modules return an empty model description if they fail to provide one
+        if size_out == 0 {
+            self.dealloc_fn.call(&mut self.store, mem)?;
+            return Err(DTERR(DtasmError::InvalidModelDescription("Module returned an empty model description".to_string())));
+        }
+
         let bytes = &self.memory.data(&mut self.store)[mem as usize..(mem+size_out) as usize];
    
-        let model_desc_fb = DTMD::root_as_model_description(bytes).unwrap();
+        let model_desc_fb = DTMD::root_as_model_description(bytes)
+            .map_err(|e| DTERR(DtasmError::InvalidModelDescription(e.to_string())))?;
         let md = convert_model_description(&model_desc_fb);
-        self.md = Some(md.clone());
-        self.var_types = Instance::collect_var_types(&md)?;
 
         self.dealloc_fn.call(&mut self.store, mem)?;
+
+        self.validate_model_description(&md)?;
+        self.md = Some(md.clone());
+        self.var_types = Instance::collect_var_types(&md)?;
    
         Ok(md)
     }
 
+    /// dtasm ABI version the module of this instance was built for
+    pub fn abi_version(&self) -> AbiVersion {
+        self.abi_version
+    }
+
+    /// Set the name of the instance reported with its log messages
+    pub fn set_name(&mut self, name: &str) {
+        self.store.data_mut().instance_name = name.to_string();
+    }
+
+    /// Receive the log messages the module reports through the `dtasm.log` import
+    /// (dropped if no callback is set); messages above the `log_level` passed to 
+    /// `initialize` are filtered by the module
+    pub fn set_log_callback(&mut self, callback: LogCallback) {
+        self.store.data_mut().log_callback = Some(callback);
+    }
+
+    /// Diagnostics found when validating the model description; empty if validation
+    /// is turned off or the model description has not been retrieved yet
+    pub fn model_description_diagnostics(&self) -> &Vec<Diagnostic> {
+        &self.diagnostics
+    }
+
+    /// Set how `initialize` and `set_values` treat values outside the `min`/`max` 
+    /// bounds of their variable (values are rejected by default)
+    pub fn set_bounds_policy(&mut self, policy: BoundsPolicy) {
+        self.bounds_policy = policy;
+    }
+
+    /// Values outside the bounds of their variable that were accepted (`BoundsPolicy::Warn`) 
+    /// or clamped (`BoundsPolicy::Clamp`) by the last call to `initialize` or `set_values`
+    pub fn bounds_violations(&self) -> &Vec<BoundsViolation> {
+        &self.bounds_violations
+    }
+
+    fn validate_model_description(&mut self, md: &MD::ModelDescription) -> Result<(), DtasmtimeError> {
+        if self.validation_level == ValidationLevel::Off {
+            return Ok(());
+        }
+
+        self.diagnostics = match self.validation_level {
+            ValidationLevel::Strict => validate_strict(md),
+            _ => validate(md)
+        };
+
+        if self.validation_level == ValidationLevel::Strict && has_errors(&self.diagnostics) {
+            let messages: Vec<String> = self.diagnostics.iter().map(|d| d.to_string()).collect();
+            return Err(DTERR(DtasmError::InvalidModelDescription(messages.join("; "))));
+        }
+
+        Ok(())
+    }
+
     /// Initialize the instance with the given initial values and simulation parameters
     ///
     /// * `initial_vals` - initial values for the state variables
@@ -209,7 +462,7 @@ impl Instance {
     /// * `log_level` - maximal level at which log messages should be reported
     /// * `check` - whether to check validity of buffers (not currently implemented)
     pub fn initialize(&mut self, initial_vals: &DtasmVarValues, tmin: f64, tmax: Option<f64>, 
-        tol: Option<f64>, log_level: LogLevel, check: bool) -> Result<Status, DtasmtimeError>{
+        tol: Option<f64>, log_level: LogLevel, check: bool) -> Result<StatusResponse, DtasmtimeError>{
         // TODO: Check if state valid
 
         let md = &self.md.as_ref().ok_or(DTERR(DtasmError::InvalidCallingOrder))?;
@@ -228,92 +481,16 @@ impl Instance {
             LogLevel::Error => DTT::LogLevel::Error,
         };
 
-        let mut var_values = DtasmVarValues::new();
-
-        // collect all initial values that are explicitly set and check their types
-        for (id, val) in &initial_vals.real_values {
-            if !self.var_types.contains_key(id) { 
-                return Err(DTERR(DtasmError::UnknownVariableId(*id))); 
-            }
-            if self.var_types[id].value_type != MD::VariableType::DtasmReal { 
-                return Err(DTERR(DtasmError::VariableTypeMismatch(self.var_types[id].value_type, *id))); 
-            }
-            var_values.real_values.insert(*id, *val);
-        }
-        for (id, val) in &initial_vals.int_values {
-            if !self.var_types.contains_key(id) { 
-                return Err(DTERR(DtasmError::UnknownVariableId(*id))); 
-            }
-            if self.var_types[id].value_type != MD::VariableType::DtasmInt { 
-                return Err(DTERR(DtasmError::VariableTypeMismatch(self.var_types[id].value_type, *id))); 
-            }
-            var_values.int_values.insert(*id, *val);
-        }
-        for (id, val) in &initial_vals.bool_values {
-            if !self.var_types.contains_key(id) { 
-                return Err(DTERR(DtasmError::UnknownVariableId(*id))); 
-            }
-            if self.var_types[id].value_type != MD::VariableType::DtasmBool { 
-                return Err(DTERR(DtasmError::VariableTypeMismatch(self.var_types[id].value_type, *id))); 
-            }
-            var_values.bool_values.insert(*id, *val);
-        }
-        for (id, val) in &initial_vals.string_values {
-            if !self.var_types.contains_key(id) { 
-                return Err(DTERR(DtasmError::UnknownVariableId(*id))); 
-            }
-            if self.var_types[id].value_type != MD::VariableType::DtasmString { 
-                return Err(DTERR(DtasmError::VariableTypeMismatch(self.var_types[id].value_type, *id))); 
-            }
-            var_values.string_values.insert(*id, val.clone());
-        }
+        // check existence, types and dimensions of all initial values that are explicitly set
+        check_var_values(initial_vals, &self.var_types, false).map_err(DTERR)?;
+        let mut initial_vals = initial_vals.clone();
+        self.bounds_violations = apply_bounds_policy(&mut initial_vals, &self.var_types, self.bounds_policy)
+            .map_err(DTERR)?;
+        let initial_vals = &initial_vals;
 
         // build up the init request message
         let model_id = self.builder.create_string(&md.model.id);
-
-        let mut real_offs: Vec<flatbuffers::WIPOffset<DTT::RealVal>> = Vec::new();
-        for (id, val) in &var_values.real_values {
-            real_offs.push(DTT::RealVal::create(&mut self.builder, &DTT::RealValArgs{
-                id: *id,
-                val: *val
-            }));
-        }
-        let real_vals = self.builder.create_vector(&real_offs);
-
-        let mut int_offs: Vec<flatbuffers::WIPOffset<DTT::IntVal>> = Vec::new();
-        for (id, val) in &var_values.int_values {
-            int_offs.push(DTT::IntVal::create(&mut self.builder, &DTT::IntValArgs{
-                id: *id,
-                val: *val
-            }));
-        }
-        let int_vals = self.builder.create_vector(&int_offs);
-
-        let mut bool_offs: Vec<flatbuffers::WIPOffset<DTT::BoolVal>> = Vec::new();
-        for (id, val) in &var_values.bool_values {
-            bool_offs.push(DTT::BoolVal::create(&mut self.builder, &DTT::BoolValArgs{
-                id: *id,
-                val: *val
-            }));
-        }
-        let bool_vals = self.builder.create_vector(&bool_offs);
-
-        let mut string_offs: Vec<flatbuffers::WIPOffset<DTT::StringVal>> = Vec::new();
-        for (id, val) in &var_values.string_values {
-            let val_str = self.builder.create_string(val);
-            string_offs.push(DTT::StringVal::create(&mut self.builder, &DTT::StringValArgs{
-                id: *id,
-                val: Some(val_str)
-            }));
-        }
-        let string_vals = self.builder.create_vector(&string_offs);
-
-        let scalar_vals = DTT::VarValues::create(&mut self.builder, &DTT::VarValuesArgs{
-            real_vals: Some(real_vals), 
-            int_vals: Some(int_vals),
-            bool_vals: Some(bool_vals),
-            string_vals: Some(string_vals)
-        });
+        let var_vals = create_var_values(&mut self.builder, initial_vals);
 
         let init_req = DTAPI::InitReq::create(&mut self.builder, &DTAPI::InitReqArgs{
             id: Some(model_id), 
@@ -324,7 +501,7 @@ impl Instance {
             tolerance_set: match tol {Some(_v) => true, None => false}, 
             loglevel_limit: fb_log, 
             check_consistency: check, 
-            init_values: Some(scalar_vals)
+            init_values: Some(var_vals)
         });
         self.builder.finish(init_req, None);
 
@@ -336,8 +513,8 @@ impl Instance {
         self.memory.data_mut(&mut self.store)[init_req_ptr..init_req_ptr+init_req_len]
             .copy_from_slice(init_req_buf);
 
-        // return value is status only, should fit into 64 bytes
-        let size = 64;
+        // return value is status and an optional message
+        let size = BASE_MEM_SIZE;
         let init_res_ptr = self.alloc_fn.call(&mut self.store, size)? as usize;
         let size_out = self.init_fn.call(&mut self.store, (init_req_ptr as i32, init_req_len as i32, init_res_ptr as i32, size))?;
 
@@ -345,9 +522,13 @@ impl Instance {
 
         let res_bytes = &self.memory.data(&mut self.store)[init_res_ptr..init_res_ptr+(size_out as usize)];
 
-        let init_res = FB::root::<DTAPI::StatusRes>(res_bytes).unwrap();
+        let init_res = decode_response::<DTAPI::StatusRes>(res_bytes, "init")?;
 
-        let status_res = init_res.status().into();
+        let status_res = StatusResponse {
+            status: init_res.status().into(),
+            message: init_res.message().map(|msg| msg.to_string()),
+            error_code: error_code(init_res.error_code())
+        };
         
         self.dealloc_fn.call(&mut self.store, init_req_ptr as i32)?;
         self.dealloc_fn.call(&mut self.store, init_res_ptr as i32)?;
@@ -401,125 +582,40 @@ impl Instance {
     
         let res_bytes = &self.memory.data(&mut self.store)[getval_res_ptr..getval_res_ptr+size_out as usize];
     
-        let getvalues_res = FB::root::<DTAPI::GetValuesRes>(res_bytes).unwrap();
+        let getvalues_res = decode_response::<DTAPI::GetValuesRes>(res_bytes, "getValues")?;
         let var_values = Instance::extract_vals(&getvalues_res, &self.var_types)?;
         let current_time = getvalues_res.current_time();
         let status = getvalues_res.status().into();
+        let message = getvalues_res.message().map(|msg| msg.to_string());
+        let error_code = error_code(getvalues_res.error_code());
 
         self.dealloc_fn.call(&mut self.store, getval_req_ptr as i32)?;
         self.dealloc_fn.call(&mut self.store, getval_res_ptr as i32)?;
         self.builder.reset();
 
-        Ok(GetValuesResponse {status, current_time, values: var_values})
+        Ok(GetValuesResponse {status, current_time, values: var_values, message, error_code})
     }
 
 
-    /// Set values of input variables for the next timestep
+    /// Set values of input variables (and tunable parameters) for the next timestep
     ///
-    /// * `input_vals`: Values for the input variables
-    pub fn set_values(&mut self, input_vals: &DtasmVarValues) -> Result<Status, DtasmtimeError>{
+    /// * `input_vals`: Values for the input variables and tunable parameters
+    pub fn set_values(&mut self, input_vals: &DtasmVarValues) -> Result<StatusResponse, DtasmtimeError>{
         // TODO: check state
 
-        // start with default values from model description
-        let mut var_values = DtasmVarValues::new();
-
-        // collect set values and check their existence and types
-        for (id, val) in &input_vals.real_values {
-            if !self.var_types.contains_key(id) { 
-                return Err(DTERR(DtasmError::UnknownVariableId(*id))); 
-            }
-            if self.var_types[id].causality != MD::CausalityType::Input { 
-                return Err(DTERR(DtasmError::VariableCausalityInvalidForSet(self.var_types[id].causality, *id))); 
-            }
-            if self.var_types[id].value_type != MD::VariableType::DtasmReal { 
-                return Err(DTERR(DtasmError::VariableTypeMismatch(self.var_types[id].value_type, *id))); 
-            }
-            var_values.real_values.insert(*id, *val);
-        }
-        for (id, val) in &input_vals.int_values {
-            if !self.var_types.contains_key(id) { 
-                return Err(DTERR(DtasmError::UnknownVariableId(*id))); 
-            }
-            if self.var_types[id].causality != MD::CausalityType::Input { 
-                return Err(DTERR(DtasmError::VariableCausalityInvalidForSet(self.var_types[id].causality, *id))); 
-            }
-            if self.var_types[id].value_type != MD::VariableType::DtasmInt { 
-                return Err(DTERR(DtasmError::VariableTypeMismatch(self.var_types[id].value_type, *id))); 
-            }
-            var_values.int_values.insert(*id, *val);
-        }
-        for (id, val) in &input_vals.bool_values {
-            if !self.var_types.contains_key(id) { 
-                return Err(DTERR(DtasmError::UnknownVariableId(*id))); 
-            }
-            if self.var_types[id].causality != MD::CausalityType::Input { 
-                return Err(DTERR(DtasmError::VariableCausalityInvalidForSet(self.var_types[id].causality, *id))); 
-            }
-            if self.var_types[id].value_type != MD::VariableType::DtasmBool { 
-                return Err(DTERR(DtasmError::VariableTypeMismatch(self.var_types[id].value_type, *id))); 
-            }
-            var_values.bool_values.insert(*id, *val);
-        }
-        for (id, val) in &input_vals.string_values {
-            if !self.var_types.contains_key(id) { 
-                return Err(DTERR(DtasmError::UnknownVariableId(*id))); 
-            }
-            if self.var_types[id].causality != MD::CausalityType::Input { 
-                return Err(DTERR(DtasmError::VariableCausalityInvalidForSet(self.var_types[id].causality, *id))); 
-            }
-            if self.var_types[id].value_type != MD::VariableType::DtasmString { 
-                return Err(DTERR(DtasmError::VariableTypeMismatch(self.var_types[id].value_type, *id))); 
-            }
-            var_values.string_values.insert(*id, val.clone());
-        }
+        // check existence, causality (inputs and tunable parameters only), types and 
+        // dimensions of the set values
+        check_var_values(input_vals, &self.var_types, true).map_err(DTERR)?;
+        let mut input_vals = input_vals.clone();
+        self.bounds_violations = apply_bounds_policy(&mut input_vals, &self.var_types, self.bounds_policy)
+            .map_err(DTERR)?;
+        let input_vals = &input_vals;
 
         // build the setValues request message
-        let mut real_offs: Vec<flatbuffers::WIPOffset<DTT::RealVal>> = Vec::new();
-        for (id, val) in &var_values.real_values {
-            real_offs.push(DTT::RealVal::create(&mut self.builder, &DTT::RealValArgs{
-                id: *id,
-                val: *val
-            }));
-        }
-        let real_vals = self.builder.create_vector(&real_offs);
-
-        let mut int_offs: Vec<flatbuffers::WIPOffset<DTT::IntVal>> = Vec::new();
-        for (id, val) in &var_values.int_values {
-            int_offs.push(DTT::IntVal::create(&mut self.builder, &DTT::IntValArgs{
-                id: *id,
-                val: *val
-            }));
-        }
-        let int_vals = self.builder.create_vector(&int_offs);
-
-        let mut bool_offs: Vec<flatbuffers::WIPOffset<DTT::BoolVal>> = Vec::new();
-        for (id, val) in &var_values.bool_values {
-            bool_offs.push(DTT::BoolVal::create(&mut self.builder, &DTT::BoolValArgs{
-                id: *id,
-                val: *val
-            }));
-        }
-        let bool_vals = self.builder.create_vector(&bool_offs);
-
-        let mut string_offs: Vec<flatbuffers::WIPOffset<DTT::StringVal>> = Vec::new();
-        for (id, val) in &var_values.string_values {
-            let val_str = self.builder.create_string(val);
-            string_offs.push(DTT::StringVal::create(&mut self.builder, &DTT::StringValArgs{
-                id: *id,
-                val: Some(val_str)
-            }));
-        }
-        let string_vals = self.builder.create_vector(&string_offs);
-
-        let scalar_vals = DTT::VarValues::create(&mut self.builder, &DTT::VarValuesArgs{
-            real_vals: Some(real_vals), 
-            int_vals: Some(int_vals),
-            bool_vals: Some(bool_vals),
-            string_vals: Some(string_vals)
-        });
+        let var_vals = create_var_values(&mut self.builder, input_vals);
 
         let set_vals_req = DTAPI::SetValuesReq::create(&mut self.builder, &DTAPI::SetValuesReqArgs{
-            values: Some(scalar_vals),
+            values: Some(var_vals),
         });
         self.builder.finish(set_vals_req, None);
 
@@ -531,8 +627,8 @@ impl Instance {
         self.memory.data_mut(&mut self.store)[set_req_ptr..set_req_ptr+set_req_len]
                 .copy_from_slice(set_req_buf);
 
-        // return value is status only, should fit into 64 bytes
-        let size = 64;
+        // return value is status and an optional message
+        let size = BASE_MEM_SIZE;
         let set_res_ptr = self.alloc_fn.call(&mut self.store, size)? as usize;
         let size_out = self.set_values_fn.call(&mut self.store, (set_req_ptr as i32, set_req_len as i32, set_res_ptr as i32, size))?;
 
@@ -540,9 +636,13 @@ impl Instance {
 
         let res_bytes = &self.memory.data(&mut self.store)[set_res_ptr..set_res_ptr+(size_out as usize)];
 
-        let init_res = FB::root::<DTAPI::StatusRes>(res_bytes).unwrap();
+        let init_res = decode_response::<DTAPI::StatusRes>(res_bytes, "setValues")?;
 
-        let status_res = init_res.status().into();
+        let status_res = StatusResponse {
+            status: init_res.status().into(),
+            message: init_res.message().map(|msg| msg.to_string()),
+            error_code: error_code(init_res.error_code())
+        };
         
         self.dealloc_fn.call(&mut self.store, set_req_ptr as i32)?;
         self.dealloc_fn.call(&mut self.store, set_res_ptr as i32)?;
@@ -580,99 +680,117 @@ impl Instance {
     
         let res_bytes = &self.memory.data(&mut self.store)[dostep_res_ptr..dostep_res_ptr+size_out as usize];
     
-        let dostep_res = FB::root::<DTAPI::DoStepRes>(res_bytes).unwrap();
+        let dostep_res = decode_response::<DTAPI::DoStepRes>(res_bytes, "doStep")?;
         let updated_time = dostep_res.updated_time();
         let status_res = dostep_res.status().into();
+        let message = dostep_res.message().map(|msg| msg.to_string());
+        let error_code = error_code(dostep_res.error_code());
+        let event_occurred = dostep_res.event_occurred();
+        let next_time_event = match dostep_res.next_time_event_set() {
+            true => Some(dostep_res.next_time_event()),
+            false => None
+        };
      
         self.dealloc_fn.call(&mut self.store, dostep_req_ptr as i32)?;
         self.dealloc_fn.call(&mut self.store, dostep_res_ptr as i32)?;
         self.builder.reset();
 
-        Ok(DoStepResponse {status: status_res, updated_time})
+        Ok(DoStepResponse {status: status_res, updated_time, message, error_code, event_occurred, next_time_event})
+    }
+
+    /// Reset the instance to the state at the beginning of the last time step, e.g. 
+    /// after the step was discarded; fails if the module does not export `resetStep`
+    /// (see the `can_reset_step` capability)
+    ///
+    /// * `current_time` - time reached by the last step
+    /// * `reset_time` - time at the beginning of the last step
+    pub fn reset_step(&mut self, current_time: f64, reset_time: f64) -> Result<StatusResponse, DtasmtimeError> {
+        let reset_step_fn = match &self.reset_step_fn {
+            Some(f) => f,
+            None => return Err(DTERR(DtasmError::NotImplementedError("resetStep".to_string())))
+        };
+
+        // build resetStep request message
+        let req = DTAPI::ResetStepReq::create(&mut self.builder, &DTAPI::ResetStepReqArgs{
+            current_time,
+            reset_time
+        });
+        self.builder.finish(req, None);
+
+        let reset_req_buf = self.builder.finished_data();
+        let reset_req_len = reset_req_buf.len();
+        let reset_req_ptr = self.alloc_fn.call(&mut self.store, reset_req_len as i32)? as usize;
+
+        self.memory.data_mut(&mut self.store)[reset_req_ptr..reset_req_ptr+reset_req_len]
+            .copy_from_slice(reset_req_buf);
+
+        // return value is status and an optional message
+        let size = BASE_MEM_SIZE;
+        let reset_res_ptr = self.alloc_fn.call(&mut self.store, size)? as usize;
+        let size_out = reset_step_fn.call(&mut self.store, (reset_req_ptr as i32, reset_req_len as i32, reset_res_ptr as i32, size))?;
+
+        if size_out > size { return Err(DTERR(DtasmError::DtasmInternalError(format!("Unexpected size returned from resetStep request: {}", size_out)))); }
+
+        let res_bytes = &self.memory.data(&mut self.store)[reset_res_ptr..reset_res_ptr+(size_out as usize)];
+
+        let reset_res = decode_response::<DTAPI::StatusRes>(res_bytes, "resetStep")?;
+
+        let status_res = StatusResponse {
+            status: reset_res.status().into(),
+            message: reset_res.message().map(|msg| msg.to_string()),
+            error_code: error_code(reset_res.error_code())
+        };
+
+        self.dealloc_fn.call(&mut self.store, reset_req_ptr as i32)?;
+        self.dealloc_fn.call(&mut self.store, reset_res_ptr as i32)?;
+        self.builder.reset();
+
+        Ok(status_res)
     }
 
     fn extract_vals(&getvalues_res: &DTAPI::GetValuesRes, 
         map_id_var: &HashMap<i32, DtasmVarType>) -> Result<DtasmVarValues, DtasmError> {
 
-        let mut var_vals = DtasmVarValues::new();
-        
         let values = getvalues_res.values()
             .ok_or(DtasmError::DtasmInternalError("Invalid response received to getValues request: `values` field empty".to_string()))?;
-        
-        for real_val in values.real_vals().iter().flat_map(identity) {
-            let id = real_val.id();
-            let val = real_val.val();
-    
-            if !map_id_var.contains_key(&id){
-                return Err(DtasmError::UnknownVariableId(id));
-            }
-            if map_id_var[&id].value_type != MD::VariableType::DtasmReal {
-                return Err(DtasmError::VariableTypeMismatch(MD::VariableType::DtasmReal, id));
-            }
-            var_vals.real_values.insert(id, val);
-        }
-
-        for int_val in values.int_vals().iter().flat_map(identity) {
-            let id = int_val.id();
-            let val = int_val.val();
-    
-            if !map_id_var.contains_key(&id){
-                return Err(DtasmError::UnknownVariableId(id));
-            }
-            if map_id_var[&id].value_type != MD::VariableType::DtasmInt {
-                return Err(DtasmError::VariableTypeMismatch(MD::VariableType::DtasmInt, id));
-            }
-            var_vals.int_values.insert(id, val);
-        }
-
-        for bool_val in values.bool_vals().iter().flat_map(identity) {
-            let id = bool_val.id();
-            let val = bool_val.val();
-    
-            if !map_id_var.contains_key(&id){
-                return Err(DtasmError::UnknownVariableId(id));
-            }
-            if map_id_var[&id].value_type != MD::VariableType::DtasmBool {
-                return Err(DtasmError::VariableTypeMismatch(MD::VariableType::DtasmBool, id));
-            }
-            var_vals.bool_values.insert(id, val);
-        }
 
-        for str_val in values.string_vals().iter().flat_map(identity) {
-            let id = str_val.id();
-            let val = str_val.val().ok_or(DtasmError::InvalidVariableValue("None".to_string(), id))?;
-    
-            if !map_id_var.contains_key(&id){
-                return Err(DtasmError::UnknownVariableId(id));
-            }
-            if map_id_var[&id].value_type != MD::VariableType::DtasmString {
-                return Err(DtasmError::VariableTypeMismatch(MD::VariableType::DtasmString, id));
-            }
-            var_vals.string_values.insert(id, val.to_string());
-        }
+        let var_vals = convert_var_values(&values)?;
+        check_var_values(&var_vals, map_id_var, false)?;
 
         Ok(var_vals)
     }
 
 
+    /// Variable types by id; fails on duplicate ids regardless of the validation 
+    /// level, as values of such variables could not be told apart
     fn collect_var_types(md: &MD::ModelDescription) -> Result<HashMap<i32, DtasmVarType>, DtasmtimeError> {
         let model_vars = &md.variables;
         let mut var_types: HashMap<i32, DtasmVarType> = HashMap::new();
 
         for model_var in model_vars.iter() {
+            if let Some(var_type) = var_types.get(&model_var.id) {
+                return Err(DTERR(DtasmError::InvalidModelDescription(format!(
+                    "Variables {} and {} share the id `{}`", var_type.name, model_var.name, model_var.id))));
+            }
+
             var_types.insert(model_var.id,
                 DtasmVarType {
                     name: model_var.name.clone(), 
                     causality: model_var.causality.clone(),
                     value_type: model_var.value_type.clone(),
-                    default: model_var.default.clone()
+                    default: model_var.default.clone(),
+                    dimensions: model_var.dimensions.clone(),
+                    enumeration: md.enumeration_type(model_var).cloned(),
+                    min: model_var.min.clone(),
+                    max: model_var.max.clone(),
+                    variability: model_var.variability
                 });
         }
 
         Ok(var_types)
     }
 
-    /// Load a serialized state from file into this instance
+    /// Load a state saved with `save_state` into this instance
     pub fn load_state(&mut self, filepath: PathBuf) -> Result<(), DtasmtimeError>{
         let mut file = std::fs::File::open(filepath)?;
 
@@ -680,26 +798,201 @@ impl Instance {
         // read the whole file
         file.read_to_end(&mut buffer)?;
 
-        let state_size = buffer.len() as u64;
-        let mem_size = &self.memory.size(&mut self.store);
+        self.restore_snapshot(&Snapshot::from_bytes(buffer))
+    }
+
+    /// Serialize the current state of the instance (see `snapshot`) to a binary file
+    pub fn save_state(&mut self, filepath: PathBuf) -> Result<(),DtasmtimeError>{
+        let mut file = std::fs::File::create(filepath)?;
+
+        file.write_all(self.snapshot().as_bytes())?;
 
-        if state_size > &self.memory.size(&mut self.store) * WASM_PAGE_SIZE {
-            let add_pages = state_size  / WASM_PAGE_SIZE - mem_size;
-            let old_size = &self.memory.grow(&mut self.store, add_pages)?;
-            assert!(old_size == mem_size, "Memory sizing inconsistency detected");
+        Ok(())
+    }
+
+    /// Take a snapshot of the current state of the instance (its exported mutable 
+    /// globals and its linear memory)
+    pub fn snapshot(&mut self) -> Snapshot {
+        let globals: Vec<u64> = self.globals.iter()
+            .map(|global| match global.get(&mut self.store) {
+                WT::Val::I32(v) => v as u32 as u64,
+                WT::Val::I64(v) => v as u64,
+                WT::Val::F32(v) => v as u64,
+                WT::Val::F64(v) => v,
+                _ => 0
+            })
+            .collect();
+
+        Snapshot::new(&globals, self.memory.data(&self.store))
+    }
+
+    /// Reset the instance to the state of a snapshot taken from this instance or 
+    /// another instance of the same module; memory grown after the snapshot was 
+    /// taken is zeroed
+    pub fn restore_snapshot(&mut self, snapshot: &Snapshot) -> Result<(), DtasmtimeError> {
+        let (globals, memory) = snapshot.parts()?;
+        if globals.len() != self.globals.len() {
+            return Err(DTERR(DtasmError::DtasmInternalError(format!(
+                "Invalid snapshot: {} globals instead of {}", globals.len(), self.globals.len()))));
+        }
+
+        let snapshot_pages = (memory.len() as u64 + WASM_PAGE_SIZE - 1) / WASM_PAGE_SIZE;
+        let mem_pages = self.memory.size(&mut self.store);
+        if snapshot_pages > mem_pages {
+            self.memory.grow(&mut self.store, snapshot_pages - mem_pages)?;
         }
 
-        let _ = &self.memory.data_mut(&mut self.store).copy_from_slice(&buffer[..]);
+        let data = self.memory.data_mut(&mut self.store);
+        data[..memory.len()].copy_from_slice(memory);
+        data[memory.len()..].iter_mut().for_each(|b| *b = 0);
+
+        for (global, bits) in self.globals.iter().zip(globals) {
+            let value = match global.ty(&self.store).content() {
+                WT::ValType::I32 => WT::Val::I32(bits as u32 as i32),
+                WT::ValType::I64 => WT::Val::I64(bits as i64),
+                WT::ValType::F32 => WT::Val::F32(bits as u32),
+                _ => WT::Val::F64(bits)
+            };
+            global.set(&mut self.store, value)?;
+        }
 
         Ok(())
     }
 
-    /// Serialize the current state of the instance to a binary file
-    pub fn save_state(&mut self, filepath: PathBuf) -> Result<(),DtasmtimeError>{
-        let mut file = std::fs::File::create(filepath)?;
+    // Raw access to the module exports and linear memory, used by the conformance checks
+
+    pub(crate) fn alloc(&mut self, size: i32) -> Result<i32, DtasmtimeError> {
+        Ok(self.alloc_fn.call(&mut self.store, size)?)
+    }
+
+    pub(crate) fn dealloc(&mut self, ptr: i32) -> Result<(), DtasmtimeError> {
+        Ok(self.dealloc_fn.call(&mut self.store, ptr)?)
+    }
 
-        file.write_all(&self.memory.data(&mut self.store))?;
+    /// Size of the linear memory in wasm pages
+    pub(crate) fn memory_pages(&mut self) -> u64 {
+        self.memory.size(&mut self.store)
+    }
+
+    pub(crate) fn read_memory(&mut self, ptr: i32, len: usize) -> Result<Vec<u8>, DtasmtimeError> {
+        let start = ptr as u32 as usize;
+        self.memory.data(&mut self.store)
+            .get(start..start+len)
+            .map(|bytes| bytes.to_vec())
+            .ok_or(DTERR(DtasmError::DtasmInternalError(format!("Memory access out of bounds: {}+{}", start, len))))
+    }
+
+    pub(crate) fn write_memory(&mut self, ptr: i32, bytes: &[u8]) -> Result<(), DtasmtimeError> {
+        let start = ptr as u32 as usize;
+        self.memory.data_mut(&mut self.store)
+            .get_mut(start..start+bytes.len())
+            .ok_or(DTERR(DtasmError::DtasmInternalError(format!("Memory access out of bounds: {}+{}", start, bytes.len()))))?
+            .copy_from_slice(bytes);
 
         Ok(())
     }
+
+    /// Call the `getModelDescription` export once with the given output buffer and
+    /// return the size reported by the module
+    pub(crate) fn call_get_model_description(&mut self, ptr: i32, size: i32) -> Result<i32, DtasmtimeError> {
+        Ok(self.get_md_fn.call(&mut self.store, (ptr, size))?)
+    }
+}
+
+/// Host function behind the `dtasm.log` import: pass the message to the log
+/// callback of the instance
+fn log_message(mut caller: WT::Caller<'_, HostState>, level: i32, ptr: i32, len: i32) -> Result<(), WT::Trap> {
+    let memory = caller.get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| WT::Trap::new("dtasm.log: module does not export memory"))?;
+
+    let start = ptr as u32 as usize;
+    let message = memory.data(&caller)
+        .get(start..start + len as u32 as usize)
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+        .ok_or_else(|| WT::Trap::new(format!("dtasm.log: message out of bounds: {}+{}", start, len)))?;
+
+    let level = LogLevel::from(DTT::LogLevel(level as i8));
+    let state = caller.data_mut();
+    let record = LogRecord { instance: &state.instance_name, level, message: &message };
+
+    // without a callback, the message is dropped
+    if let Some(callback) = &mut state.log_callback {
+        callback(&record);
+    }
+
+    Ok(())
+}
+
+/// Decode a response returned by the module, reporting invalid FlatBuffers as internal errors
+fn decode_response<'a, T>(bytes: &'a [u8], request: &str) -> Result<T::Inner, DtasmtimeError>
+    where T: 'a + FB::Follow<'a> + FB::Verifiable {
+    FB::root::<T>(bytes)
+        .map_err(|e| DTERR(DtasmError::DtasmInternalError(format!("Invalid response received to {} request: {}", request, e))))
+}
+
+/// Error code from a dtasm result message, 0 meaning no error code was set
+fn error_code(code: i32) -> Option<i32> {
+    match code {
+        0 => None,
+        code => Some(code)
+    }
+}
+
+/// Check that all values refer to existing variables and match their types and
+/// dimensions, and that values of enumeration variables are declared items; with 
+/// `between_steps`, values may only be given for inputs and tunable parameters
+fn check_var_values(values: &DtasmVarValues, var_types: &HashMap<i32, DtasmVarType>, 
+    between_steps: bool) -> Result<(), DtasmError> {
+
+    let check = |id: &i32, value_type: MD::VariableType, len: Option<usize>| -> Result<(), DtasmError> {
+        let var_type = var_types.get(id).ok_or(DtasmError::UnknownVariableId(*id))?;
+
+        if between_steps && !var_type.is_settable_between_steps() {
+            return Err(DtasmError::VariableCausalityInvalidForSet(var_type.causality, *id));
+        }
+        if var_type.value_type != value_type {
+            return Err(DtasmError::VariableTypeMismatch(var_type.value_type, *id));
+        }
+
+        // scalar values for scalar variables, arrays of matching length for array variables
+        match (var_type.dimensions.is_empty(), len) {
+            (true, None) => Ok(()),
+            (false, Some(len)) if len == MD::array_len(&var_type.dimensions) => Ok(()),
+            (_, len) => Err(DtasmError::VariableDimensionMismatch(len.unwrap_or(1), *id))
+        }
+    };
+
+    let check_items = |id: &i32, vals: &[i32]| -> Result<(), DtasmError> {
+        match &var_types[id].enumeration {
+            Some(enum_type) => match vals.iter().find(|val| enum_type.item_name(**val).is_none()) {
+                Some(val) => Err(DtasmError::InvalidVariableValue(val.to_string(), *id)),
+                None => Ok(())
+            },
+            None => Ok(())
+        }
+    };
+
+    for id in values.real_values.keys() { check(id, MD::VariableType::DtasmReal, None)?; }
+    for (id, val) in &values.int_values {
+        check(id, MD::VariableType::DtasmInt, None)?;
+        check_items(id, std::slice::from_ref(val))?;
+    }
+    for id in values.bool_values.keys() { check(id, MD::VariableType::DtasmBool, None)?; }
+    for id in values.string_values.keys() { check(id, MD::VariableType::DtasmString, None)?; }
+    for id in values.binary_values.keys() { check(id, MD::VariableType::DtasmBinary, None)?; }
+    for id in values.int64_values.keys() { check(id, MD::VariableType::DtasmInt64, None)?; }
+    for id in values.float32_values.keys() { check(id, MD::VariableType::DtasmFloat32, None)?; }
+
+    for (id, val) in &values.real_arrays { check(id, MD::VariableType::DtasmReal, Some(val.len()))?; }
+    for (id, val) in &values.int_arrays {
+        check(id, MD::VariableType::DtasmInt, Some(val.len()))?;
+        check_items(id, val)?;
+    }
+    for (id, val) in &values.bool_arrays { check(id, MD::VariableType::DtasmBool, Some(val.len()))?; }
+    for (id, val) in &values.string_arrays { check(id, MD::VariableType::DtasmString, Some(val.len()))?; }
+    for (id, val) in &values.int64_arrays { check(id, MD::VariableType::DtasmInt64, Some(val.len()))?; }
+    for (id, val) in &values.float32_arrays { check(id, MD::VariableType::DtasmFloat32, Some(val.len()))?; }
+
+    Ok(())
 }
@@ -0,0 +1,71 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+//! Stepping of instances in macro steps of a fixed size. Modules announce the
+//! time of their next time event in each `doStep` response; a macro step that
+//! would pass this time is truncated so that it ends at the event.
+
+use crate::errors::{DtasmtimeError, DtasmtimeError::DtasmError as DTERR};
+use crate::runtime::Instance;
+use dtasm_base::errors::DtasmError;
+use dtasm_base::types::{DoStepResponse, Status};
+
+/// Macro step size and the time event announced by the last step
+#[derive(Debug,Clone)]
+pub struct Stepper {
+    timestep: f64,
+    next_time_event: Option<f64>
+}
+
+impl Stepper {
+    /// * `timestep` - size of the macro steps
+    pub fn new(timestep: f64) -> Stepper {
+        Stepper {
+            timestep,
+            next_time_event: None
+        }
+    }
+
+    /// Time event announced by the last step, if any
+    pub fn next_time_event(&self) -> Option<f64> {
+        self.next_time_event
+    }
+
+    /// Length of the step starting at `t`: the macro step size, truncated to
+    /// the announced time event if it lies within the step
+    ///
+    /// * `t` - time at the beginning of the step
+    pub fn next_step(&self, t: f64) -> f64 {
+        match self.next_time_event {
+            Some(t_event) if t_event > t && t_event < t + self.timestep => t_event - t,
+            _ => self.timestep
+        }
+    }
+
+    /// Record the time event announced in the response to a step
+    pub fn update(&mut self, res: &DoStepResponse) {
+        self.next_time_event = res.next_time_event;
+    }
+
+    /// Advance the instance by the next (possibly truncated) step; fails if the
+    /// module reports an error or does not advance its time
+    ///
+    /// * `inst` - the initialized instance
+    /// * `t` - current time
+    pub fn do_step(&mut self, inst: &mut Instance, t: f64) -> Result<DoStepResponse, DtasmtimeError> {
+        let res = inst.do_step(t, self.next_step(t))?;
+
+        if let Status::Error = res.status {
+            return Err(DTERR(DtasmError::DtasmInternalError(format!("doStep at t = {} failed: {}",
+                t, res.message.as_deref().unwrap_or("no message given")))));
+        }
+        if res.updated_time <= t {
+            return Err(DTERR(DtasmError::DtasmInternalError(format!("doStep at t = {} did not advance the time (updated time {})",
+                t, res.updated_time))));
+        }
+
+        self.update(&res);
+
+        Ok(res)
+    }
+}
@@ -0,0 +1,101 @@
+mod common;
+use common::{add_wasm_path, test_wasm_path, wasm_with_sections};
+
+use dtasmtime::abi::{check_abi_version, AbiFeature, AbiVersion, ABI_VERSION_SECTION, ABI_VERSION_SECTION_CONTENTS};
+use dtasmtime::runtime::{Engine, Module};
+use dtasmtime::errors::DtasmtimeError;
+use dtasm_base::errors::DtasmError;
+
+use rstest::rstest;
+
+
+#[rstest]
+#[case(None, Some(AbiVersion::LEGACY))]
+#[case(Some("1.0"), Some(AbiVersion { major: 1, minor: 0 }))]
+#[case(Some("1.1"), Some(AbiVersion { major: 1, minor: 1 }))]
+#[case(Some("1.2"), None)]
+#[case(Some("1.10"), None)]
+#[case(Some("2.0"), None)]
+#[case(Some("0.9"), None)]
+#[case(Some("1"), None)]
+#[case(Some("1.x"), None)]
+fn it_checks_abi_versions(#[case] section: Option<&str>, #[case] expected: Option<AbiVersion>) {
+    match (check_abi_version(section.map(|s| s.as_bytes())), expected) {
+        (Ok(version), Some(expected)) => assert_eq!(version, expected),
+        (Err(DtasmError::UnsupportedAbiVersion(_)), None) => {},
+        (result, _) => panic!("Unexpected result {:?} for section {:?}", result, section)
+    }
+}
+
+#[test]
+fn it_states_the_current_abi_version() {
+    assert_eq!(check_abi_version(Some(ABI_VERSION_SECTION_CONTENTS.as_bytes())).unwrap(), AbiVersion::CURRENT);
+    assert_eq!(AbiVersion::CURRENT.to_string(), "1.1");
+}
+
+#[test]
+fn it_compares_minor_versions_numerically() {
+    let version = AbiVersion::parse("1.10").unwrap();
+
+    assert_eq!(version, AbiVersion { major: 1, minor: 10 });
+    assert!(version > AbiVersion::parse("1.9").unwrap());
+    assert_eq!(version.to_string(), "1.10");
+}
+
+#[test]
+fn it_enables_features_for_newer_modules() {
+    assert!(AbiVersion::CURRENT.supports(AbiFeature::LogImport));
+    assert!(!AbiVersion::LEGACY.supports(AbiFeature::LogImport));
+}
+
+#[test]
+fn it_reads_the_abi_version_of_dtasm_rs_modules() {
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(add_wasm_path(), &engine).expect("Could not instantiate dtasm module");
+    assert_eq!(dtasm_module.abi_version(), AbiVersion::CURRENT);
+
+    let inst = dtasm_module.instantiate().expect("Instantiate failed!");
+    assert_eq!(inst.abi_version(), AbiVersion::CURRENT);
+}
+
+#[test]
+fn it_treats_modules_without_version_as_legacy() {
+    let path = wasm_with_sections(&add_wasm_path(), &[]);
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(path.to_path_buf(), &engine).expect("Could not instantiate dtasm module");
+    assert_eq!(dtasm_module.abi_version(), AbiVersion::LEGACY);
+
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+    assert_eq!(inst.abi_version(), AbiVersion::LEGACY);
+    inst.get_model_description().expect("Get Model Description failed!");
+}
+
+#[test]
+fn it_rejects_modules_with_unsupported_version() {
+    let path = wasm_with_sections(&add_wasm_path(), &[(ABI_VERSION_SECTION, b"2.0")]);
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    match Module::new(path.to_path_buf(), &engine) {
+        Ok(_) => panic!("Module with unsupported ABI version was loaded"),
+        Err(err) => assert!(err.to_string().contains("Unsupported dtasm ABI version `2.0`"))
+    }
+}
+
+#[rstest]
+#[case::current(Some("1.1"), true)]
+#[case::legacy(Some("1.0"), false)]
+#[case::unversioned(None, false)]
+fn it_requires_abi_version_for_log_import(#[case] abi_version: Option<&str>, #[case] supported: bool) {
+    let sections: Vec<(&str, &[u8])> = abi_version.iter().map(|v| (ABI_VERSION_SECTION, v.as_bytes())).collect();
+    let path = wasm_with_sections(&test_wasm_path(), &sections);
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    match Module::new(path.to_path_buf(), &engine) {
+        Ok(_) => assert!(supported),
+        Err(err) => {
+            assert!(!supported);
+            assert!(matches!(err, DtasmtimeError::DtasmError(DtasmError::UnsupportedAbiFeature(AbiFeature::LogImport, _, _))), "{:?}", err);
+        }
+    }
+}
@@ -1,6 +1,9 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::collections::HashMap;
 
-use dtasmtime::{runtime::{Engine, Instance, Module}, types::{DtasmVarValues, LogLevel}};
+mod common;
+use common::add_wasm_path;
+
+use dtasmtime::{runtime::{Engine, Instance, Module, Snapshot}, stepping::Stepper, types::{DtasmVarValues, LogLevel, Status}};
 use dtasm_base::model_description as MD;
 
 use float_cmp::approx_eq;
@@ -15,10 +18,7 @@ struct DtasmFixture {
 
 #[fixture]
 fn fix() -> DtasmFixture {
-    let mut add_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    add_path.push("tests");
-    add_path.push("assets");
-    add_path.push("add_rs.wasm");
+    let add_path = add_wasm_path();
 
     if !std::path::Path::new(&add_path).exists() {
         panic!("add_rs.wasm not found - did you compile dtasm modules in release mode?");
@@ -127,4 +127,83 @@ fn it_concats_two_strings(mut fix: DtasmFixture) {
     let result_val = &get_vals.values.string_values[&out_id];
 
     assert_eq!(*result_val, "hello world".to_string());
-}
\ No newline at end of file
+}
+
+#[rstest]
+fn it_steps_without_events(mut fix: DtasmFixture) {
+    let mut stepper = Stepper::new(0.02);
+
+    let dostep_res = stepper.do_step(&mut fix.inst, 0.0).expect("DoStep failed");
+
+    assert!(!dostep_res.event_occurred);
+    assert_eq!(dostep_res.next_time_event, None);
+    assert!( approx_eq!(f64, dostep_res.updated_time, 0.02, ulps = 2) );
+    assert_eq!(stepper.next_step(dostep_res.updated_time), 0.02);
+}
+
+#[rstest]
+fn it_resets_a_step(mut fix: DtasmFixture) {
+    let real_in1_id = fix.map_name_id["real_in1"];
+    let real_in2_id = fix.map_name_id["real_in2"];
+    let real_out_id = fix.map_name_id["real_out"];
+
+    let mut input_vals = DtasmVarValues::new();
+    input_vals.real_values.insert(real_in1_id, 1.0);
+    input_vals.real_values.insert(real_in2_id, 2.0);
+    fix.inst.set_values(&input_vals).expect("Could not set input values");
+    let t = fix.inst.do_step(0.0, 0.1).expect("DoStep failed").updated_time;
+
+    input_vals.real_values.insert(real_in1_id, 10.0);
+    input_vals.real_values.insert(real_in2_id, 20.0);
+    fix.inst.set_values(&input_vals).expect("Could not set input values");
+    fix.inst.do_step(t, 0.1).expect("DoStep failed");
+    let first_try = fix.inst.get_values(&fix.out_ids).expect("Error in get values");
+
+    let reset_res = fix.inst.reset_step(t + 0.1, t).expect("ResetStep failed");
+    assert!(matches!(reset_res.status, Status::OK));
+
+    let get_vals = fix.inst.get_values(&fix.out_ids).expect("Error in get values");
+    assert!( approx_eq!(f64, get_vals.current_time, t, ulps = 2) );
+    assert!( approx_eq!(f64, get_vals.values.real_values[&real_out_id], 3.0, ulps = 2) );
+
+    // repeating the step with the inputs set before it gives the same results
+    fix.inst.do_step(t, 0.1).expect("DoStep failed");
+    let second_try = fix.inst.get_values(&fix.out_ids).expect("Error in get values");
+    assert!( approx_eq!(f64, second_try.current_time, first_try.current_time, ulps = 2) );
+    assert!( approx_eq!(f64, second_try.values.real_values[&real_out_id], 30.0, ulps = 2) );
+    assert_eq!(second_try.values.int_values, first_try.values.int_values);
+    assert_eq!(second_try.values.string_values, first_try.values.string_values);
+
+    // only the last step can be reset
+    let reset_res = fix.inst.reset_step(t + 0.1, 0.0).expect("ResetStep failed");
+    assert!(matches!(reset_res.status, Status::Error));
+}
+
+#[rstest]
+fn it_restores_snapshots(mut fix: DtasmFixture) {
+    let mut input_vals = DtasmVarValues::new();
+    let real_in1_id = fix.map_name_id["real_in1"];
+    let real_out_id = fix.map_name_id["real_out"];
+
+    input_vals.real_values.insert(real_in1_id, 1.0);
+    fix.inst.set_values(&input_vals).expect("Could not set input values");
+    let t = fix.inst.do_step(0.0, 0.1).expect("DoStep failed").updated_time;
+    let before = fix.inst.get_values(&fix.out_ids).expect("Error in get values");
+    let snapshot = fix.inst.snapshot();
+
+    input_vals.real_values.insert(real_in1_id, 10.0);
+    fix.inst.set_values(&input_vals).expect("Could not set input values");
+    fix.inst.do_step(t, 0.1).expect("DoStep failed");
+
+    let bytes = snapshot.as_bytes().to_vec();
+    fix.inst.restore_snapshot(&Snapshot::from_bytes(bytes)).expect("Could not restore snapshot");
+    let after = fix.inst.get_values(&fix.out_ids).expect("Error in get values");
+    assert!( approx_eq!(f64, after.current_time, before.current_time, ulps = 2) );
+    assert!( approx_eq!(f64, after.values.real_values[&real_out_id], before.values.real_values[&real_out_id], ulps = 2) );
+}
+
+#[rstest]
+fn it_rejects_malformed_snapshots(mut fix: DtasmFixture) {
+    assert!(fix.inst.restore_snapshot(&Snapshot::from_bytes(vec![])).is_err());
+    assert!(fix.inst.restore_snapshot(&Snapshot::from_bytes(vec![0xFF, 0xFF, 0xFF, 0xFF, 0])).is_err());
+}
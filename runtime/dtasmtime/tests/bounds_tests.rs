@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use dtasmtime::bounds::{apply_bounds_policy, check_bounds, BoundsPolicy};
+use dtasmtime::model_description as MD;
+use dtasmtime::types::{DtasmVarType, DtasmVarValues};
+use dtasm_base::errors::DtasmError;
+
+use rstest::rstest;
+
+
+fn var_type(value_type: MD::VariableType, min: MD::VariableValue, max: MD::VariableValue) -> DtasmVarType {
+    DtasmVarType {
+        name: "valve".to_string(),
+        value_type,
+        causality: MD::CausalityType::Input,
+        default: None,
+        dimensions: Vec::new(),
+        enumeration: None,
+        min: Some(min),
+        max: Some(max),
+        variability: MD::VariabilityType::Continuous
+    }
+}
+
+fn var_types() -> HashMap<i32, DtasmVarType> {
+    let mut var_types = HashMap::new();
+    var_types.insert(1, var_type(MD::VariableType::DtasmReal,
+        MD::VariableValue { real_val: 0.0, ..Default::default() },
+        MD::VariableValue { real_val: 1.0, ..Default::default() }));
+    var_types.insert(2, var_type(MD::VariableType::DtasmInt64,
+        MD::VariableValue { int64_val: -10, ..Default::default() },
+        MD::VariableValue { int64_val: 1 << 40, ..Default::default() }));
+
+    var_types
+}
+
+fn values(valve: f64, counter: i64) -> DtasmVarValues {
+    let mut values = DtasmVarValues::new();
+    values.real_values.insert(1, valve);
+    values.int64_arrays.insert(2, vec![0, counter]);
+
+    values
+}
+
+#[test]
+fn it_accepts_values_within_bounds() {
+    let var_types = var_types();
+
+    assert!(check_bounds(&values(1.0, 1 << 40), &var_types).is_empty());
+    assert!(check_bounds(&values(0.0, -10), &var_types).is_empty());
+}
+
+#[test]
+fn it_reports_violations() {
+    let violations = check_bounds(&values(7.0, -11), &var_types());
+
+    assert_eq!(violations.len(), 2);
+    let valve = violations.iter().find(|v| v.id == 1).unwrap();
+    assert_eq!(valve.value, "7");
+    assert_eq!(valve.max.as_deref(), Some("1"));
+}
+
+#[rstest]
+#[case::reject(BoundsPolicy::Reject)]
+#[case::clamp(BoundsPolicy::Clamp)]
+#[case::warn(BoundsPolicy::Warn)]
+fn it_applies_bounds_policy(#[case] policy: BoundsPolicy) {
+    let mut vals = values(7.0, 1 << 41);
+    let res = apply_bounds_policy(&mut vals, &var_types(), policy);
+
+    match policy {
+        BoundsPolicy::Reject => {
+            assert!(matches!(res, Err(DtasmError::VariableOutOfBounds(_, _))));
+        },
+        BoundsPolicy::Clamp => {
+            assert_eq!(res.unwrap().len(), 2);
+            assert_eq!(vals.real_values[&1], 1.0);
+            assert_eq!(vals.int64_arrays[&2], vec![0, 1 << 40]);
+        },
+        BoundsPolicy::Warn => {
+            assert_eq!(res.unwrap().len(), 2);
+            assert_eq!(vals.real_values[&1], 7.0);
+        }
+    }
+}
+
+#[rstest]
+#[case::reject(BoundsPolicy::Reject)]
+#[case::clamp(BoundsPolicy::Clamp)]
+#[case::warn(BoundsPolicy::Warn)]
+fn it_rejects_nan_with_every_policy(#[case] policy: BoundsPolicy) {
+    let mut vals = values(f64::NAN, 0);
+    let res = apply_bounds_policy(&mut vals, &var_types(), policy);
+
+    match res {
+        Err(DtasmError::VariableOutOfBounds(val, 1)) => assert_eq!(val, "NaN"),
+        res => panic!("Expected NaN to be rejected, got {:?}", res)
+    }
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "columnar")]
+
+use std::{collections::HashMap, fs::File};
+
+mod common;
+use common::{add_model_description_json_path, add_wasm_path};
+
+use dtasmtime::{runtime::{Engine, Module}, types::{DtasmVarValues, LogLevel}};
+use dtasmtime::results::columnar::{read_model_description, ArrowRecorder, ColumnarFormat};
+use dtasmtime::errors::DtasmtimeError;
+use dtasm_base::model_description as MD;
+
+use arrow::array::{Array, FixedSizeListArray, Float64Array, Int32Array, StringArray};
+use arrow::ipc::reader::FileReader;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rstest::rstest;
+
+
+#[test]
+fn it_records_add_results_to_arrow_ipc() {
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(add_wasm_path(), &engine).expect("Could not instantiate dtasm module");
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+    let md = inst.get_model_description().expect("Get Model Description failed!");
+
+    inst.initialize(&DtasmVarValues::new(), 0.0, None, None, LogLevel::Warn, true)
+        .expect("Failed to initialize add_rs.wasm");
+
+    let map_name_id: HashMap<String, i32> = md.variables.iter()
+        .map(|v| (v.name.to_string(), v.id))
+        .collect();
+    let out_ids: Vec<i32> = md.variables.iter()
+        .filter(|v| v.causality == MD::CausalityType::Output)
+        .map(|v| v.id)
+        .collect();
+
+    let dir = tempfile::tempdir().expect("Could not create temporary directory");
+    let ipc_path = dir.path().join("add_results.arrow");
+
+    // batch size smaller than the number of steps to get multiple batches
+    let mut recorder = ArrowRecorder::create(ipc_path.clone(), ColumnarFormat::ArrowIpc, &md, 4)
+        .expect("Could not create recorder");
+
+    let mut t = 0.0;
+    for i in 0..10 {
+        let mut input_vals = DtasmVarValues::new();
+        input_vals.real_values.insert(map_name_id["real_in1"], i as f64);
+        input_vals.real_values.insert(map_name_id["real_in2"], 1.0);
+        input_vals.int_values.insert(map_name_id["int_in1"], i);
+        input_vals.int_values.insert(map_name_id["int_in2"], i);
+        input_vals.string_values.insert(map_name_id["string_in1"], "x".to_string());
+        input_vals.string_values.insert(map_name_id["string_in2"], i.to_string());
+
+        inst.set_values(&input_vals).expect("Could not set input values");
+        t = inst.do_step(t, 0.1).expect("DoStep failed").updated_time;
+
+        let values = inst.get_values(&out_ids).expect("Error in get values").values;
+        recorder.record(&values, t).expect("Could not record values");
+    }
+    recorder.finish().expect("Could not finish recording");
+
+    let reader = FileReader::try_new(File::open(ipc_path).unwrap(), None)
+        .expect("Could not open Arrow IPC file");
+    let schema = reader.schema();
+
+    assert_eq!(read_model_description(&schema).expect("Could not read model description"), md);
+    let real_out_field = schema.field_with_name("real_out").unwrap();
+    assert_eq!(real_out_field.metadata().as_ref().unwrap()["dtasm.causality"], "Output");
+
+    let batches: Vec<_> = reader.map(|b| b.expect("Invalid record batch")).collect();
+    assert_eq!(batches.len(), 3);
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+
+    let last = &batches[2];
+    let col = |name: &str| last.column(schema.index_of(name).unwrap()).clone();
+
+    let real_out = col("real_out");
+    let real_out = real_out.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(real_out.value(1), 10.0);
+
+    let int_out = col("int_out");
+    let int_out = int_out.as_any().downcast_ref::<Int32Array>().unwrap();
+    assert_eq!(int_out.value(1), 18);
+
+    let string_out = col("string_out");
+    let string_out = string_out.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(string_out.value(1), "x9");
+
+    // inputs were not passed to the recorder and are recorded as null
+    let real_in1 = col("real_in1");
+    assert_eq!(real_in1.null_count(), 2);
+}
+
+#[rstest]
+#[case::arrow_ipc(ColumnarFormat::ArrowIpc)]
+#[case::parquet(ColumnarFormat::Parquet)]
+fn it_stores_the_model_description(#[case] format: ColumnarFormat) {
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(add_wasm_path(), &engine).expect("Could not instantiate dtasm module");
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+    let md = inst.get_model_description().expect("Get Model Description failed!");
+
+    let dir = tempfile::tempdir().expect("Could not create temporary directory");
+    let path = dir.path().join("add_results");
+
+    let mut recorder = ArrowRecorder::create(path.clone(), format, &md, 4)
+        .expect("Could not create recorder");
+    recorder.record(&DtasmVarValues::new(), 0.0).expect("Could not record values");
+    recorder.finish().expect("Could not finish recording");
+
+    let file = File::open(path).unwrap();
+    let schema = match format {
+        ColumnarFormat::ArrowIpc => FileReader::try_new(file, None)
+            .expect("Could not open Arrow IPC file").schema(),
+        ColumnarFormat::Parquet => ParquetRecordBatchReaderBuilder::try_new(file)
+            .expect("Could not open Parquet file").schema().clone()
+    };
+
+    assert_eq!(read_model_description(&schema).expect("Could not read model description"), md);
+}
+
+#[test]
+fn it_records_array_variables_as_lists() {
+    let mut md = MD::ModelDescription::read_json(add_model_description_json_path()).expect("Could not read modelDescription.json");
+    let real_out = md.variables.iter_mut().find(|v| v.name == "real_out").unwrap();
+    real_out.dimensions = vec![3];
+    let real_out_id = real_out.id;
+
+    let dir = tempfile::tempdir().expect("Could not create temporary directory");
+    let ipc_path = dir.path().join("array_results.arrow");
+    let mut recorder = ArrowRecorder::create(ipc_path.clone(), ColumnarFormat::ArrowIpc, &md, 4)
+        .expect("Could not create recorder");
+
+    let mut values = DtasmVarValues::new();
+    values.real_arrays.insert(real_out_id, vec![1.0, 2.0, 3.0]);
+    recorder.record(&values, 0.0).expect("Could not record values");
+    recorder.record(&DtasmVarValues::new(), 0.1).expect("Could not record values");
+
+    values.real_arrays.insert(real_out_id, vec![1.0, 2.0]);
+    let res = recorder.record(&values, 0.2);
+    assert!(matches!(res, Err(DtasmtimeError::ResultFormatError(_))));
+    recorder.finish().expect("Could not finish recording");
+
+    let reader = FileReader::try_new(File::open(ipc_path).unwrap(), None)
+        .expect("Could not open Arrow IPC file");
+    let schema = reader.schema();
+    let field = schema.field_with_name("real_out").unwrap();
+    assert_eq!(field.metadata().as_ref().unwrap()["dtasm.dimensions"], "3");
+
+    let batches: Vec<_> = reader.map(|b| b.expect("Invalid record batch")).collect();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].num_rows(), 2);
+
+    let real_out = batches[0].column(schema.index_of("real_out").unwrap()).clone();
+    let real_out = real_out.as_any().downcast_ref::<FixedSizeListArray>().unwrap();
+    let first = real_out.value(0);
+    let first = first.as_any().downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(first.values(), &[1.0, 2.0, 3.0]);
+    assert!(real_out.is_null(1));
+}
@@ -0,0 +1,117 @@
+// Paths shared by the integration tests of dtasmtime and the crates built on
+// it; not every test file uses all of them
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempPath;
+
+
+/// The `runtime` directory, parent of all crates including this module
+fn runtime_dir() -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("..");
+
+    path
+}
+
+/// Path of a compiled dtasm module in the test assets directory of dtasmtime
+pub fn asset_path(file_name: &str) -> PathBuf {
+    let mut path = runtime_dir();
+    path.push("dtasmtime");
+    path.push("tests");
+    path.push("assets");
+    path.push(file_name);
+
+    path
+}
+
+pub fn add_wasm_path() -> PathBuf {
+    asset_path("add_rs.wasm")
+}
+
+/// Path of the model description the add_rs module is built from
+pub fn add_model_description_json_path() -> PathBuf {
+    let mut path = runtime_dir();
+    path.push("..");
+    path.push("module");
+    path.push("add_rs");
+    path.push("src");
+    path.push("modelDescription.json");
+
+    path
+}
+
+pub fn test_wasm_path() -> PathBuf {
+    asset_path("test_rs.wasm")
+}
+
+/// Copy of a module with its `dtasm.*` custom sections replaced by the given
+/// ones, written to a temporary file
+///
+/// * `source` - path of the module
+/// * `sections` - names and contents of the custom sections to append
+pub fn wasm_with_sections(source: &Path, sections: &[(&str, &[u8])]) -> TempPath {
+    let bytes = std::fs::read(source).expect("Could not read module");
+
+    // keep the header and all sections except dtasm custom sections
+    let mut out = bytes[..8].to_vec();
+    let mut pos = 8;
+    while pos < bytes.len() {
+        let start = pos;
+        let (size, size_len) = read_leb128_u32(&bytes[pos + 1..]);
+        pos += 1 + size_len + size as usize;
+
+        let is_custom = bytes[start] == 0;
+        if !is_custom || !custom_section_name(&bytes[start + 1 + size_len..pos]).starts_with("dtasm.") {
+            out.extend_from_slice(&bytes[start..pos]);
+        }
+    }
+
+    for (section_name, data) in sections {
+        let mut contents = leb128_u32(section_name.len() as u32);
+        contents.extend_from_slice(section_name.as_bytes());
+        contents.extend_from_slice(data);
+
+        out.push(0);
+        out.extend(leb128_u32(contents.len() as u32));
+        out.extend(contents);
+    }
+
+    let mut file = tempfile::Builder::new().suffix(".wasm").tempfile().expect("Could not create module file");
+    file.write_all(&out).expect("Could not write module");
+
+    file.into_temp_path()
+}
+
+fn custom_section_name(contents: &[u8]) -> String {
+    let (len, len_len) = read_leb128_u32(contents);
+
+    String::from_utf8_lossy(&contents[len_len..len_len + len as usize]).into_owned()
+}
+
+fn read_leb128_u32(bytes: &[u8]) -> (u32, usize) {
+    let mut value = 0;
+    for (i, byte) in bytes.iter().take(5).enumerate() {
+        value |= ((byte & 0x7f) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return (value, i + 1);
+        }
+    }
+
+    panic!("Invalid LEB128 value");
+}
+
+fn leb128_u32(mut value: u32) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
@@ -0,0 +1,20 @@
+mod common;
+use common::add_wasm_path;
+
+use dtasmtime::runtime::{Engine, Module};
+use dtasmtime::conformance::check_module;
+
+
+#[test]
+fn add_module_conforms() {
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(add_wasm_path(), &engine).expect("Could not instantiate dtasm module");
+
+    let report = check_module(&mut dtasm_module);
+
+    assert_eq!(report.results.len(), 8);
+    for result in &report.results {
+        assert!(result.passed(), "Check {} failed: {:?}", result.name, result.error);
+    }
+    assert!(report.passed());
+}
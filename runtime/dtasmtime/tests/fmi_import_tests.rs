@@ -0,0 +1,164 @@
+use dtasm_base::fmi::convert_fmi_model_description;
+use dtasm_base::model_description as MD;
+use dtasm_base::validation::{validate, has_errors, Diagnostic};
+
+
+const FMI2_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<fmiModelDescription fmiVersion="2.0" modelName="BouncingBall" guid="{8c4e810f-3df3-4a00-8276-176fa3c9f003}"
+    description="Bouncing ball" generationTool="Reference FMUs" variableNamingConvention="structured">
+  <CoSimulation modelIdentifier="BouncingBall" canHandleVariableCommunicationStepSize="true" canGetAndSetFMUstate="true"/>
+  <UnitDefinitions>
+    <Unit name="m"><BaseUnit m="1"/></Unit>
+    <Unit name="m/s"><BaseUnit m="1" s="-1"/></Unit>
+    <Unit name="km"><BaseUnit m="1" factor="1000"/><DisplayUnit name="mm" factor="1000000"/></Unit>
+  </UnitDefinitions>
+  <TypeDefinitions>
+    <SimpleType name="Position"><Real unit="m" min="0"/></SimpleType>
+    <SimpleType name="Mode">
+      <Enumeration>
+        <Item name="Falling" value="1"/>
+        <Item name="Resting" value="2"/>
+      </Enumeration>
+    </SimpleType>
+  </TypeDefinitions>
+  <DefaultExperiment startTime="0" stopTime="3" stepSize="0.01" tolerance="1e-4"/>
+  <ModelVariables>
+    <ScalarVariable name="time" valueReference="0" causality="independent"><Real/></ScalarVariable>
+    <ScalarVariable name="h" valueReference="1" causality="output" initial="exact"><Real declaredType="Position" start="1"/></ScalarVariable>
+    <ScalarVariable name="der(h)" valueReference="2" causality="local"><Real unit="m/s" derivative="2"/></ScalarVariable>
+    <ScalarVariable name="e" valueReference="3" causality="parameter" variability="tunable"><Real start="0.7" min="0.5" max="1"/></ScalarVariable>
+    <ScalarVariable name="mode" valueReference="1" causality="output" variability="discrete"><Enumeration declaredType="Mode" start="1"/></ScalarVariable>
+    <ScalarVariable name="height" valueReference="1" causality="local"><Real/></ScalarVariable>
+    <ScalarVariable name="bounce" valueReference="4" causality="input" variability="discrete"><Boolean start="false"/></ScalarVariable>
+    <ScalarVariable name="label" valueReference="5" causality="parameter" variability="fixed"><String start="ball"/></ScalarVariable>
+  </ModelVariables>
+  <ModelStructure/>
+</fmiModelDescription>
+"#;
+
+const FMI3_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<fmiModelDescription fmiVersion="3.0" modelName="Feedthrough" instantiationToken="{37B954F1-CC86-4D8F-B97F-C7C36F6670D2}">
+  <CoSimulation modelIdentifier="Feedthrough" canHandleVariableCommunicationStepSize="true" canInterpolateInputs="false"
+      hasEventMode="true"/>
+  <DefaultExperiment stopTime="2" stepSize="0.5"/>
+  <ModelVariables>
+    <Float64 name="time" valueReference="0" causality="independent" variability="continuous"/>
+    <Float32 name="Float32_input" valueReference="1" causality="input" start="1.5"/>
+    <Float64 name="Float64_array" valueReference="2" causality="output">
+      <Dimension start="3"/>
+    </Float64>
+    <Int8 name="Int8_input" valueReference="3" causality="input" start="-4"/>
+    <UInt64 name="UInt64_input" valueReference="4" causality="input" start="7"/>
+    <Int64 name="Int64_param" valueReference="5" causality="structuralParameter" variability="fixed" start="2"/>
+    <Binary name="Binary_input" valueReference="6" causality="input">
+      <Start value="0a0b"/>
+    </Binary>
+    <Clock name="tick" valueReference="7" causality="input" intervalVariability="triggered"/>
+    <Float64 name="der_Float64" valueReference="8" causality="local" derivative="2"/>
+    <Float64 name="sized" valueReference="9" causality="output">
+      <Dimension valueReference="5"/>
+    </Float64>
+  </ModelVariables>
+</fmiModelDescription>
+"#;
+
+
+fn variable<'a>(md: &'a MD::ModelDescription, name: &str) -> &'a MD::ModelVariable {
+    md.variables.iter().find(|v| v.name == name).expect("Variable not converted")
+}
+
+fn reported(diagnostics: &[Diagnostic], text: &str) -> bool {
+    diagnostics.iter().any(|d| d.message.contains(text))
+}
+
+#[test]
+fn it_converts_fmi2_model_descriptions() {
+    let (md, warnings) = convert_fmi_model_description(FMI2_XML).expect("Could not convert model description");
+
+    assert_eq!(md.model.name, "BouncingBall");
+    assert_eq!(md.model.id, "{8c4e810f-3df3-4a00-8276-176fa3c9f003}");
+    assert_eq!(md.model.generation_tool, "Reference FMUs");
+    assert!(md.model.capabilities.can_handle_variable_step_size);
+    assert!(!md.model.capabilities.can_interpolate_inputs);
+
+    let exp = md.experiment.as_ref().unwrap();
+    assert_eq!(exp.end_time_default, 3.0);
+    assert_eq!(exp.time_step_default, 0.01);
+
+    assert_eq!(md.unit_definitions.len(), 3);
+    assert_eq!(md.unit_definitions[1].base_unit.s, -1);
+    assert_eq!(md.unit_definitions[2].base_unit.factor, 1000.0);
+    assert_eq!(md.enumeration_types[0].name, "Mode");
+    assert_eq!(md.enumeration_types[0].items.len(), 2);
+
+    let h = variable(&md, "h");
+    assert_eq!(h.id, 1);
+    assert_eq!(h.causality, MD::CausalityType::Output);
+    assert_eq!(h.unit, "m");
+    assert_eq!(h.min.as_ref().unwrap().real_val, 0.0);
+    assert_eq!(h.default.as_ref().unwrap().real_val, 1.0);
+
+    assert_eq!(variable(&md, "der(h)").derivative_of_id, 1);
+
+    let e = variable(&md, "e");
+    assert_eq!(e.variability, MD::VariabilityType::Tunable);
+    assert_eq!(e.max.as_ref().unwrap().real_val, 1.0);
+
+    let mode = variable(&md, "mode");
+    assert_eq!(mode.value_type, MD::VariableType::DtasmInt);
+    assert_eq!(mode.enumeration_type, "Mode");
+    assert_eq!(mode.id, 6);
+
+    assert!(!variable(&md, "bounce").default.as_ref().unwrap().bool_val);
+    assert_eq!(variable(&md, "label").default.as_ref().unwrap().string_val, "ball");
+
+    assert!(md.variables.iter().all(|v| v.name != "time" && v.name != "height"));
+    assert!(reported(&warnings, "canGetAndSetFMUstate"));
+    assert!(reported(&warnings, "tolerance"));
+    assert!(reported(&warnings, "Display units of unit `km`"));
+    assert!(reported(&warnings, "`time` of causality `independent`"));
+    assert!(reported(&warnings, "Alias variable `height`"));
+
+    assert!(!has_errors(&validate(&md)));
+}
+
+#[test]
+fn it_converts_fmi3_model_descriptions() {
+    let (md, warnings) = convert_fmi_model_description(FMI3_XML).expect("Could not convert model description");
+
+    assert_eq!(md.model.id, "{37B954F1-CC86-4D8F-B97F-C7C36F6670D2}");
+    assert_eq!(md.experiment.as_ref().unwrap().start_time_default, 0.0);
+
+    let float32 = variable(&md, "Float32_input");
+    assert_eq!(float32.value_type, MD::VariableType::DtasmFloat32);
+    assert_eq!(float32.default.as_ref().unwrap().float32_val, 1.5);
+
+    let array = variable(&md, "Float64_array");
+    assert_eq!(array.dimensions, vec![3]);
+    assert_eq!(array.variability, MD::VariabilityType::Continuous);
+
+    let int8 = variable(&md, "Int8_input");
+    assert_eq!(int8.value_type, MD::VariableType::DtasmInt);
+    assert_eq!(int8.variability, MD::VariabilityType::Discrete);
+    assert_eq!(int8.default.as_ref().unwrap().int_val, -4);
+
+    assert_eq!(variable(&md, "UInt64_input").value_type, MD::VariableType::DtasmInt64);
+    assert_eq!(variable(&md, "Int64_param").causality, MD::CausalityType::Parameter);
+    assert_eq!(variable(&md, "Binary_input").default.as_ref().unwrap().binary_val, vec![0x0a, 0x0b]);
+    assert_eq!(variable(&md, "der_Float64").derivative_of_id, 2);
+    assert_eq!(variable(&md, "sized").dimensions, vec![1]);
+
+    assert!(md.variables.iter().all(|v| v.name != "tick"));
+    assert!(reported(&warnings, "hasEventMode"));
+    assert!(reported(&warnings, "UInt64 variable `UInt64_input`"));
+    assert!(reported(&warnings, "Structural parameter `Int64_param`"));
+    assert!(reported(&warnings, "`tick` of type Clock"));
+    assert!(reported(&warnings, "Variable size dimension of variable `sized`"));
+}
+
+#[test]
+fn it_rejects_unsupported_documents() {
+    assert!(convert_fmi_model_description("<fmiModelDescription fmiVersion=\"1.0\"/>").is_err());
+    assert!(convert_fmi_model_description("<modelDescription fmiVersion=\"2.0\"/>").is_err());
+    assert!(convert_fmi_model_description("not xml").is_err());
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+mod common;
+use common::{add_model_description_json_path, add_wasm_path};
+
+use dtasmtime::{runtime::{Engine, Module}, types::{DtasmVarValues, LogLevel}};
+use dtasmtime::errors::DtasmtimeError;
+use dtasmtime::results::mat::{MatResultWriter, read_mat_results};
+use dtasm_base::errors::DtasmError;
+use dtasm_base::model_description as MD;
+
+use float_cmp::approx_eq;
+use rstest::rstest;
+
+
+#[test]
+fn it_round_trips_add_results() {
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(add_wasm_path(), &engine).expect("Could not instantiate dtasm module");
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+    let md = inst.get_model_description().expect("Get Model Description failed!");
+
+    inst.initialize(&DtasmVarValues::new(), 0.0, None, None, LogLevel::Warn, true)
+        .expect("Failed to initialize add_rs.wasm");
+
+    let map_name_id: HashMap<String, i32> = md.variables.iter()
+        .map(|v| (v.name.to_string(), v.id))
+        .collect();
+    let out_ids: Vec<i32> = md.variables.iter()
+        .filter(|v| v.causality != MD::CausalityType::Input)
+        .map(|v| v.id)
+        .collect();
+
+    let dir = tempfile::tempdir().expect("Could not create temporary directory");
+    let mat_path = dir.path().join("add_results.mat");
+
+    let mut writer = MatResultWriter::create(mat_path.clone(), &md, &DtasmVarValues::new(), 0.0)
+        .expect("Could not create result file");
+
+    let mut t = 0.0;
+    for i in 0..5 {
+        let mut input_vals = DtasmVarValues::new();
+        input_vals.real_values.insert(map_name_id["real_in1"], i as f64);
+        input_vals.real_values.insert(map_name_id["real_in2"], 0.5);
+        input_vals.int_values.insert(map_name_id["int_in1"], i);
+        input_vals.int_values.insert(map_name_id["int_in2"], 10);
+        input_vals.bool_values.insert(map_name_id["bool_in1"], i % 2 == 0);
+        input_vals.bool_values.insert(map_name_id["bool_in2"], true);
+
+        inst.set_values(&input_vals).expect("Could not set input values");
+        t = inst.do_step(t, 0.1).expect("DoStep failed").updated_time;
+
+        let mut values = inst.get_values(&out_ids).expect("Error in get values").values;
+        values.real_values.extend(input_vals.real_values);
+        values.int_values.extend(input_vals.int_values);
+        values.bool_values.extend(input_vals.bool_values);
+        writer.write_record(&values, t).expect("Could not write result record");
+    }
+    writer.finish().expect("Could not finish result file");
+
+    let results = read_mat_results(mat_path).expect("Could not read result file");
+
+    assert_eq!(results.names[0], "time");
+    assert!(!results.names.contains(&"string_out".to_string()));
+
+    let time = results.time();
+    assert_eq!(time.len(), 5);
+    assert!( approx_eq!(f64, time[4], t, ulps = 2) );
+
+    let real_out = results.trajectory("real_out").expect("real_out missing");
+    let int_out = results.trajectory("int_out").expect("int_out missing");
+    let bool_out = results.trajectory("bool_out").expect("bool_out missing");
+    for i in 0..5 {
+        assert!( approx_eq!(f64, real_out[i], i as f64 + 0.5, ulps = 2) );
+        assert_eq!(int_out[i], i as f64 + 10.0);
+        assert_eq!(bool_out[i], if i % 2 == 0 { 1.0 } else { 0.0 });
+    }
+
+    assert_eq!(results.data_1.len(), 2);
+    assert!( approx_eq!(f64, results.data_1[1][0], t, ulps = 2) );
+}
+
+#[test]
+fn it_stores_array_elements_as_variables() {
+    let mut md = MD::ModelDescription::read_json(add_model_description_json_path()).expect("Could not read modelDescription.json");
+    let real_out = md.variables.iter_mut().find(|v| v.name == "real_out").unwrap();
+    real_out.dimensions = vec![2, 3];
+    let real_out_id = real_out.id;
+
+    let dir = tempfile::tempdir().expect("Could not create temporary directory");
+    let mat_path = dir.path().join("array_results.mat");
+    let mut writer = MatResultWriter::create(mat_path.clone(), &md, &DtasmVarValues::new(), 0.0)
+        .expect("Could not create result file");
+
+    for t in 0..2 {
+        let mut values = DtasmVarValues::new();
+        for variable in md.variables.iter().filter(|v| v.causality != MD::CausalityType::Parameter) {
+            values.insert_default(variable, &MD::VariableValue::default());
+        }
+        values.real_arrays.insert(real_out_id, (0..6).map(|i| (10 * t + i) as f64).collect());
+        writer.write_record(&values, t as f64).expect("Could not write result record");
+    }
+    writer.finish().expect("Could not finish result file");
+
+    let results = read_mat_results(mat_path).expect("Could not read result file");
+
+    assert!(!results.names.contains(&"real_out".to_string()));
+    assert_eq!(results.trajectory("real_out[1,1]").expect("real_out[1,1] missing"), vec![0.0, 10.0]);
+    assert_eq!(results.trajectory("real_out[1,3]").expect("real_out[1,3] missing"), vec![2.0, 12.0]);
+    assert_eq!(results.trajectory("real_out[2,1]").expect("real_out[2,1] missing"), vec![3.0, 13.0]);
+    assert_eq!(results.trajectory("real_out[2,3]").expect("real_out[2,3] missing"), vec![5.0, 15.0]);
+}
+
+#[test]
+fn it_rejects_records_with_missing_array_elements() {
+    let mut md = MD::ModelDescription::read_json(add_model_description_json_path()).expect("Could not read modelDescription.json");
+    let real_out = md.variables.iter_mut().find(|v| v.name == "real_out").unwrap();
+    real_out.dimensions = vec![3];
+    let real_out_id = real_out.id;
+
+    let dir = tempfile::tempdir().expect("Could not create temporary directory");
+    let mut writer = MatResultWriter::create(dir.path().join("array_results.mat"), &md, &DtasmVarValues::new(), 0.0)
+        .expect("Could not create result file");
+
+    let mut values = DtasmVarValues::new();
+    for variable in md.variables.iter().filter(|v| v.causality != MD::CausalityType::Parameter) {
+        values.insert_default(variable, &MD::VariableValue::default());
+    }
+    values.real_arrays.insert(real_out_id, vec![1.0, 2.0]);
+
+    let res = writer.write_record(&values, 0.0);
+    assert!(matches!(res, Err(DtasmtimeError::DtasmError(DtasmError::UnknownVariableId(id))) if id == real_out_id));
+}
+
+/// MAT v4 matrix header: type, rows, columns, imaginary flag, name length
+fn header(mat_type: i32, mrows: i32, ncols: i32, namlen: i32) -> Vec<u8> {
+    [mat_type, mrows, ncols, 0, namlen].iter()
+        .flat_map(|field| field.to_le_bytes().to_vec())
+        .collect()
+}
+
+#[rstest]
+#[case::truncated_header(header(0, 1, 1, 5)[..12].to_vec())]
+#[case::name_exceeds_file(header(0, 1, 1, i32::MAX))]
+#[case::size_overflows(header(0, i32::MAX, i32::MAX, 5).into_iter().chain(b"data\0".to_vec()).collect())]
+#[case::size_exceeds_file(header(0, 1000, 1000, 5).into_iter().chain(b"data\0".to_vec()).collect())]
+fn it_rejects_corrupt_matrix_headers(#[case] bytes: Vec<u8>) {
+    let dir = tempfile::tempdir().expect("Could not create temporary directory");
+    let mat_path = dir.path().join("corrupt.mat");
+    std::fs::write(&mat_path, bytes).expect("Could not write result file");
+
+    let res = read_mat_results(mat_path);
+    assert!(matches!(res, Err(DtasmtimeError::ResultFormatError(_))), "{:?}", res.err());
+}
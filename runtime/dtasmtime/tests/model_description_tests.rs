@@ -0,0 +1,168 @@
+mod common;
+use common::{add_wasm_path, add_model_description_json_path, wasm_with_sections};
+
+use dtasmtime::runtime::{Engine, Module, MODEL_DESCRIPTION_SECTION};
+use dtasm_abi::dtasm_generated::dtasm_model_description as DTMD;
+use dtasm_base::model_conversion::{convert_model_description, model_description_to_bytes};
+use dtasm_base::model_description as MD;
+use dtasm_base::abi::ABI_VERSION_SECTION;
+
+use rstest::rstest;
+
+
+fn add_model_description() -> MD::ModelDescription {
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(add_wasm_path(), &engine).expect("Could not instantiate dtasm module");
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+
+    inst.get_model_description().expect("Get Model Description failed!")
+}
+
+#[test]
+fn it_reads_flatc_json() {
+    let md_json = MD::ModelDescription::read_json(add_model_description_json_path()).expect("Could not read modelDescription.json");
+    let md_wasm = add_model_description();
+
+    assert_eq!(md_json, md_wasm);
+    assert_eq!(md_json.variables[0].derivative_of_id, -1);
+    assert_eq!(md_json.variables[0].causality, MD::CausalityType::Input);
+}
+
+#[test]
+fn it_round_trips_json() {
+    let md = add_model_description();
+
+    let json = md.to_json().expect("Could not serialize model description");
+    assert!(json.contains("\"generation_datetime\""));
+    assert!(json.contains("\"timestep_default\""));
+    assert!(json.contains("\"causality\": \"input\""));
+
+    let md_json = MD::ModelDescription::from_json(&json).expect("Could not parse model description");
+    assert_eq!(md, md_json);
+}
+
+#[test]
+fn it_round_trips_flatbuffer() {
+    let md = add_model_description();
+
+    let bytes = model_description_to_bytes(&md);
+    assert!(DTMD::model_description_buffer_has_identifier(&bytes));
+
+    let md_fb = DTMD::root_as_model_description(&bytes).expect("Invalid model description buffer");
+    assert_eq!(convert_model_description(&md_fb), md);
+}
+
+#[test]
+fn it_round_trips_array_dimensions() {
+    let mut md = add_model_description();
+    md.variables[0].dimensions = vec![2, 100];
+
+    let json = md.to_json().expect("Could not serialize model description");
+    assert_eq!(json.matches("\"dimensions\"").count(), 1);
+    assert_eq!(MD::ModelDescription::from_json(&json).expect("Could not parse model description"), md);
+
+    let bytes = model_description_to_bytes(&md);
+    let md_fb = DTMD::root_as_model_description(&bytes).expect("Invalid model description buffer");
+    let md_conv = convert_model_description(&md_fb);
+
+    let var = md_conv.variables.iter().find(|v| v.id == md.variables[0].id).unwrap();
+    assert_eq!(var.dimensions, vec![2, 100]);
+    assert_eq!(var.array_len(), 200);
+    assert!(md_conv.variables.iter().filter(|v| v.id != var.id).all(|v| !v.is_array()));
+}
+
+#[test]
+fn it_round_trips_binary_default() {
+    let mut md = add_model_description();
+    md.variables[0].value_type = MD::VariableType::DtasmBinary;
+    md.variables[0].default = Some(MD::VariableValue { binary_val: vec![0, 1, 254, 255], ..Default::default() });
+
+    let json = md.to_json().expect("Could not serialize model description");
+    assert_eq!(json.matches("\"binary_val\"").count(), 1);
+    assert_eq!(MD::ModelDescription::from_json(&json).expect("Could not parse model description"), md);
+
+    let bytes = model_description_to_bytes(&md);
+    let md_fb = DTMD::root_as_model_description(&bytes).expect("Invalid model description buffer");
+    assert_eq!(convert_model_description(&md_fb), md);
+}
+
+#[test]
+fn it_round_trips_variability() {
+    let mut md = add_model_description();
+    md.variables[0].causality = MD::CausalityType::Parameter;
+    md.variables[0].variability = MD::VariabilityType::Tunable;
+    assert_eq!(md.variables[1].variability, MD::VariabilityType::Continuous);
+
+    let json = md.to_json().expect("Could not serialize model description");
+    assert!(json.contains("\"variability\": \"tunable\""));
+    assert_eq!(MD::ModelDescription::from_json(&json).expect("Could not parse model description"), md);
+
+    let bytes = model_description_to_bytes(&md);
+    let md_fb = DTMD::root_as_model_description(&bytes).expect("Invalid model description buffer");
+    let md_conv = convert_model_description(&md_fb);
+    assert_eq!(md_conv, md);
+    assert!(md_conv.variables[0].is_settable_between_steps());
+    assert!(!md_conv.variables.iter().any(|v| v.causality == MD::CausalityType::Output && v.is_settable_between_steps()));
+}
+
+#[test]
+fn it_round_trips_int64_and_float32_defaults() {
+    let mut md = add_model_description();
+    md.variables[0].value_type = MD::VariableType::DtasmInt64;
+    md.variables[0].default = Some(MD::VariableValue { int64_val: 1 << 40, ..Default::default() });
+    md.variables[1].value_type = MD::VariableType::DtasmFloat32;
+    md.variables[1].default = Some(MD::VariableValue { float32_val: 0.5, ..Default::default() });
+
+    let json = md.to_json().expect("Could not serialize model description");
+    assert_eq!(MD::ModelDescription::from_json(&json).expect("Could not parse model description"), md);
+
+    let bytes = model_description_to_bytes(&md);
+    let md_fb = DTMD::root_as_model_description(&bytes).expect("Invalid model description buffer");
+    assert_eq!(convert_model_description(&md_fb), md);
+}
+
+#[test]
+fn it_falls_back_to_model_description_export() {
+    let path = wasm_with_sections(&add_wasm_path(), &[]);
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(path.to_path_buf(), &engine).expect("Could not instantiate dtasm module");
+    assert_eq!(dtasm_module.model_description().expect("Could not read model description"), add_model_description());
+}
+
+#[test]
+fn it_reads_model_description_section_of_dtasm_rs_modules() {
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(add_wasm_path(), &engine).expect("Could not instantiate dtasm module");
+
+    assert_eq!(dtasm_module.model_description().expect("Could not read model description"), add_model_description());
+}
+
+#[rstest]
+#[case::current(Some("1.1"))]
+#[case::legacy(None)]
+fn it_reads_model_description_section(#[case] abi_version: Option<&str>) {
+    let mut md = add_model_description();
+    md.model.name = "AddFromSection".to_string();
+    let md_bytes = model_description_to_bytes(&md);
+
+    let mut sections: Vec<(&str, &[u8])> = vec![(MODEL_DESCRIPTION_SECTION, &md_bytes)];
+    if let Some(version) = abi_version {
+        sections.push((ABI_VERSION_SECTION, version.as_bytes()));
+    }
+    let path = wasm_with_sections(&add_wasm_path(), &sections);
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(path.to_path_buf(), &engine).expect("Could not instantiate dtasm module");
+    assert_eq!(dtasm_module.model_description().expect("Could not read model description"), md);
+}
+
+#[test]
+fn it_rejects_invalid_model_description_section() {
+    let path = wasm_with_sections(&add_wasm_path(),
+        &[(ABI_VERSION_SECTION, b"1.1"), (MODEL_DESCRIPTION_SECTION, &[1, 2, 3])]);
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(path.to_path_buf(), &engine).expect("Could not instantiate dtasm module");
+    assert!(dtasm_module.model_description().is_err());
+}
@@ -0,0 +1,42 @@
+use dtasmtime::stepping::Stepper;
+use dtasmtime::types::{DoStepResponse, Status};
+
+use float_cmp::approx_eq;
+use rstest::rstest;
+
+
+fn response(updated_time: f64, event_occurred: bool, next_time_event: Option<f64>) -> DoStepResponse {
+    DoStepResponse {
+        status: Status::OK,
+        updated_time,
+        message: None,
+        error_code: None,
+        event_occurred,
+        next_time_event
+    }
+}
+
+#[rstest]
+#[case::no_event(None, 1.0, 0.1)]
+#[case::event_within_step(Some(1.04), 1.0, 0.04)]
+#[case::event_after_step(Some(1.5), 1.0, 0.1)]
+#[case::event_at_step_end(Some(1.1), 1.0, 0.1)]
+#[case::event_reached(Some(1.0), 1.0, 0.1)]
+#[case::event_passed(Some(0.5), 1.0, 0.1)]
+fn it_truncates_steps_to_time_events(#[case] next_time_event: Option<f64>, #[case] t: f64, #[case] step: f64) {
+    let mut stepper = Stepper::new(0.1);
+    stepper.update(&response(t, false, next_time_event));
+
+    assert_eq!(stepper.next_time_event(), next_time_event);
+    assert!( approx_eq!(f64, stepper.next_step(t), step, epsilon = 1e-12) );
+}
+
+#[test]
+fn it_forgets_time_events_not_announced_again() {
+    let mut stepper = Stepper::new(0.1);
+    stepper.update(&response(0.96, true, Some(1.04)));
+    assert!( approx_eq!(f64, stepper.next_step(1.0), 0.04, epsilon = 1e-12) );
+
+    stepper.update(&response(1.04, true, None));
+    assert_eq!(stepper.next_step(1.04), 0.1);
+}
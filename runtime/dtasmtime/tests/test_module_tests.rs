@@ -0,0 +1,158 @@
+mod common;
+use common::test_wasm_path;
+
+use std::sync::{Arc, Mutex};
+
+use dtasmtime::{errors::DtasmtimeError, runtime::{Engine, Instance, LogRecord, Module}, stepping::Stepper, types::{DtasmVarValues, LogLevel, Status}};
+use dtasm_base::errors::DtasmError;
+
+use rstest::{fixture, rstest};
+
+
+const MODE_IN: i32 = 1;
+const MODE_OUT: i32 = 2;
+const FAIL_STEP: i32 = 3;
+const LOG_MESSAGES: i32 = 4;
+const LOG_OUT_OF_BOUNDS: i32 = 5;
+
+#[fixture]
+fn inst() -> Instance {
+    let test_path = test_wasm_path();
+
+    if !test_path.exists() {
+        panic!("test_rs.wasm not found - did you run `make test-assets`?");
+    }
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(test_path, &engine).expect("Could not instantiate dtasm module");
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+    inst.get_model_description().expect("Get Model Description failed!");
+
+    inst
+}
+
+fn initialize(inst: &mut Instance) {
+    inst.initialize(&DtasmVarValues::new(), 0.0, None, None, LogLevel::Warn, true)
+        .expect("Failed to initialize test_rs.wasm");
+}
+
+fn flag_values(id: i32) -> DtasmVarValues {
+    let mut values = DtasmVarValues::new();
+    values.bool_values.insert(id, true);
+
+    values
+}
+
+/// Instance name, level and message of each log record reported by the instance
+fn collect_log_records(inst: &mut Instance) -> Arc<Mutex<Vec<(String, String, String)>>> {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let callback_records = records.clone();
+    inst.set_log_callback(Box::new(move |record: &LogRecord| {
+        callback_records.lock().unwrap().push(
+            (record.instance.to_string(), format!("{:?}", record.level), record.message.to_string()));
+    }));
+
+    records
+}
+
+fn mode_values(mode: i32) -> DtasmVarValues {
+    let mut values = DtasmVarValues::new();
+    values.int_values.insert(MODE_IN, mode);
+
+    values
+}
+
+#[rstest]
+fn it_sets_declared_enumeration_items(mut inst: Instance) {
+    initialize(&mut inst);
+
+    inst.set_values(&mode_values(1)).expect("Failed to set declared enumeration item");
+    inst.do_step(0.0, 0.1).expect("Failed to do step");
+
+    let res = inst.get_values(&vec![MODE_OUT]).expect("Failed to get values");
+    assert_eq!(res.values.int_values[&MODE_OUT], 1);
+}
+
+#[rstest]
+fn it_rejects_undeclared_enumeration_items(mut inst: Instance) {
+    initialize(&mut inst);
+
+    match inst.set_values(&mode_values(5)) {
+        Err(DtasmtimeError::DtasmError(DtasmError::InvalidVariableValue(val, MODE_IN))) => assert_eq!(val, "5"),
+        res => panic!("Expected invalid variable value, got {:?}", res)
+    }
+}
+
+#[rstest]
+fn it_rejects_undeclared_initial_enumeration_items(mut inst: Instance) {
+    let res = inst.initialize(&mode_values(-1), 0.0, None, None, LogLevel::Warn, true);
+
+    assert!(matches!(res, Err(DtasmtimeError::DtasmError(DtasmError::InvalidVariableValue(_, MODE_IN)))));
+}
+
+#[rstest]
+fn it_reports_failed_steps(mut inst: Instance) {
+    initialize(&mut inst);
+    inst.do_step(0.0, 0.1).expect("Failed to do step");
+
+    inst.set_values(&flag_values(FAIL_STEP)).expect("Failed to set values");
+
+    let res = inst.do_step(0.1, 0.1).expect("Failed to do step");
+    assert!(matches!(res.status, Status::Error));
+    assert!(res.message.unwrap().contains("failed as requested"));
+    assert_eq!(res.updated_time, 0.1);
+}
+
+#[rstest]
+fn it_stops_stepping_at_failed_steps(mut inst: Instance) {
+    initialize(&mut inst);
+    let mut stepper = Stepper::new(0.1);
+    stepper.do_step(&mut inst, 0.0).expect("Failed to do step");
+
+    inst.set_values(&flag_values(FAIL_STEP)).expect("Failed to set values");
+
+    match stepper.do_step(&mut inst, 0.1) {
+        Err(DtasmtimeError::DtasmError(DtasmError::DtasmInternalError(message))) => assert!(message.contains("failed as requested")),
+        res => panic!("Expected failed step, got {:?}", res)
+    }
+}
+
+#[rstest]
+#[case::info(LogLevel::Info, vec!["Error", "Warn", "Info"])]
+#[case::warn(LogLevel::Warn, vec!["Error", "Warn"])]
+#[case::error(LogLevel::Error, vec!["Error"])]
+fn it_passes_log_messages_to_the_callback(mut inst: Instance, #[case] log_level: LogLevel, #[case] levels: Vec<&str>) {
+    inst.set_name("test");
+    let records = collect_log_records(&mut inst);
+
+    inst.initialize(&flag_values(LOG_MESSAGES), 0.0, None, None, log_level, true)
+        .expect("Failed to initialize test_rs.wasm");
+    inst.do_step(0.0, 0.1).expect("Failed to do step");
+
+    let records = records.lock().unwrap();
+    assert_eq!(records.iter().map(|(_, level, _)| level.as_str()).collect::<Vec<_>>(), levels);
+    assert!(records.iter().all(|(instance, _, _)| instance == "test"));
+    assert_eq!(records[0].2, "error at t = 0");
+}
+
+#[rstest]
+fn it_drops_log_messages_without_callback(mut inst: Instance) {
+    inst.initialize(&flag_values(LOG_MESSAGES), 0.0, None, None, LogLevel::Info, true)
+        .expect("Failed to initialize test_rs.wasm");
+
+    let res = inst.do_step(0.0, 0.1).expect("Failed to do step");
+    assert!(matches!(res.status, Status::OK));
+}
+
+#[rstest]
+fn it_traps_on_log_messages_out_of_bounds(mut inst: Instance) {
+    let records = collect_log_records(&mut inst);
+    initialize(&mut inst);
+    inst.set_values(&flag_values(LOG_OUT_OF_BOUNDS)).expect("Failed to set values");
+
+    match inst.do_step(0.0, 0.1) {
+        Err(DtasmtimeError::ModuleTrapError(trap)) => assert!(trap.to_string().contains("dtasm.log: message out of bounds")),
+        res => panic!("Expected trap, got {:?}", res)
+    }
+    assert!(records.lock().unwrap().is_empty());
+}
@@ -0,0 +1,131 @@
+mod common;
+use common::add_model_description_json_path;
+
+use dtasmtime::connection::{transfer_values, Connection};
+use dtasmtime::model_description as MD;
+use dtasmtime::types::DtasmVarValues;
+use dtasmtime::units::UnitRegistry;
+use dtasm_base::errors::DtasmError;
+use dtasm_base::validation::{validate, Severity};
+
+use float_cmp::approx_eq;
+use rstest::rstest;
+
+
+fn add_model_description(real_in_unit: &str, real_out_unit: &str) -> MD::ModelDescription {
+    let mut md = MD::ModelDescription::read_json(add_model_description_json_path()).expect("Could not read modelDescription.json");
+    md.variables.iter_mut().find(|v| v.name == "real_in1").unwrap().unit = real_in_unit.to_string();
+    md.variables.iter_mut().find(|v| v.name == "real_out").unwrap().unit = real_out_unit.to_string();
+
+    md
+}
+
+#[rstest]
+#[case("kg.m/s2", "N", 1.0)]
+#[case("kg*m*s-2", "N", 1.0)]
+#[case("rad/s^2", "rad.s-2", 1.0)]
+#[case("1/s", "Hz", 1.0)]
+#[case("km/h", "m/s", 1.0 / 3.6)]
+#[case("kWh", "J", 3.6e6)]
+#[case("mbar", "Pa", 100.0)]
+#[case("rpm", "rad/s", std::f64::consts::PI / 30.0)]
+#[case("cm2", "m2", 1e-4)]
+fn it_converts_compatible_units(#[case] from: &str, #[case] to: &str, #[case] factor: f64) {
+    let registry = UnitRegistry::new();
+    let from_unit = registry.resolve(from).expect("Could not resolve unit");
+    let to_unit = registry.resolve(to).expect("Could not resolve unit");
+
+    let conversion = from_unit.conversion_to(&to_unit).expect("Units are not compatible");
+    assert!( approx_eq!(f64, conversion.factor, factor, ulps = 4) );
+    assert_eq!(conversion.offset, 0.0);
+}
+
+#[test]
+fn it_converts_units_with_offset() {
+    let registry = UnitRegistry::new();
+    let deg_c = registry.resolve("degC").unwrap();
+    let deg_f = registry.resolve("degF").unwrap();
+    let kelvin = registry.resolve("K").unwrap();
+
+    assert!( approx_eq!(f64, deg_c.conversion_to(&kelvin).unwrap().apply(20.0), 293.15, ulps = 4) );
+    assert!( approx_eq!(f64, deg_f.conversion_to(&deg_c).unwrap().apply(212.0), 100.0, epsilon = 1e-12) );
+
+    // temperature differences do not carry the offset
+    let per_deg_c = registry.resolve("W/degC").unwrap();
+    assert!(per_deg_c.conversion_to(&registry.resolve("W/K").unwrap()).unwrap().is_identity());
+}
+
+#[rstest]
+#[case("m", "s")]
+#[case("rad", "1")]
+#[case("N", "J")]
+fn it_rejects_incompatible_units(#[case] from: &str, #[case] to: &str) {
+    let registry = UnitRegistry::new();
+    let from_unit = registry.resolve(from).unwrap();
+    let to_unit = registry.resolve(to).unwrap();
+
+    assert!(!from_unit.is_compatible(&to_unit));
+    assert!(from_unit.conversion_to(&to_unit).is_none());
+}
+
+#[rstest]
+#[case("furlong")]
+#[case("m/")]
+#[case("kg..m")]
+#[case("^2")]
+fn it_rejects_invalid_units(#[case] unit: &str) {
+    assert!(matches!(UnitRegistry::new().resolve(unit), Err(DtasmError::InvalidUnit(_))));
+}
+
+#[test]
+fn it_resolves_unit_definitions() {
+    let mut md = add_model_description("kn", "");
+    md.unit_definitions.push(MD::UnitDefinition {
+        name: "kn".to_string(),
+        base_unit: MD::BaseUnit { m: 1, s: -1, factor: 1852.0 / 3600.0, ..Default::default() }
+    });
+
+    let registry = UnitRegistry::with_model_description(&md);
+    let conversion = registry.resolve("kn").unwrap().conversion_to(&registry.resolve("km/h").unwrap()).unwrap();
+    assert!( approx_eq!(f64, conversion.apply(10.0), 18.52, ulps = 4) );
+    let n_diagnostics = validate(&md).len();
+
+    md.unit_definitions.clear();
+    let diagnostics = validate(&md);
+    assert_eq!(diagnostics.len(), n_diagnostics + 1);
+    assert!(diagnostics.iter().any(|d| d.severity == Severity::Warning && d.variable_id == Some(1)));
+}
+
+#[test]
+fn it_transfers_values_with_unit_conversion() {
+    let from_md = add_model_description("", "km/h");
+    let to_md = add_model_description("m/s", "");
+
+    let connections = vec![
+        Connection::new(&from_md, 3, &to_md, 1).expect("Could not connect real variables"),
+        Connection::new(&from_md, 6, &to_md, 4).expect("Could not connect int variables")
+    ];
+
+    let mut outputs = DtasmVarValues::new();
+    outputs.real_values.insert(3, 36.0);
+    outputs.int_values.insert(6, 42);
+
+    let inputs = transfer_values(&connections, &outputs);
+    assert!( approx_eq!(f64, inputs.real_values[&1], 10.0, ulps = 4) );
+    assert_eq!(inputs.int_values[&4], 42);
+    assert_eq!(inputs.real_values.len() + inputs.int_values.len(), 2);
+}
+
+#[rstest]
+#[case::incompatible_units(3, 1, "km/h", "kg")]
+#[case::unknown_unit(3, 1, "km/h", "furlong")]
+#[case::type_mismatch(3, 4, "", "")]
+#[case::output_to_output(3, 6, "", "")]
+#[case::input_to_input(1, 2, "", "")]
+#[case::unknown_variable(3, 42, "", "")]
+fn it_rejects_invalid_connections(#[case] from_id: i32, #[case] to_id: i32, #[case] from_unit: &str, #[case] to_unit: &str) {
+    let from_md = add_model_description("", from_unit);
+    let to_md = add_model_description(to_unit, "");
+
+    assert!(Connection::new(&from_md, from_id, &to_md, to_id).is_err());
+}
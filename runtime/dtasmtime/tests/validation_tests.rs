@@ -0,0 +1,243 @@
+mod common;
+use common::add_wasm_path;
+
+use std::io::Write;
+
+use dtasmtime::errors::DtasmtimeError;
+use dtasmtime::runtime::{Engine, Module, ValidationLevel};
+use dtasm_base::errors::DtasmError;
+use dtasm_base::model_conversion::model_description_to_bytes;
+use dtasm_base::model_description as MD;
+use dtasm_base::validation::{validate, validate_strict, has_errors, Severity};
+
+use rstest::rstest;
+
+
+fn variable(id: i32, name: &str, value_type: MD::VariableType, causality: MD::CausalityType) -> MD::ModelVariable {
+    MD::ModelVariable {
+        id,
+        name: name.to_string(),
+        value_type,
+        description: String::new(),
+        unit: String::new(),
+        causality,
+        derivative_of_id: -1,
+        default: Some(MD::VariableValue::default()),
+        dimensions: Vec::new(),
+        enumeration_type: String::new(),
+        min: None,
+        max: None,
+        nominal: None,
+        variability: MD::VariabilityType::Continuous
+    }
+}
+
+fn model_description() -> MD::ModelDescription {
+    MD::ModelDescription {
+        model: MD::ModelInfo {
+            name: "Test".to_string(),
+            id: String::new(),
+            description: String::new(),
+            generation_tool: String::new(),
+            generation_date_time: String::new(),
+            name_delimiter: ".".to_string(),
+            capabilities: MD::Capabilities::default()
+        },
+        variables: vec![
+            variable(1, "body.x", MD::VariableType::DtasmReal, MD::CausalityType::Local),
+            variable(2, "body.v", MD::VariableType::DtasmReal, MD::CausalityType::Local),
+            variable(3, "force", MD::VariableType::DtasmReal, MD::CausalityType::Input),
+            variable(4, "mode", MD::VariableType::DtasmInt, MD::CausalityType::Parameter),
+        ],
+        experiment: Some(MD::ExperimentInfo {
+            time_step_min: 0.001,
+            time_step_max: 0.1,
+            time_step_default: 0.01,
+            start_time_default: 0.0,
+            end_time_default: 10.0,
+            time_unit: "s".to_string()
+        }),
+        enumeration_types: Vec::new(),
+        unit_definitions: Vec::new()
+    }
+}
+
+/// Module returning the given model description from `getModelDescription`; 
+/// all other exports are stubs
+fn stub_module(md: &MD::ModelDescription) -> tempfile::TempPath {
+    let md_bytes = model_description_to_bytes(md);
+    let data: String = md_bytes.iter().map(|b| format!("\\{:02x}", b)).collect();
+    let wat = format!(r#"
+        (module
+            (memory (export "memory") 2)
+            (data (i32.const 32768) "{data}")
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "dealloc") (param i32))
+            (func (export "getModelDescription") (param $out i32) (param $max i32) (result i32)
+                (local $i i32)
+                (if (i32.le_u (i32.const {len}) (local.get $max))
+                    (then
+                        (block $done
+                            (loop $copy
+                                (br_if $done (i32.ge_u (local.get $i) (i32.const {len})))
+                                (i32.store8 (i32.add (local.get $out) (local.get $i))
+                                    (i32.load8_u (i32.add (i32.const 32768) (local.get $i))))
+                                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                                (br $copy)))))
+                (i32.const {len}))
+            (func (export "init") (param i32 i32 i32 i32) (result i32) (i32.const 0))
+            (func (export "getValues") (param i32 i32 i32 i32) (result i32) (i32.const 0))
+            (func (export "setValues") (param i32 i32 i32 i32) (result i32) (i32.const 0))
+            (func (export "doStep") (param i32 i32 i32 i32) (result i32) (i32.const 0)))
+        "#, data = data, len = md_bytes.len());
+
+    let wasm = wat::parse_str(&wat).expect("Invalid stub module");
+    let mut file = tempfile::NamedTempFile::new().expect("Could not create module file");
+    file.write_all(&wasm).expect("Could not write module file");
+
+    file.into_temp_path()
+}
+
+fn enumeration_item(name: &str, value: i32) -> MD::EnumerationItem {
+    MD::EnumerationItem { name: name.to_string(), value, description: String::new() }
+}
+
+fn model_description_with_enumeration() -> MD::ModelDescription {
+    let mut md = model_description();
+    md.enumeration_types.push(MD::EnumerationType {
+        name: "Mode".to_string(),
+        description: String::new(),
+        items: vec![enumeration_item("Off", 0), enumeration_item("Idle", 1), enumeration_item("Run", 2)]
+    });
+    md.variables[3].enumeration_type = "Mode".to_string();
+
+    md
+}
+
+#[test]
+fn it_accepts_valid_model_description() {
+    let mut md = model_description();
+    md.variables[1].derivative_of_id = 1;
+
+    assert!(validate(&md).is_empty());
+}
+
+#[rstest]
+#[case::duplicate_id(|md: &mut MD::ModelDescription| md.variables[1].id = 1)]
+#[case::duplicate_name(|md: &mut MD::ModelDescription| md.variables[1].name = "body.x".to_string())]
+#[case::empty_name_part(|md: &mut MD::ModelDescription| md.variables[0].name = "body..x".to_string())]
+#[case::trailing_delimiter(|md: &mut MD::ModelDescription| md.variables[0].name = "body.".to_string())]
+#[case::unknown_derivative(|md: &mut MD::ModelDescription| md.variables[1].derivative_of_id = 42)]
+#[case::non_real_derivative(|md: &mut MD::ModelDescription| md.variables[1].derivative_of_id = 4)]
+#[case::default_type(|md: &mut MD::ModelDescription| md.variables[3].default.as_mut().unwrap().real_val = 1.5)]
+#[case::default_type_int64(|md: &mut MD::ModelDescription| md.variables[0].default.as_mut().unwrap().int64_val = 1 << 40)]
+#[case::timestep_below_min(|md: &mut MD::ModelDescription| md.experiment.as_mut().unwrap().time_step_default = 0.0001)]
+#[case::timestep_above_max(|md: &mut MD::ModelDescription| md.experiment.as_mut().unwrap().time_step_default = 1.0)]
+#[case::non_positive_dimension(|md: &mut MD::ModelDescription| md.variables[2].dimensions = vec![3, 0])]
+#[case::binary_dimensions(|md: &mut MD::ModelDescription| { md.variables[3].value_type = MD::VariableType::DtasmBinary; md.variables[3].dimensions = vec![4] })]
+#[case::derivative_dimensions(|md: &mut MD::ModelDescription| { md.variables[1].derivative_of_id = 1; md.variables[1].dimensions = vec![3] })]
+#[case::min_above_max(|md: &mut MD::ModelDescription| {
+    md.variables[0].default = None;
+    md.variables[0].min = Some(MD::VariableValue { real_val: 1.0, ..Default::default() });
+    md.variables[0].max = Some(MD::VariableValue { real_val: 0.5, ..Default::default() }) })]
+#[case::default_out_of_bounds(|md: &mut MD::ModelDescription| md.variables[3].min = Some(MD::VariableValue { int_val: 1, ..Default::default() }))]
+#[case::bounds_type(|md: &mut MD::ModelDescription| md.variables[0].max = Some(MD::VariableValue { int_val: 1, ..Default::default() }))]
+#[case::duplicate_unit_definition(|md: &mut MD::ModelDescription| {
+    let unit_def = MD::UnitDefinition { name: "km/h".to_string(), base_unit: MD::BaseUnit { m: 1, s: -1, factor: 1.0 / 3.6, ..Default::default() } };
+    md.unit_definitions = vec![unit_def.clone(), unit_def] })]
+#[case::tunable_input(|md: &mut MD::ModelDescription| md.variables[2].variability = MD::VariabilityType::Tunable)]
+#[case::constant_without_default(|md: &mut MD::ModelDescription| {
+    md.variables[0].default = None;
+    md.variables[0].variability = MD::VariabilityType::Constant })]
+#[case::start_after_end(|md: &mut MD::ModelDescription| md.experiment.as_mut().unwrap().start_time_default = 20.0)]
+fn it_reports_errors(#[case] modify: fn(&mut MD::ModelDescription)) {
+    let mut md = model_description();
+    modify(&mut md);
+
+    let diagnostics = validate(&md);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert!(has_errors(&diagnostics));
+}
+
+#[test]
+fn it_accepts_enumeration_variable() {
+    assert!(validate(&model_description_with_enumeration()).is_empty());
+}
+
+#[rstest]
+#[case::undeclared_type(|md: &mut MD::ModelDescription| md.variables[3].enumeration_type = "Gear".to_string())]
+#[case::non_int_variable(|md: &mut MD::ModelDescription| md.variables[2].enumeration_type = "Mode".to_string())]
+#[case::default_not_an_item(|md: &mut MD::ModelDescription| md.variables[3].default.as_mut().unwrap().int_val = 7)]
+#[case::duplicate_item_name(|md: &mut MD::ModelDescription| md.enumeration_types[0].items[1].name = "Off".to_string())]
+#[case::duplicate_item_value(|md: &mut MD::ModelDescription| md.enumeration_types[0].items[1].value = 2)]
+#[case::no_items(|md: &mut MD::ModelDescription| { md.enumeration_types[0].items.clear(); md.variables[3].default = None })]
+fn it_reports_enumeration_errors(#[case] modify: fn(&mut MD::ModelDescription)) {
+    let mut md = model_description_with_enumeration();
+    modify(&mut md);
+
+    let diagnostics = validate(&md);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn it_warns_about_inputs_without_default() {
+    let mut md = model_description();
+    md.variables[2].default = None;
+
+    let diagnostics = validate(&md);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].variable_id, Some(3));
+    assert!(!has_errors(&diagnostics));
+}
+
+#[test]
+fn it_rejects_inputs_without_default_strictly() {
+    let mut md = model_description();
+    md.variables[2].default = None;
+
+    let diagnostics = validate_strict(&md);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].variable_id, Some(3));
+    assert!(has_errors(&diagnostics));
+}
+
+#[test]
+fn it_loads_add_module_strictly() {
+    let engine = Engine::with_validation(ValidationLevel::Strict).expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(add_wasm_path(), &engine).expect("Could not instantiate dtasm module");
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+
+    inst.get_model_description().expect("Get Model Description failed!");
+    assert!(!has_errors(inst.model_description_diagnostics()));
+}
+
+#[test]
+fn it_rejects_duplicate_ids_with_default_validation() {
+    let mut md = model_description();
+    md.variables[1].id = 1;
+    let module_path = stub_module(&md);
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(module_path.to_path_buf(), &engine).expect("Could not instantiate dtasm module");
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+
+    let res = inst.get_model_description();
+    assert!(matches!(res, Err(DtasmtimeError::DtasmError(DtasmError::InvalidModelDescription(_)))), "{:?}", res.err());
+}
+
+#[test]
+fn it_loads_stub_module_with_default_validation() {
+    let md = model_description();
+    let module_path = stub_module(&md);
+
+    let engine = Engine::new().expect("Could not instantiate dtasm engine");
+    let mut dtasm_module = Module::new(module_path.to_path_buf(), &engine).expect("Could not instantiate dtasm module");
+    let mut inst = dtasm_module.instantiate().expect("Instantiate failed!");
+
+    let md_loaded = inst.get_model_description().expect("Get Model Description failed!");
+    assert_eq!(md_loaded.variables.len(), md.variables.len());
+}
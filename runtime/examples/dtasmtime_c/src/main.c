@@ -10,6 +10,13 @@
 
 #include <dtasmtime-c-api.h>
 
+#define CHECK(call) \
+    if ((call) != DtasmtimeOk) \
+    { \
+        printf("Failed (code %d): %s\n", dtasmtime_last_error_code(), dtasmtime_last_error_message()); \
+        exit(1); \
+    }
+
 
 int main(int argc, char *argv[]) {
     double tmin = 0.0;
@@ -43,19 +50,23 @@ int main(int argc, char *argv[]) {
     double dt = (tmax-tmin)/n_steps;
     
     printf("Creating engine... ");
-    Engine *engine = dtasmtime_engine_new();
+    Engine *engine;
+    CHECK(dtasmtime_engine_new(&engine));
     printf("Ok.\n");
 
     printf("Creating Module... ");
-    Module *module = dtasmtime_module_new(dtasm_file, engine);
+    Module *module;
+    CHECK(dtasmtime_module_new(dtasm_file, engine, &module));
     printf("Ok.\n");
 
     printf("Instantiating Module... ");
-    Instance *inst = dtasmtime_module_instantiate(module);
+    Instance *inst;
+    CHECK(dtasmtime_module_instantiate(module, &inst));
     printf("Ok.\n");
 
     printf("Getting model description... \n");
-    DtasmModelDescription md = dtasmtime_modeldescription_get(inst);
+    DtasmModelDescription md;
+    CHECK(dtasmtime_modeldescription_get(inst, &md));
 
     DtasmModelInfo mi = md.model;
     printf(" ID: %s,\n Name: %s,\n Description: %s,\n GenTool: %s \n", mi.id, mi.name, mi.description, mi.generation_tool);
@@ -119,6 +130,49 @@ int main(int argc, char *argv[]) {
     initial_vals.string_ids = NULL;
     initial_vals.string_values = NULL;
 
+    initial_vals.n_real_arrays = 0;
+    initial_vals.real_array_ids = NULL;
+    initial_vals.real_array_lens = NULL;
+    initial_vals.real_array_values = NULL;
+
+    initial_vals.n_int_arrays = 0;
+    initial_vals.int_array_ids = NULL;
+    initial_vals.int_array_lens = NULL;
+    initial_vals.int_array_values = NULL;
+
+    initial_vals.n_bool_arrays = 0;
+    initial_vals.bool_array_ids = NULL;
+    initial_vals.bool_array_lens = NULL;
+    initial_vals.bool_array_values = NULL;
+
+    initial_vals.n_string_arrays = 0;
+    initial_vals.string_array_ids = NULL;
+    initial_vals.string_array_lens = NULL;
+    initial_vals.string_array_values = NULL;
+
+    initial_vals.n_binaries = 0;
+    initial_vals.binary_ids = NULL;
+    initial_vals.binary_lens = NULL;
+    initial_vals.binary_values = NULL;
+
+    initial_vals.n_int64s = 0;
+    initial_vals.int64_ids = NULL;
+    initial_vals.int64_values = NULL;
+
+    initial_vals.n_float32s = 0;
+    initial_vals.float32_ids = NULL;
+    initial_vals.float32_values = NULL;
+
+    initial_vals.n_int64_arrays = 0;
+    initial_vals.int64_array_ids = NULL;
+    initial_vals.int64_array_lens = NULL;
+    initial_vals.int64_array_values = NULL;
+
+    initial_vals.n_float32_arrays = 0;
+    initial_vals.float32_array_ids = NULL;
+    initial_vals.float32_array_lens = NULL;
+    initial_vals.float32_array_values = NULL;
+
     int i_var = 0;
     for (int i=0; i<var_count; i++)
     {
@@ -134,7 +188,8 @@ int main(int argc, char *argv[]) {
     }
 
     printf("Calling initialize...\n");
-    DtasmStatus st = dtasmtime_initialize(inst, initial_vals, tmin, true, tmax, false, 0.0, DtasmLogWarn, false);
+    DtasmStatus st;
+    CHECK(dtasmtime_initialize(inst, initial_vals, tmin, true, tmax, false, 0.0, DtasmLogWarn, false, &st));
     printf("Returned %d\n", st);
 
     free(initial_vals.real_ids);
@@ -152,7 +207,8 @@ int main(int argc, char *argv[]) {
     }
 
     printf("Calling get values...\n");
-    DtasmGetValuesResponse get_vals_res = dtasmtime_getvalues(inst, req_ids, output_state_count);
+    DtasmGetValuesResponse get_vals_res;
+    CHECK(dtasmtime_getvalues(inst, req_ids, output_state_count, &get_vals_res));
     printf("Received status: %d\n", get_vals_res.status);
     printf("Current time: %f\n", get_vals_res.current_time);
 
@@ -173,10 +229,11 @@ int main(int argc, char *argv[]) {
 
     for (int i=0; i<n_steps; ++i) {
         printf("Calling do_step...\n");
-        DtasmDoStepResponse dostep_res = dtasmtime_dostep(inst, t, dt);
+        DtasmDoStepResponse dostep_res;
+        CHECK(dtasmtime_dostep(inst, t, dt, &dostep_res));
         printf("Returned %d, updated time %f\n", dostep_res.status, dostep_res.updated_time);
 
-        get_vals_res = dtasmtime_getvalues(inst, req_ids, output_state_count);
+        CHECK(dtasmtime_getvalues(inst, req_ids, output_state_count, &get_vals_res));
         for (int i = 0; i < get_vals_res.values.n_reals; ++i) {
             printf("Value for var id %d: %f\n", get_vals_res.values.real_ids[i], get_vals_res.values.real_values[i]);
         }
@@ -0,0 +1,170 @@
+// Copyright 2021 Siemens AG
+// SPDX-License-Identifier: MIT
+
+// Round-trips real, int, bool and string values through the add_rs module:
+// sets the inputs, steps and checks the outputs as read by getvalues (add_rs
+// only reports outputs and ignores initial values).
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include <dtasmtime-c-api.h>
+
+#define ID_REAL_IN1 1
+#define ID_REAL_IN2 2
+#define ID_REAL_OUT 3
+#define ID_INT_IN1 4
+#define ID_INT_IN2 5
+#define ID_INT_OUT 6
+#define ID_BOOL_IN1 7
+#define ID_BOOL_IN2 8
+#define ID_BOOL_OUT 9
+#define ID_STRING_IN1 10
+#define ID_STRING_IN2 11
+#define ID_STRING_OUT 12
+
+static int failures = 0;
+
+#define CHECK(call) \
+    if ((call) != DtasmtimeOk) \
+    { \
+        printf("%s failed (code %d): %s\n", #call, dtasmtime_last_error_code(), dtasmtime_last_error_message()); \
+        exit(1); \
+    }
+
+#define EXPECT(cond) \
+    if (!(cond)) \
+    { \
+        printf("%s:%d: expectation failed: %s\n", __FILE__, __LINE__, #cond); \
+        failures++; \
+    }
+
+static int index_of(const int32_t *ids, int32_t n, int32_t id)
+{
+    for (int i = 0; i < n; i++)
+    {
+        if (ids[i] == id)
+            return i;
+    }
+
+    return -1;
+}
+
+static void set_inputs(DtasmVarValues *vals, int32_t *real_ids, double *reals, int32_t *int_ids, int32_t *ints, 
+    int32_t *bool_ids, bool *bools, int32_t *string_ids, char **strings)
+{
+    memset(vals, 0, sizeof(DtasmVarValues));
+
+    vals->n_reals = 2;
+    vals->real_ids = real_ids;
+    vals->real_values = reals;
+    vals->n_ints = 2;
+    vals->int_ids = int_ids;
+    vals->int_values = ints;
+    vals->n_bools = 2;
+    vals->bool_ids = bool_ids;
+    vals->bool_values = bools;
+    vals->n_strings = 2;
+    vals->string_ids = string_ids;
+    vals->string_values = strings;
+}
+
+static void expect_outputs(Instance *inst, double real_out, int32_t int_out, bool bool_out, const char *string_out)
+{
+    int32_t ids[] = { ID_REAL_OUT, ID_INT_OUT, ID_BOOL_OUT, ID_STRING_OUT };
+    DtasmGetValuesResponse res;
+    CHECK(dtasmtime_getvalues(inst, ids, 4, &res));
+
+    DtasmVarValues *vals = &res.values;
+    EXPECT(vals->n_reals == 1 && vals->n_ints == 1 && vals->n_bools == 1 && vals->n_strings == 1);
+
+    int i = index_of(vals->real_ids, vals->n_reals, ID_REAL_OUT);
+    EXPECT(i >= 0 && vals->real_values[i] == real_out);
+    i = index_of(vals->int_ids, vals->n_ints, ID_INT_OUT);
+    EXPECT(i >= 0 && vals->int_values[i] == int_out);
+    i = index_of(vals->bool_ids, vals->n_bools, ID_BOOL_OUT);
+    EXPECT(i >= 0 && vals->bool_values[i] == bool_out);
+    i = index_of(vals->string_ids, vals->n_strings, ID_STRING_OUT);
+    EXPECT(i >= 0 && strcmp(vals->string_values[i], string_out) == 0);
+
+    dtasmtime_getvalues_free(res);
+}
+
+int main(int argc, char *argv[])
+{
+    if (argc <= 1)
+    {
+        printf("Usage: add_roundtrip add_rs.wasm\n");
+        return 2;
+    }
+
+    Engine *engine;
+    CHECK(dtasmtime_engine_new(&engine));
+
+    Module *missing;
+    EXPECT(dtasmtime_module_new("does_not_exist.wasm", engine, &missing) != DtasmtimeOk);
+    EXPECT(dtasmtime_last_error_code() != DtasmtimeOk);
+    EXPECT(dtasmtime_last_error_message() != NULL);
+
+    Module *module;
+    CHECK(dtasmtime_module_new(argv[1], engine, &module));
+    Instance *inst;
+    CHECK(dtasmtime_module_instantiate(module, &inst));
+
+    int32_t real_ids[] = { ID_REAL_IN1, ID_REAL_IN2 };
+    int32_t int_ids[] = { ID_INT_IN1, ID_INT_IN2 };
+    int32_t bool_ids[] = { ID_BOOL_IN1, ID_BOOL_IN2 };
+    int32_t string_ids[] = { ID_STRING_IN1, ID_STRING_IN2 };
+
+    double reals[] = { 1.25, 2.5 };
+    int32_t ints[] = { 40, 2 };
+    bool bools[] = { true, false };
+    char *strings[] = { "round", "trip" };
+
+    DtasmVarValues vals;
+    DtasmStatus status;
+    memset(&vals, 0, sizeof(DtasmVarValues));
+    CHECK(dtasmtime_initialize(inst, vals, 0.0, true, 1.0, false, 0.0, DtasmLogWarn, false, &status));
+    EXPECT(status == DtasmOK);
+
+    set_inputs(&vals, real_ids, reals, int_ids, ints, bool_ids, bools, string_ids, strings);
+    CHECK(dtasmtime_setvalues(inst, vals, &status));
+    EXPECT(status == DtasmOK);
+
+    DtasmDoStepResponse step;
+    CHECK(dtasmtime_dostep(inst, 0.0, 0.5, &step));
+    EXPECT(step.status == DtasmOK && step.updated_time == 0.5);
+    expect_outputs(inst, 3.75, 42, false, "roundtrip");
+
+    double new_reals[] = { -1.0, 0.5 };
+    int32_t new_ints[] = { -3, -4 };
+    bool new_bools[] = { true, true };
+    char *new_strings[] = { "dt", "asm" };
+    set_inputs(&vals, real_ids, new_reals, int_ids, new_ints, bool_ids, new_bools, string_ids, new_strings);
+    CHECK(dtasmtime_setvalues(inst, vals, &status));
+    EXPECT(status == DtasmOK);
+
+    CHECK(dtasmtime_dostep(inst, 0.5, 0.5, &step));
+    expect_outputs(inst, -0.5, -7, true, "dtasm");
+
+    // errors are reported instead of aborting the host
+    int32_t input_id = ID_STRING_IN1;
+    DtasmGetValuesResponse res;
+    EXPECT(dtasmtime_getvalues(inst, &input_id, 1, &res) != DtasmtimeOk);
+    EXPECT(dtasmtime_last_error_message() != NULL);
+    EXPECT(dtasmtime_dostep(inst, 1.0, 0.5, NULL) == DtasmtimeInvalidArgument);
+
+    dtasmtime_instance_free(inst);
+    dtasmtime_module_free(module);
+    dtasmtime_engine_free(engine);
+
+    if (failures > 0)
+    {
+        printf("%d expectation(s) failed\n", failures);
+        return 1;
+    }
+
+    printf("Ok.\n");
+    return 0;
+}